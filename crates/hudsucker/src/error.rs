@@ -12,6 +12,8 @@ pub enum Error {
     Decode,
     #[error("builder error")]
     Builder(#[from] builder::Error),
+    #[error("connection aborted by handler")]
+    Aborted,
     #[error("unknown error")]
     Unknown,
 }
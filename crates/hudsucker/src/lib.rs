@@ -23,6 +23,7 @@ mod error;
 mod noop;
 mod proxy;
 mod rewind;
+mod throttle;
 
 pub mod certificate_authority;
 
@@ -48,6 +49,7 @@ pub use decoder::{decode_request, decode_response};
 pub use error::Error;
 pub use noop::*;
 pub use proxy::*;
+pub use throttle::Throttle;
 
 /// Enum representing either an HTTP request or response.
 #[derive(Debug)]
@@ -70,6 +72,59 @@ impl From<Response<Body>> for RequestOrResponse {
     }
 }
 
+/// Request extension that keeps the `Host` header set by an [`HttpHandler`].
+///
+/// By default the `Host` header is removed before a request is forwarded, so that it is derived
+/// from the request URI. Insert this marker into the request extensions to forward the `Host`
+/// header as is, e.g. when the upstream server relies on virtual hosting.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PreserveHost;
+
+/// Response extension that aborts the client connection instead of sending the response.
+///
+/// Insert this marker into a response returned from [`HttpHandler::handle_request`] to simulate a
+/// connection reset. HTTP/1 connections are closed without a response, HTTP/2 streams are reset.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct AbortConnection;
+
+/// Request extension that forwards a request over a Unix domain socket instead of TCP.
+///
+/// The request URI still provides the request target and the `Host` header, only the connection
+/// is made to the given socket path. Applies to both HTTP and WebSocket requests. Connections to
+/// Unix sockets are not pooled.
+#[cfg(unix)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnixSocket(pub std::path::PathBuf);
+
+/// Request extension that opens the upstream connection with a custom connector.
+///
+/// Applies to HTTP and WebSocket requests as well as pass-through CONNECT tunnels, and takes
+/// precedence over the dialer set with
+/// [`ProxyBuilder::with_upstream_dialer`](crate::builder::ProxyBuilder::with_upstream_dialer). The
+/// connector is called the same way, it only opens the connection and TLS is added by the proxy.
+/// Like connections to Unix sockets, these connections are not pooled.
+#[derive(Clone)]
+pub struct Dialer(pub(crate) proxy::dialer::UpstreamDialer);
+
+impl Dialer {
+    /// Create a dialer from a connector.
+    pub fn new<S>(connector: S) -> Self
+    where
+        S: tower_service::Service<Uri> + Clone + Send + Sync + 'static,
+        S::Response: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        S::Future: Send + 'static,
+    {
+        Self(proxy::dialer::UpstreamDialer::new(connector))
+    }
+}
+
+impl std::fmt::Debug for Dialer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dialer").finish_non_exhaustive()
+    }
+}
+
 /// Context for HTTP requests and responses.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 #[non_exhaustive]
@@ -114,6 +169,9 @@ pub trait HttpHandler: Clone + Send + Sync + 'static {
 
     /// This handler will be called for each HTTP response. It can modify a response before it is
     /// forwarded to the client.
+    ///
+    /// Requests sent over a [`UnixSocket`] or a [`Dialer`] that fail are answered with a 502 Bad
+    /// Gateway, which is passed to this handler as well.
     fn handle_response(
         &mut self,
         _ctx: &HttpContext,
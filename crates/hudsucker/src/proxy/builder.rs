@@ -1,6 +1,8 @@
+use super::dialer::UpstreamDialer;
 use crate::{
     HttpHandler, NoopHandler, Proxy, WebSocketHandler, certificate_authority::CertificateAuthority,
 };
+use hyper::Uri;
 use hyper_util::{
     client::legacy::{Builder as ClientBuilder, connect::Connect},
     rt::TokioExecutor,
@@ -15,6 +17,7 @@ use thiserror::Error;
 use tokio::net::TcpListener;
 use tokio_rustls::rustls::{ClientConfig, crypto::CryptoProvider};
 use tokio_tungstenite::Connector;
+use tower_service::Service;
 
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -136,12 +139,14 @@ impl<CA> ProxyBuilder<WantsClient<CA>> {
             Err(e) => {
                 return ProxyBuilder(WantsHandlers {
                     al: self.0.al,
+                    socks5: None,
                     ca: self.0.ca,
                     http_connector: Err(Error::from(e)),
                     client: None,
                     http_handler: NoopHandler::new(),
                     websocket_handler: NoopHandler::new(),
                     websocket_connector: None,
+                    upstream_dialer: None,
                     server: None,
                     graceful_shutdown: pending(),
                 });
@@ -160,12 +165,85 @@ impl<CA> ProxyBuilder<WantsClient<CA>> {
 
         ProxyBuilder(WantsHandlers {
             al: self.0.al,
+            socks5: None,
             ca: self.0.ca,
             http_connector: Ok(https),
             client: None,
             http_handler: NoopHandler::new(),
             websocket_handler: NoopHandler::new(),
             websocket_connector: Some(Connector::Rustls(Arc::new(rustls_config))),
+            upstream_dialer: None,
+            server: None,
+            graceful_shutdown: pending(),
+        })
+    }
+
+    /// Use a hyper-rustls connector that opens its TCP connections through `inner`.
+    ///
+    /// This is like [`with_rustls_connector`](Self::with_rustls_connector), but lets the caller
+    /// decide how the underlying connection is established, e.g. through an upstream proxy. The
+    /// same connector is also used for WebSocket connections and pass-through CONNECT tunnels, see
+    /// [`with_upstream_dialer`](ProxyBuilder::with_upstream_dialer).
+    #[cfg(feature = "rustls-client")]
+    pub fn with_rustls_connector_over<H>(
+        self,
+        provider: CryptoProvider,
+        inner: H,
+    ) -> ProxyBuilder<WantsHandlers<CA, impl Connect + Clone, NoopHandler, NoopHandler, Pending<()>>>
+    where
+        H: Service<Uri> + Clone + Send + Sync + 'static,
+        H::Response: hyper_util::client::legacy::connect::Connection
+            + hyper::rt::Read
+            + hyper::rt::Write
+            + Unpin
+            + Send
+            + 'static,
+        H::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        H::Future: Send + 'static,
+    {
+        use hyper_rustls::ConfigBuilderExt;
+
+        let rustls_config = match ClientConfig::builder_with_provider(Arc::new(provider))
+            .with_safe_default_protocol_versions()
+        {
+            Ok(config) => config.with_webpki_roots().with_no_client_auth(),
+            Err(e) => {
+                return ProxyBuilder(WantsHandlers {
+                    al: self.0.al,
+                    socks5: None,
+                    ca: self.0.ca,
+                    http_connector: Err(Error::from(e)),
+                    client: None,
+                    http_handler: NoopHandler::new(),
+                    websocket_handler: NoopHandler::new(),
+                    websocket_connector: None,
+                    upstream_dialer: None,
+                    server: None,
+                    graceful_shutdown: pending(),
+                });
+            }
+        };
+
+        let https = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config(rustls_config.clone())
+            .https_or_http()
+            .enable_http1();
+
+        #[cfg(feature = "http2")]
+        let https = https.enable_http2();
+
+        let https = https.wrap_connector(inner.clone());
+
+        ProxyBuilder(WantsHandlers {
+            al: self.0.al,
+            socks5: None,
+            ca: self.0.ca,
+            http_connector: Ok(https),
+            client: None,
+            http_handler: NoopHandler::new(),
+            websocket_handler: NoopHandler::new(),
+            websocket_connector: Some(Connector::Rustls(Arc::new(rustls_config))),
+            upstream_dialer: Some(UpstreamDialer::new(inner)),
             server: None,
             graceful_shutdown: pending(),
         })
@@ -184,12 +262,14 @@ impl<CA> ProxyBuilder<WantsClient<CA>> {
             Err(e) => {
                 return ProxyBuilder(WantsHandlers {
                     al: self.0.al,
+                    socks5: None,
                     ca: self.0.ca,
                     http_connector: Err(Error::from(e)),
                     client: None,
                     http_handler: NoopHandler::new(),
                     websocket_handler: NoopHandler::new(),
                     websocket_connector: None,
+                    upstream_dialer: None,
                     server: None,
                     graceful_shutdown: pending(),
                 });
@@ -201,12 +281,14 @@ impl<CA> ProxyBuilder<WantsClient<CA>> {
 
         ProxyBuilder(WantsHandlers {
             al: self.0.al,
+            socks5: None,
             ca: self.0.ca,
             http_connector: Ok(https),
             client: None,
             http_handler: NoopHandler::new(),
             websocket_handler: NoopHandler::new(),
             websocket_connector: Some(Connector::NativeTls(tls_connector)),
+            upstream_dialer: None,
             server: None,
             graceful_shutdown: pending(),
         })
@@ -222,12 +304,14 @@ impl<CA> ProxyBuilder<WantsClient<CA>> {
     {
         ProxyBuilder(WantsHandlers {
             al: self.0.al,
+            socks5: None,
             ca: self.0.ca,
             http_connector: Ok(connector),
             client: None,
             http_handler: NoopHandler::new(),
             websocket_handler: NoopHandler::new(),
             websocket_connector: None,
+            upstream_dialer: None,
             server: None,
             graceful_shutdown: pending(),
         })
@@ -237,12 +321,14 @@ impl<CA> ProxyBuilder<WantsClient<CA>> {
 /// Builder state that can take additional handlers.
 pub struct WantsHandlers<CA, C, H, W, F> {
     al: AddrOrListener,
+    socks5: Option<AddrOrListener>,
     ca: CA,
     http_connector: Result<C, Error>,
     client: Option<ClientBuilder>,
     http_handler: H,
     websocket_handler: W,
     websocket_connector: Option<Connector>,
+    upstream_dialer: Option<UpstreamDialer>,
     server: Option<ServerBuilder<TokioExecutor>>,
     graceful_shutdown: F,
 }
@@ -255,12 +341,14 @@ impl<CA, C, H, W, F> ProxyBuilder<WantsHandlers<CA, C, H, W, F>> {
     ) -> ProxyBuilder<WantsHandlers<CA, C, H2, W, F>> {
         ProxyBuilder(WantsHandlers {
             al: self.0.al,
+            socks5: self.0.socks5,
             ca: self.0.ca,
             http_connector: self.0.http_connector,
             client: self.0.client,
             http_handler,
             websocket_handler: self.0.websocket_handler,
             websocket_connector: self.0.websocket_connector,
+            upstream_dialer: self.0.upstream_dialer,
             server: self.0.server,
             graceful_shutdown: self.0.graceful_shutdown,
         })
@@ -273,12 +361,14 @@ impl<CA, C, H, W, F> ProxyBuilder<WantsHandlers<CA, C, H, W, F>> {
     ) -> ProxyBuilder<WantsHandlers<CA, C, H, W2, F>> {
         ProxyBuilder(WantsHandlers {
             al: self.0.al,
+            socks5: self.0.socks5,
             ca: self.0.ca,
             http_connector: self.0.http_connector,
             client: self.0.client,
             http_handler: self.0.http_handler,
             websocket_handler,
             websocket_connector: self.0.websocket_connector,
+            upstream_dialer: self.0.upstream_dialer,
             server: self.0.server,
             graceful_shutdown: self.0.graceful_shutdown,
         })
@@ -292,6 +382,49 @@ impl<CA, C, H, W, F> ProxyBuilder<WantsHandlers<CA, C, H, W, F>> {
         })
     }
 
+    /// Set a connector to use for WebSocket connections and pass-through CONNECT tunnels.
+    ///
+    /// By default, WebSocket connections and tunnels connect to the upstream server directly. With a
+    /// dialer, they are opened through `connector` instead, e.g. to route them via an upstream proxy.
+    /// The connector is called with an `http` or `https` URI and only opens the connection, TLS is
+    /// added by the proxy with the WebSocket connector. Tunnels are dialed with an `https` URI when
+    /// the client starts a TLS handshake or the target port is 443, so the connector can choose a
+    /// route by scheme.
+    pub fn with_upstream_dialer<D>(self, connector: D) -> Self
+    where
+        D: Service<Uri> + Clone + Send + Sync + 'static,
+        D::Response: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+        D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        D::Future: Send + 'static,
+    {
+        ProxyBuilder(WantsHandlers {
+            upstream_dialer: Some(UpstreamDialer::new(connector)),
+            ..self.0
+        })
+    }
+
+    /// Also accept SOCKS5 clients on the given address.
+    ///
+    /// SOCKS5 CONNECT requests, including ones for a domain name, are handled like HTTP CONNECT
+    /// requests: they are passed to the HTTP handler and then intercepted or tunneled. Only the
+    /// "no authentication" method is supported.
+    pub fn with_socks5_addr(self, addr: SocketAddr) -> Self {
+        ProxyBuilder(WantsHandlers {
+            socks5: Some(AddrOrListener::Addr(addr)),
+            ..self.0
+        })
+    }
+
+    /// Also accept SOCKS5 clients on the given listener.
+    ///
+    /// See [`with_socks5_addr`](Self::with_socks5_addr).
+    pub fn with_socks5_listener(self, listener: TcpListener) -> Self {
+        ProxyBuilder(WantsHandlers {
+            socks5: Some(AddrOrListener::Listener(listener)),
+            ..self.0
+        })
+    }
+
     /// Set a custom client builder to use for the proxy server.
     pub fn with_client(self, client: ClientBuilder) -> Self {
         ProxyBuilder(WantsHandlers {
@@ -315,12 +448,14 @@ impl<CA, C, H, W, F> ProxyBuilder<WantsHandlers<CA, C, H, W, F>> {
     ) -> ProxyBuilder<WantsHandlers<CA, C, H, W, F2>> {
         ProxyBuilder(WantsHandlers {
             al: self.0.al,
+            socks5: self.0.socks5,
             ca: self.0.ca,
             http_connector: self.0.http_connector,
             client: self.0.client,
             http_handler: self.0.http_handler,
             websocket_handler: self.0.websocket_handler,
             websocket_connector: self.0.websocket_connector,
+            upstream_dialer: self.0.upstream_dialer,
             server: self.0.server,
             graceful_shutdown,
         })
@@ -333,12 +468,14 @@ impl<CA, C, H, W, F> ProxyBuilder<WantsHandlers<CA, C, H, W, F>> {
     {
         Ok(Proxy {
             al: self.0.al,
+            socks5: self.0.socks5,
             ca: Arc::new(self.0.ca),
             http_connector: self.0.http_connector?,
             client: self.0.client,
             http_handler: self.0.http_handler,
             websocket_handler: self.0.websocket_handler,
             websocket_connector: self.0.websocket_connector,
+            upstream_dialer: self.0.upstream_dialer,
             server: self.0.server,
             graceful_shutdown: self.0.graceful_shutdown,
         })
@@ -0,0 +1,86 @@
+use futures::future::BoxFuture;
+use http::uri::Scheme;
+use hyper::Uri;
+use hyper_util::rt::TokioIo;
+use std::{error::Error as StdError, future::poll_fn, sync::Arc};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::Connector;
+use tower_service::Service;
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+
+pub(crate) trait UpstreamIo: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> UpstreamIo for T {}
+
+/// Type-erased connector used for WebSocket connections and CONNECT tunnels.
+#[derive(Clone)]
+pub(crate) struct UpstreamDialer(
+    Arc<dyn Fn(Uri) -> BoxFuture<'static, Result<Box<dyn UpstreamIo>, BoxError>> + Send + Sync>,
+);
+
+impl UpstreamDialer {
+    pub(crate) fn new<S>(connector: S) -> Self
+    where
+        S: Service<Uri> + Clone + Send + Sync + 'static,
+        S::Response: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+        S::Error: Into<BoxError>,
+        S::Future: Send + 'static,
+    {
+        Self(Arc::new(move |uri| {
+            let mut connector = connector.clone();
+            Box::pin(async move {
+                poll_fn(|cx| connector.poll_ready(cx))
+                    .await
+                    .map_err(Into::into)?;
+                let io = connector.call(uri).await.map_err(Into::into)?;
+                Ok(Box::new(TokioIo::new(io)) as Box<dyn UpstreamIo>)
+            })
+        }))
+    }
+
+    /// Adds TLS with `connector` to the connections this dialer opens for `https` URIs.
+    pub(crate) fn with_tls(self, connector: Option<Connector>) -> Self {
+        Self(Arc::new(move |uri: Uri| {
+            let inner = self.clone();
+            let connector = connector.clone();
+            Box::pin(async move {
+                let is_https = uri.scheme() == Some(&Scheme::HTTPS);
+                let host = uri
+                    .host()
+                    .unwrap_or_default()
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_owned();
+                let stream = inner.dial(uri).await?;
+                if !is_https {
+                    return Ok(stream);
+                }
+
+                match connector {
+                    #[cfg(feature = "rustls-client")]
+                    Some(Connector::Rustls(config)) => {
+                        let server_name =
+                            tokio_rustls::rustls::pki_types::ServerName::try_from(host)?;
+                        let stream = tokio_rustls::TlsConnector::from(config)
+                            .connect(server_name, stream)
+                            .await?;
+                        Ok(Box::new(stream) as Box<dyn UpstreamIo>)
+                    }
+                    #[cfg(feature = "native-tls-client")]
+                    Some(Connector::NativeTls(connector)) => {
+                        let stream = tokio_native_tls::TlsConnector::from(connector)
+                            .connect(&host, stream)
+                            .await?;
+                        Ok(Box::new(stream) as Box<dyn UpstreamIo>)
+                    }
+                    _ => Err(format!("no TLS connector available for {host}").into()),
+                }
+            })
+        }))
+    }
+
+    pub(crate) async fn dial(&self, uri: Uri) -> Result<Box<dyn UpstreamIo>, BoxError> {
+        (self.0)(uri).await
+    }
+}
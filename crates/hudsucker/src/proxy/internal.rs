@@ -1,6 +1,10 @@
+use super::{
+    dialer::UpstreamDialer,
+    socks5::{self, Reply},
+};
 use crate::{
-    HttpContext, HttpHandler, RequestOrResponse, WebSocketContext, WebSocketHandler, body::Body,
-    certificate_authority::CertificateAuthority, rewind::Rewind,
+    HttpContext, HttpHandler, PreserveHost, RequestOrResponse, WebSocketContext, WebSocketHandler,
+    body::Body, certificate_authority::CertificateAuthority, rewind::Rewind,
 };
 use futures::{Sink, Stream, StreamExt};
 use http::uri::{Authority, Scheme};
@@ -16,8 +20,12 @@ use hyper_util::{
     rt::{TokioExecutor, TokioIo},
     server::conn::auto::Builder as ServerBuilder,
 };
-use std::{convert::Infallible, net::SocketAddr, sync::Arc};
-use tokio::{io::AsyncReadExt, net::TcpStream, task::JoinHandle};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite},
+    net::TcpStream,
+    task::JoinHandle,
+};
 use tokio_rustls::TlsAcceptor;
 use tokio_tungstenite::{
     Connector, MaybeTlsStream, WebSocketStream,
@@ -25,6 +33,13 @@ use tokio_tungstenite::{
 };
 use tracing::{Instrument, Span, error, info_span, instrument, warn};
 
+fn throttle_response(res: Response<Body>, throttle: Option<crate::Throttle>) -> Response<Body> {
+    match throttle {
+        Some(throttle) => res.map(|body| throttle.download_body(body)),
+        None => res,
+    }
+}
+
 fn bad_request() -> Response<Body> {
     Response::builder()
         .status(StatusCode::BAD_REQUEST)
@@ -53,6 +68,7 @@ pub(crate) struct InternalProxy<C, CA, H, W> {
     pub http_handler: H,
     pub websocket_handler: W,
     pub websocket_connector: Option<Connector>,
+    pub upstream_dialer: Option<UpstreamDialer>,
     pub client_addr: SocketAddr,
 }
 
@@ -70,6 +86,7 @@ where
             http_handler: self.http_handler.clone(),
             websocket_handler: self.websocket_handler.clone(),
             websocket_connector: self.websocket_connector.clone(),
+            upstream_dialer: self.upstream_dialer.clone(),
             client_addr: self.client_addr,
         }
     }
@@ -100,7 +117,7 @@ where
     pub(crate) async fn proxy(
         mut self,
         req: Request<Incoming>,
-    ) -> Result<Response<Body>, Infallible> {
+    ) -> Result<Response<Body>, crate::Error> {
         let ctx = self.context();
 
         let req = match self
@@ -110,14 +127,80 @@ where
             .await
         {
             RequestOrResponse::Request(req) => req,
-            RequestOrResponse::Response(res) => return Ok(res),
+            RequestOrResponse::Response(res) => {
+                if res.extensions().get::<crate::AbortConnection>().is_some() {
+                    return Err(crate::Error::Aborted);
+                }
+                return Ok(res);
+            }
         };
+        let throttle = req.extensions().get::<crate::Throttle>().copied();
 
         if req.method() == Method::CONNECT {
             Ok(self.process_connect(req))
         } else if hyper_tungstenite::is_upgrade_request(&req) {
             Ok(self.upgrade_websocket(req).await)
         } else {
+            let req = match throttle {
+                Some(throttle) => {
+                    tokio::time::sleep(throttle.latency).await;
+                    req.map(|body| throttle.upload_body(body))
+                }
+                None => req,
+            };
+
+            #[cfg(unix)]
+            if let Some(crate::UnixSocket(path)) = req.extensions().get().cloned() {
+                let res = send_unix_request(&path, normalize_request(req))
+                    .instrument(info_span!("proxy_unix_request"))
+                    .await;
+
+                return Ok(match res {
+                    Ok(res) => throttle_response(
+                        self.http_handler
+                            .handle_response(&ctx, res.map(Body::from))
+                            .instrument(info_span!("handle_response"))
+                            .await,
+                        throttle,
+                    ),
+                    Err(e) => {
+                        error!("Failed to forward request to {}: {}", path.display(), e);
+                        self.http_handler
+                            .handle_response(&ctx, bad_gateway())
+                            .instrument(info_span!("handle_response"))
+                            .await
+                    }
+                });
+            }
+
+            if let Some(crate::Dialer(dialer)) = req.extensions().get().cloned() {
+                let uri = req.uri().clone();
+                let dialer = dialer.with_tls(self.websocket_connector.clone());
+                let res = async {
+                    let stream = dialer.dial(uri.clone()).await?;
+                    send_request(stream, normalize_request(req)).await
+                }
+                .instrument(info_span!("proxy_dialed_request"))
+                .await;
+
+                return Ok(match res {
+                    Ok(res) => throttle_response(
+                        self.http_handler
+                            .handle_response(&ctx, res.map(Body::from))
+                            .instrument(info_span!("handle_response"))
+                            .await,
+                        throttle,
+                    ),
+                    Err(e) => {
+                        error!("Failed to forward request to {}: {}", uri, e);
+                        self.http_handler
+                            .handle_response(&ctx, bad_gateway())
+                            .instrument(info_span!("handle_response"))
+                            .await
+                    }
+                });
+            }
+
             let res = self
                 .client
                 .request(normalize_request(req))
@@ -125,11 +208,13 @@ where
                 .await;
 
             match res {
-                Ok(res) => Ok(self
-                    .http_handler
-                    .handle_response(&ctx, res.map(Body::from))
-                    .instrument(info_span!("handle_response"))
-                    .await),
+                Ok(res) => Ok(throttle_response(
+                    self.http_handler
+                        .handle_response(&ctx, res.map(Body::from))
+                        .instrument(info_span!("handle_response"))
+                        .await,
+                    throttle,
+                )),
                 Err(err) => Ok(self
                     .http_handler
                     .handle_error(&ctx, err)
@@ -139,97 +224,14 @@ where
         }
     }
 
-    fn process_connect(mut self, mut req: Request<Body>) -> Response<Body> {
+    fn process_connect(self, mut req: Request<Body>) -> Response<Body> {
         match req.uri().authority().cloned() {
             Some(authority) => {
                 let span = info_span!("process_connect");
                 let fut = async move {
                     match hyper::upgrade::on(&mut req).await {
                         Ok(upgraded) => {
-                            let mut upgraded = TokioIo::new(upgraded);
-                            let mut buffer = [0; 4];
-                            let bytes_read = match upgraded.read(&mut buffer).await {
-                                Ok(bytes_read) => bytes_read,
-                                Err(e) => {
-                                    error!("Failed to read from upgraded connection: {}", e);
-                                    return;
-                                }
-                            };
-
-                            let mut upgraded = Rewind::new(
-                                upgraded,
-                                Bytes::copy_from_slice(buffer[..bytes_read].as_ref()),
-                            );
-
-                            if self
-                                .http_handler
-                                .should_intercept(&self.context(), &req)
-                                .await
-                            {
-                                if buffer == *b"GET " {
-                                    if let Err(e) = self
-                                        .serve_stream(
-                                            TokioIo::new(upgraded),
-                                            Scheme::HTTP,
-                                            authority,
-                                        )
-                                        .await
-                                    {
-                                        error!("WebSocket connect error: {}", e);
-                                    }
-
-                                    return;
-                                } else if buffer[..2] == *b"\x16\x03" {
-                                    let server_config = self
-                                        .ca
-                                        .gen_server_config(&authority)
-                                        .instrument(info_span!("gen_server_config"))
-                                        .await;
-
-                                    let stream = match TlsAcceptor::from(server_config)
-                                        .accept(upgraded)
-                                        .await
-                                    {
-                                        Ok(stream) => TokioIo::new(stream),
-                                        Err(e) => {
-                                            error!("Failed to establish TLS connection: {}", e);
-                                            return;
-                                        }
-                                    };
-
-                                    if let Err(e) =
-                                        self.serve_stream(stream, Scheme::HTTPS, authority).await
-                                    {
-                                        if !e
-                                            .to_string()
-                                            .starts_with("error shutting down connection")
-                                        {
-                                            error!("HTTPS connect error: {}", e);
-                                        }
-                                    }
-
-                                    return;
-                                } else {
-                                    warn!(
-                                        "Unknown protocol, read '{:02X?}' from upgraded connection",
-                                        &buffer[..bytes_read]
-                                    );
-                                }
-                            }
-
-                            let mut server = match TcpStream::connect(authority.as_ref()).await {
-                                Ok(server) => server,
-                                Err(e) => {
-                                    error!("Failed to connect to {}: {}", authority, e);
-                                    return;
-                                }
-                            };
-
-                            if let Err(e) =
-                                tokio::io::copy_bidirectional(&mut upgraded, &mut server).await
-                            {
-                                error!("Failed to tunnel to {}: {}", authority, e);
-                            }
+                            self.tunnel(req, authority, TokioIo::new(upgraded)).await;
                         }
                         Err(e) => error!("Upgrade error: {}", e),
                     };
@@ -242,9 +244,180 @@ where
         }
     }
 
+    /// Serves an established CONNECT tunnel (or SOCKS5 connection) to `authority`.
+    ///
+    /// The first bytes are sniffed to decide whether the tunnel can be intercepted as plain HTTP or
+    /// TLS. Otherwise the client is connected to the target as is.
+    async fn tunnel<I>(mut self, req: Request<Body>, authority: Authority, mut upgraded: I)
+    where
+        I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut buffer = [0; 4];
+        let bytes_read = match upgraded.read(&mut buffer).await {
+            Ok(bytes_read) => bytes_read,
+            Err(e) => {
+                error!("Failed to read from upgraded connection: {}", e);
+                return;
+            }
+        };
+
+        let upgraded = Rewind::new(
+            upgraded,
+            Bytes::copy_from_slice(buffer[..bytes_read].as_ref()),
+        );
+
+        if self
+            .http_handler
+            .should_intercept(&self.context(), &req)
+            .await
+        {
+            if buffer == *b"GET " {
+                if let Err(e) = self
+                    .serve_stream(TokioIo::new(upgraded), Scheme::HTTP, authority)
+                    .await
+                {
+                    error!("WebSocket connect error: {}", e);
+                }
+
+                return;
+            } else if buffer[..2] == *b"\x16\x03" {
+                let server_config = self
+                    .ca
+                    .gen_server_config(&authority)
+                    .instrument(info_span!("gen_server_config"))
+                    .await;
+
+                let stream = match TlsAcceptor::from(server_config).accept(upgraded).await {
+                    Ok(stream) => TokioIo::new(stream),
+                    Err(e) => {
+                        error!("Failed to establish TLS connection: {}", e);
+                        return;
+                    }
+                };
+
+                if let Err(e) = self.serve_stream(stream, Scheme::HTTPS, authority).await {
+                    if !e.to_string().starts_with("error shutting down connection") {
+                        error!("HTTPS connect error: {}", e);
+                    }
+                }
+
+                return;
+            } else {
+                warn!(
+                    "Unknown protocol, read '{:02X?}' from upgraded connection",
+                    &buffer[..bytes_read]
+                );
+            }
+        }
+
+        let throttle = req
+            .extensions()
+            .get::<crate::Throttle>()
+            .copied()
+            .unwrap_or_default();
+        tokio::time::sleep(throttle.latency).await;
+        let mut upgraded = throttle.stream(upgraded);
+
+        let dialer = match req.extensions().get::<crate::Dialer>() {
+            Some(crate::Dialer(dialer)) => Some(dialer.clone()),
+            None => self.upstream_dialer.clone(),
+        };
+        let res = match dialer {
+            Some(dialer) => {
+                // The tunnel is opaque and the dialer never adds TLS, the scheme only tells it
+                // what kind of traffic the tunnel carries.
+                let scheme = if buffer[..2] == *b"\x16\x03" || authority.port_u16() == Some(443) {
+                    Scheme::HTTPS
+                } else {
+                    Scheme::HTTP
+                };
+                let uri = Uri::builder()
+                    .scheme(scheme)
+                    .authority(authority.clone())
+                    .path_and_query("/")
+                    .build()
+                    .expect("Failed to build URI");
+                match dialer.dial(uri).await {
+                    Ok(mut server) => {
+                        tokio::io::copy_bidirectional(&mut upgraded, &mut server).await
+                    }
+                    Err(e) => {
+                        error!("Failed to connect to {}: {}", authority, e);
+                        return;
+                    }
+                }
+            }
+            None => match TcpStream::connect(authority.as_ref()).await {
+                Ok(mut server) => tokio::io::copy_bidirectional(&mut upgraded, &mut server).await,
+                Err(e) => {
+                    error!("Failed to connect to {}: {}", authority, e);
+                    return;
+                }
+            },
+        };
+
+        if let Err(e) = res {
+            error!("Failed to tunnel to {}: {}", authority, e);
+        }
+    }
+
+    /// Serves a SOCKS5 client.
+    ///
+    /// The requested target is turned into a CONNECT request, so it goes through the HTTP handler
+    /// and is intercepted or tunneled like an HTTP CONNECT request. A request answered by the
+    /// handler is refused.
+    #[instrument(skip_all, fields(client_addr = %self.client_addr))]
+    pub(crate) async fn proxy_socks5(mut self, mut stream: TcpStream) {
+        let authority = match socks5::accept(&mut stream).await {
+            Ok(authority) => authority,
+            Err(e) => {
+                error!("SOCKS5 handshake failed: {}", e);
+                return;
+            }
+        };
+
+        let req = Request::builder()
+            .method(Method::CONNECT)
+            .uri(authority.as_str())
+            .header(hyper::header::HOST, authority.as_str())
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let ctx = self.context();
+        let req = match self
+            .http_handler
+            .handle_request(&ctx, req)
+            .instrument(info_span!("handle_request"))
+            .await
+        {
+            RequestOrResponse::Request(req) => req,
+            RequestOrResponse::Response(_) => {
+                if let Err(e) = socks5::reply(&mut stream, Reply::NotAllowed).await {
+                    error!("Failed to send SOCKS5 reply: {}", e);
+                }
+                return;
+            }
+        };
+
+        let Some(authority) = req.uri().authority().cloned() else {
+            if let Err(e) = socks5::reply(&mut stream, Reply::GeneralFailure).await {
+                error!("Failed to send SOCKS5 reply: {}", e);
+            }
+            return;
+        };
+        if let Err(e) = socks5::reply(&mut stream, Reply::Succeeded).await {
+            error!("Failed to send SOCKS5 reply: {}", e);
+            return;
+        }
+
+        self.tunnel(req, authority, stream)
+            .instrument(info_span!("process_connect"))
+            .await;
+    }
+
     #[instrument(skip_all)]
     async fn upgrade_websocket(self, req: Request<Body>) -> Response<Body> {
-        let mut downstream_req = match websocket_request(req) {
+        let throttle = req.extensions().get::<crate::Throttle>().copied();
+        let downstream_req = match websocket_request(req) {
             Ok(req) => req,
             Err(()) => return bad_request(),
         };
@@ -261,6 +434,39 @@ where
         };
 
         let uri = upstream_req.uri().clone();
+
+        #[cfg(unix)]
+        if let Some(crate::UnixSocket(path)) = downstream_req.extensions().get().cloned() {
+            let upstream = match connect_unix_websocket(&path, upstream_req).await {
+                Ok(upstream) => upstream,
+                Err(e) => {
+                    error!(
+                        "Failed to connect to upstream WebSocket at {}: {}",
+                        path.display(),
+                        e
+                    );
+                    return bad_gateway();
+                }
+            };
+            return self.accept_websocket(downstream_req, uri, upstream, throttle);
+        }
+
+        let dialer = match downstream_req.extensions().get::<crate::Dialer>() {
+            Some(crate::Dialer(dialer)) => Some(dialer.clone()),
+            None => self.upstream_dialer.clone(),
+        };
+        if let Some(dialer) = dialer {
+            let dialer = dialer.with_tls(self.websocket_connector.clone());
+            let upstream = match connect_websocket_with_dialer(&dialer, upstream_req).await {
+                Ok(upstream) => upstream,
+                Err(e) => {
+                    error!("Failed to connect to upstream WebSocket: {}", e);
+                    return bad_gateway();
+                }
+            };
+            return self.accept_websocket(downstream_req, uri, upstream, throttle);
+        }
+
         let upstream = match self.connect_websocket(upstream_req).await {
             Ok(upstream) => upstream,
             Err(e) => {
@@ -269,6 +475,19 @@ where
             }
         };
 
+        self.accept_websocket(downstream_req, uri, upstream, throttle)
+    }
+
+    fn accept_websocket<S>(
+        self,
+        mut downstream_req: Request<()>,
+        uri: Uri,
+        upstream: (WebSocketStream<S>, WebSocketResponse),
+        throttle: Option<crate::Throttle>,
+    ) -> Response<Body>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         match hyper_tungstenite::upgrade(&mut downstream_req, None) {
             Ok((mut res, websocket)) => {
                 sync_websocket_subprotocol(res.headers_mut(), &upstream.1);
@@ -276,7 +495,9 @@ where
                 let fut = async move {
                     match websocket.await {
                         Ok(ws) => {
-                            if let Err(e) = self.handle_websocket(ws, uri, upstream.0).await {
+                            if let Err(e) =
+                                self.handle_websocket(ws, uri, upstream.0, throttle).await
+                            {
                                 error!("Failed to handle WebSocket: {}", e);
                             }
                         }
@@ -322,14 +543,29 @@ where
     }
 
     #[instrument(skip_all)]
-    async fn handle_websocket(
+    async fn handle_websocket<S>(
         self,
         client_socket: WebSocketStream<TokioIo<Upgraded>>,
         uri: Uri,
-        server_socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
-    ) -> Result<(), tungstenite::Error> {
+        server_socket: WebSocketStream<S>,
+        throttle: Option<crate::Throttle>,
+    ) -> Result<(), tungstenite::Error>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         let (server_sink, server_stream) = server_socket.split();
         let (client_sink, client_stream) = client_socket.split();
+        let (server_stream, client_stream) = match throttle {
+            Some(throttle) => (
+                throttle
+                    .messages(server_stream, throttle.download)
+                    .left_stream(),
+                throttle
+                    .messages(client_stream, throttle.upload)
+                    .left_stream(),
+            ),
+            None => (server_stream.right_stream(), client_stream.right_stream()),
+        };
 
         let InternalProxy {
             websocket_handler, ..
@@ -403,6 +639,75 @@ fn spawn_message_forwarder(
     spawn_with_trace(fut, span);
 }
 
+/// Sends a request over a fresh HTTP/1 connection to a Unix domain socket.
+#[cfg(unix)]
+async fn send_unix_request(
+    path: &std::path::Path,
+    req: Request<Body>,
+) -> Result<Response<Incoming>, Box<dyn std::error::Error + Send + Sync>> {
+    let stream = tokio::net::UnixStream::connect(path).await?;
+    send_request(stream, req).await
+}
+
+/// Sends a request over a fresh HTTP/1 connection on `stream`.
+async fn send_request<I>(
+    stream: I,
+    mut req: Request<Body>,
+) -> Result<Response<Incoming>, Box<dyn std::error::Error + Send + Sync>>
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    // Unlike the pooled client, a bare connection neither adds a Host header nor converts the
+    // request target to origin-form.
+    if !req.headers().contains_key(hyper::header::HOST)
+        && let Some(authority) = req.uri().authority()
+    {
+        let host = authority.as_str().parse()?;
+        req.headers_mut().insert(hyper::header::HOST, host);
+    }
+    let path_and_query = req.uri().path_and_query().map_or("/", |pq| pq.as_str());
+    *req.uri_mut() = path_and_query.parse()?;
+
+    let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+    tokio::spawn(async move {
+        if let Err(e) = conn.await {
+            error!("Upstream connection error: {}", e);
+        }
+    });
+
+    Ok(sender.send_request(req).await?)
+}
+
+#[cfg(unix)]
+async fn connect_unix_websocket(
+    path: &std::path::Path,
+    req: Request<()>,
+) -> Result<(WebSocketStream<tokio::net::UnixStream>, WebSocketResponse), tungstenite::Error> {
+    let stream = tokio::net::UnixStream::connect(path).await?;
+    tokio_tungstenite::client_async(req, stream).await
+}
+
+/// Opens the upstream connection with the dialer, which expects an `http`/`https` URI.
+async fn connect_websocket_with_dialer(
+    dialer: &UpstreamDialer,
+    req: Request<()>,
+) -> Result<
+    (
+        WebSocketStream<Box<dyn super::dialer::UpstreamIo>>,
+        WebSocketResponse,
+    ),
+    Box<dyn std::error::Error + Send + Sync>,
+> {
+    let mut parts = req.uri().clone().into_parts();
+    parts.scheme = Some(if req.uri().scheme_str() == Some("wss") {
+        Scheme::HTTPS
+    } else {
+        Scheme::HTTP
+    });
+    let stream = dialer.dial(Uri::from_parts(parts)?).await?;
+    Ok(tokio_tungstenite::client_async(req, stream).await?)
+}
+
 fn websocket_request(req: Request<Body>) -> Result<Request<()>, ()> {
     let (mut parts, _) = req.into_parts();
 
@@ -431,7 +736,9 @@ fn sync_websocket_subprotocol<B>(headers: &mut hyper::HeaderMap, upstream: &Resp
 #[instrument(skip_all)]
 fn normalize_request<T>(mut req: Request<T>) -> Request<T> {
     // Hyper will automatically add a Host header if needed.
-    req.headers_mut().remove(hyper::header::HOST);
+    if req.extensions().get::<PreserveHost>().is_none() {
+        req.headers_mut().remove(hyper::header::HOST);
+    }
 
     // HTTP/2 supports multiple cookie headers, but HTTP/1.x only supports one.
     if let Entry::Occupied(mut cookies) = req.headers_mut().entry(hyper::header::COOKIE) {
@@ -465,6 +772,7 @@ mod tests {
             http_handler: crate::NoopHandler::new(),
             websocket_handler: crate::NoopHandler::new(),
             websocket_connector: None,
+            upstream_dialer: None,
             client_addr: "127.0.0.1:8080".parse().unwrap(),
         }
     }
@@ -495,6 +803,23 @@ mod tests {
             assert_eq!(req.headers().get(hyper::header::HOST), None);
         }
 
+        #[test]
+        fn keeps_host_header_when_preserved() {
+            let mut req = Request::builder()
+                .uri("http://127.0.0.1:8080/")
+                .header(hyper::header::HOST, "example.com")
+                .body(())
+                .unwrap();
+            req.extensions_mut().insert(PreserveHost);
+
+            let req = normalize_request(req);
+
+            assert_eq!(
+                req.headers().get(hyper::header::HOST),
+                Some(&"example.com".parse().unwrap())
+            );
+        }
+
         #[test]
         fn joins_cookies() {
             let req = Request::builder()
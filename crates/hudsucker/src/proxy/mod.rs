@@ -1,4 +1,6 @@
+pub(crate) mod dialer;
 mod internal;
+mod socks5;
 
 pub mod builder;
 
@@ -7,6 +9,7 @@ use crate::{
     certificate_authority::CertificateAuthority,
 };
 use builder::{AddrOrListener, WantsAddr};
+use dialer::UpstreamDialer;
 use hyper::service::service_fn;
 use hyper_util::{
     client::legacy::{Builder as ClientBuilder, Client, connect::Connect},
@@ -69,12 +72,14 @@ use tracing::error;
 /// ```
 pub struct Proxy<C, CA, H, W, F> {
     al: AddrOrListener,
+    socks5: Option<AddrOrListener>,
     ca: Arc<CA>,
     http_connector: C,
     client: Option<ClientBuilder>,
     http_handler: H,
     websocket_handler: W,
     websocket_connector: Option<Connector>,
+    upstream_dialer: Option<UpstreamDialer>,
     server: Option<ServerBuilder<TokioExecutor>>,
     graceful_shutdown: F,
 }
@@ -124,6 +129,11 @@ where
             AddrOrListener::Addr(addr) => TcpListener::bind(addr).await?,
             AddrOrListener::Listener(listener) => listener,
         };
+        let socks5_listener = match self.socks5 {
+            Some(AddrOrListener::Addr(addr)) => Some(TcpListener::bind(addr).await?),
+            Some(AddrOrListener::Listener(listener)) => Some(listener),
+            None => None,
+        };
 
         let shutdown = Shutdown::new(self.graceful_shutdown);
         let guard = shutdown.guard_weak();
@@ -145,6 +155,7 @@ where
                     let http_handler = self.http_handler.clone();
                     let websocket_handler = self.websocket_handler.clone();
                     let websocket_connector = self.websocket_connector.clone();
+                    let upstream_dialer = self.upstream_dialer.clone();
 
                     shutdown.spawn_task_fn(move |guard| async move {
                         let conn = server.serve_connection_with_upgrades(
@@ -157,6 +168,7 @@ where
                                     http_handler: http_handler.clone(),
                                     websocket_handler: websocket_handler.clone(),
                                     websocket_connector: websocket_connector.clone(),
+                                    upstream_dialer: upstream_dialer.clone(),
                                     client_addr,
                                 }
                                 .proxy(req)
@@ -176,6 +188,33 @@ where
                         }
                     });
                 }
+                res = accept_socks5(socks5_listener.as_ref()) => {
+                    let (tcp, client_addr) = match res {
+                        Ok((tcp, client_addr)) => (tcp, client_addr),
+                        Err(e) => {
+                            error!("Failed to accept incoming SOCKS5 connection: {}", e);
+                            continue;
+                        }
+                    };
+
+                    let proxy = InternalProxy {
+                        ca: Arc::clone(&self.ca),
+                        client: client.clone(),
+                        server: server.clone(),
+                        http_handler: self.http_handler.clone(),
+                        websocket_handler: self.websocket_handler.clone(),
+                        websocket_connector: self.websocket_connector.clone(),
+                        upstream_dialer: self.upstream_dialer.clone(),
+                        client_addr,
+                    };
+
+                    shutdown.spawn_task_fn(move |guard| async move {
+                        tokio::select! {
+                            () = proxy.proxy_socks5(tcp) => {}
+                            () = guard.cancelled() => {}
+                        }
+                    });
+                }
                 _ = guard.cancelled() => {
                     break;
                 }
@@ -187,3 +226,13 @@ where
         Ok(())
     }
 }
+
+/// Accepts a connection on the SOCKS5 listener, or never completes without one.
+async fn accept_socks5(
+    listener: Option<&TcpListener>,
+) -> std::io::Result<(tokio::net::TcpStream, std::net::SocketAddr)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,121 @@
+//! Server side of the SOCKS5 handshake ([RFC 1928](https://www.rfc-editor.org/rfc/rfc1928)).
+//!
+//! Only the CONNECT command without authentication is supported. Domain names are passed on
+//! as is, so they are resolved by the proxy.
+
+use http::uri::Authority;
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const VERSION: u8 = 0x05;
+const NO_AUTH: u8 = 0x00;
+const NO_ACCEPTABLE_METHODS: u8 = 0xff;
+const CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// Reply codes sent to the client after its request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum Reply {
+    Succeeded = 0x00,
+    GeneralFailure = 0x01,
+    NotAllowed = 0x02,
+    CommandNotSupported = 0x07,
+    AddressTypeNotSupported = 0x08,
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Negotiates the authentication method and reads the CONNECT request.
+///
+/// Returns the requested target. Unsupported requests are answered with an error reply before an
+/// error is returned.
+pub(crate) async fn accept<S>(stream: &mut S) -> io::Result<Authority>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).await?;
+    if head[0] != VERSION {
+        return Err(invalid_data(format!(
+            "unsupported SOCKS version {}",
+            head[0]
+        )));
+    }
+    let mut methods = vec![0u8; usize::from(head[1])];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&NO_AUTH) {
+        stream.write_all(&[VERSION, NO_ACCEPTABLE_METHODS]).await?;
+        return Err(invalid_data(
+            "client does not support SOCKS5 without authentication".into(),
+        ));
+    }
+    stream.write_all(&[VERSION, NO_AUTH]).await?;
+
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    let [version, command, _, atyp] = request;
+    if version != VERSION {
+        return Err(invalid_data(format!("unsupported SOCKS version {version}")));
+    }
+
+    let host = match atyp {
+        ATYP_IPV4 => {
+            let mut octets = [0u8; 4];
+            stream.read_exact(&mut octets).await?;
+            Ipv4Addr::from(octets).to_string()
+        }
+        ATYP_IPV6 => {
+            let mut octets = [0u8; 16];
+            stream.read_exact(&mut octets).await?;
+            format!("[{}]", Ipv6Addr::from(octets))
+        }
+        ATYP_DOMAIN => {
+            let len = stream.read_u8().await?;
+            let mut domain = vec![0u8; usize::from(len)];
+            stream.read_exact(&mut domain).await?;
+            String::from_utf8_lossy(&domain).into_owned()
+        }
+        _ => {
+            reply(stream, Reply::AddressTypeNotSupported).await?;
+            return Err(invalid_data(format!(
+                "unsupported SOCKS5 address type {atyp}"
+            )));
+        }
+    };
+    let port = stream.read_u16().await?;
+
+    if command != CONNECT {
+        reply(stream, Reply::CommandNotSupported).await?;
+        return Err(invalid_data(format!(
+            "unsupported SOCKS5 command {command}"
+        )));
+    }
+
+    match Authority::try_from(format!("{host}:{port}")) {
+        Ok(authority) => Ok(authority),
+        Err(e) => {
+            reply(stream, Reply::GeneralFailure).await?;
+            Err(invalid_data(format!(
+                "invalid SOCKS5 target {host}:{port}: {e}"
+            )))
+        }
+    }
+}
+
+/// Sends a reply with an unspecified bound address.
+pub(crate) async fn reply<S>(stream: &mut S, reply: Reply) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream
+        .write_all(&[VERSION, reply as u8, 0x00, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+        .await
+}
@@ -0,0 +1,218 @@
+use crate::{Body, Error};
+use futures::{Stream, StreamExt, stream};
+use http_body_util::BodyDataStream;
+use hyper::body::{Body as HttpBody, Bytes};
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{Instant, Sleep, sleep, sleep_until},
+};
+use tokio_tungstenite::tungstenite::{self, Message};
+
+/// Request extension that limits the bandwidth of proxied traffic and adds latency.
+///
+/// Insert this into a request returned from [`HttpHandler::handle_request`](crate::HttpHandler)
+/// to simulate a slow network:
+///
+/// - HTTP requests are sent upstream after `latency`, the request body is limited to `upload` and
+///   the response body to `download`.
+/// - CONNECT tunnels that are not intercepted are opened after `latency`, bytes from the client are
+///   limited to `upload` and bytes to the client to `download`.
+/// - WebSocket messages are each delayed by `latency` plus the time their payload takes at the
+///   rate of their direction.
+///
+/// Rates are in bytes per second, `None` means unlimited.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Throttle {
+    /// Rate of data sent to the client.
+    pub download: Option<u64>,
+    /// Rate of data sent by the client.
+    pub upload: Option<u64>,
+    /// Delay added before the traffic is forwarded.
+    pub latency: Duration,
+}
+
+impl Throttle {
+    /// Limits a body to the download rate.
+    pub fn download_body(&self, body: Body) -> Body {
+        limit_body(body, self.download)
+    }
+
+    /// Limits a body to the upload rate.
+    pub fn upload_body(&self, body: Body) -> Body {
+        limit_body(body, self.upload)
+    }
+
+    /// Wraps the client side of a tunnel, limiting reads to the upload rate and writes to the
+    /// download rate.
+    pub(crate) fn stream<T>(&self, inner: T) -> Throttled<T> {
+        Throttled {
+            inner,
+            read: rate(self.upload).map(Pacer::new),
+            write: rate(self.download).map(Pacer::new),
+        }
+    }
+
+    /// Delays each message of a WebSocket stream.
+    pub(crate) fn messages<S>(
+        &self,
+        stream: S,
+        rate: Option<u64>,
+    ) -> impl Stream<Item = Result<Message, tungstenite::Error>> + Unpin + Send + 'static
+    where
+        S: Stream<Item = Result<Message, tungstenite::Error>> + Unpin + Send + 'static,
+    {
+        let latency = self.latency;
+        let rate = self::rate(rate);
+        Box::pin(stream.then(move |message| async move {
+            let size = message.as_ref().map_or(0, Message::len);
+            sleep(latency + rate.map_or(Duration::ZERO, |rate| transfer_time(size, rate))).await;
+            message
+        }))
+    }
+}
+
+fn rate(rate: Option<u64>) -> Option<u64> {
+    rate.filter(|rate| *rate > 0)
+}
+
+fn transfer_time(len: usize, rate: u64) -> Duration {
+    Duration::from_secs_f64(len as f64 / rate as f64)
+}
+
+/// Largest amount of data sent at once, about 100ms worth at the given rate.
+fn slice_len(rate: u64) -> usize {
+    usize::try_from(rate / 10).unwrap_or(usize::MAX).max(1)
+}
+
+fn limit_body(body: Body, rate: Option<u64>) -> Body {
+    let Some(rate) = self::rate(rate) else {
+        return body;
+    };
+    if body.is_end_stream() {
+        return body;
+    }
+
+    let slice = slice_len(rate);
+    let chunks = BodyDataStream::new(body).flat_map(move |chunk| {
+        let slices: Vec<Result<Bytes, Error>> = match chunk {
+            Ok(mut chunk) => {
+                let mut slices = Vec::with_capacity(chunk.len() / slice + 1);
+                while chunk.len() > slice {
+                    slices.push(Ok(chunk.split_to(slice)));
+                }
+                slices.push(Ok(chunk));
+                slices
+            }
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(slices)
+    });
+
+    Body::from_stream(chunks.then(move |chunk| async move {
+        if let Ok(chunk) = &chunk {
+            sleep(transfer_time(chunk.len(), rate)).await;
+        }
+        chunk
+    }))
+}
+
+/// Spaces out transfers so that they do not exceed a rate.
+#[derive(Debug)]
+struct Pacer {
+    rate: u64,
+    due: Instant,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl Pacer {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            due: Instant::now(),
+            delay: None,
+        }
+    }
+
+    /// Waits until the previous transfers have passed at the configured rate.
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.due > Instant::now() {
+            let due = self.due;
+            let delay = self.delay.get_or_insert_with(|| Box::pin(sleep_until(due)));
+            ready!(delay.as_mut().poll(cx));
+        }
+        self.delay = None;
+        Poll::Ready(())
+    }
+
+    fn consume(&mut self, len: usize) {
+        // Idle time does not add up to a later burst.
+        self.due = self.due.max(Instant::now()) + transfer_time(len, self.rate);
+    }
+}
+
+/// IO wrapper that limits reads and writes to a rate.
+#[derive(Debug)]
+pub(crate) struct Throttled<T> {
+    inner: T,
+    read: Option<Pacer>,
+    write: Option<Pacer>,
+}
+
+impl<T> AsyncRead for Throttled<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let Some(pacer) = &mut this.read else {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        };
+
+        ready!(pacer.poll_ready(cx));
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        pacer.consume(buf.filled().len() - filled);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncWrite for Throttled<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let Some(pacer) = &mut this.write else {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        };
+
+        ready!(pacer.poll_ready(cx));
+        let len = buf.len().min(slice_len(pacer.rate));
+        let written = ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..len]))?;
+        pacer.consume(written);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -1,2 +1,3 @@
 pub(crate) mod gen_ca;
+pub(crate) mod pac;
 pub(crate) mod start_proxy;
@@ -38,13 +38,11 @@ pub(crate) async fn gen_ca(args: &GenCaArgs) -> Result<()> {
     // 确定输出路径
     let cert_path = args
         .ca_cert
-        .as_ref()
-        .map(|p| p.clone())
+        .clone()
         .unwrap_or_else(|| default_cert_path().unwrap());
     let key_path = args
         .ca_key
-        .as_ref()
-        .map(|p| p.clone())
+        .clone()
         .unwrap_or_else(|| default_private_key_path().unwrap());
 
     // 如果使用默认路径且文件已存在，确认覆盖
@@ -102,7 +100,7 @@ mod tests {
     fn test_confirm_overwrite_yes() {
         let temp_dir = tempdir().unwrap();
         let existing_file = temp_dir.path().join("existing.txt");
-        std::fs::write(&existing_file, "test").unwrap();
+        fs::write(&existing_file, "test").unwrap();
 
         let mut input = Cursor::new(b"y\n");
         let result = confirm_overwrite(&mut input, &existing_file);
@@ -113,7 +111,7 @@ mod tests {
     fn test_confirm_overwrite_yes_uppercase() {
         let temp_dir = tempdir().unwrap();
         let existing_file = temp_dir.path().join("existing.txt");
-        std::fs::write(&existing_file, "test").unwrap();
+        fs::write(&existing_file, "test").unwrap();
 
         let mut input = Cursor::new(b"YES\n");
         let result = confirm_overwrite(&mut input, &existing_file);
@@ -124,7 +122,7 @@ mod tests {
     fn test_confirm_overwrite_no() {
         let temp_dir = tempdir().unwrap();
         let existing_file = temp_dir.path().join("existing.txt");
-        std::fs::write(&existing_file, "test").unwrap();
+        fs::write(&existing_file, "test").unwrap();
 
         let mut input = Cursor::new(b"n\n");
         let result = confirm_overwrite(&mut input, &existing_file);
@@ -135,7 +133,7 @@ mod tests {
     fn test_confirm_overwrite_default_no() {
         let temp_dir = tempdir().unwrap();
         let existing_file = temp_dir.path().join("existing.txt");
-        std::fs::write(&existing_file, "test").unwrap();
+        fs::write(&existing_file, "test").unwrap();
 
         let mut input = Cursor::new(b"something else\n");
         let result = confirm_overwrite(&mut input, &existing_file);
@@ -171,8 +169,8 @@ mod tests {
         assert!(key_path.exists(), "Private key file should exist");
 
         // 验证文件内容
-        let cert_content = std::fs::read_to_string(&cert_path).unwrap();
-        let key_content = std::fs::read_to_string(&key_path).unwrap();
+        let cert_content = fs::read_to_string(&cert_path).unwrap();
+        let key_content = fs::read_to_string(&key_path).unwrap();
 
         // 检查是否是 PEM 格式
         assert!(cert_content.contains("-----BEGIN CERTIFICATE-----"));
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use proxy_fork_core::Pac;
+use tracing::warn;
+
+use crate::commands::start_proxy::build_proxy_manager;
+use crate::config::AppConfig;
+
+/// 按配置中的规则生成 PAC 脚本；`proxy_addr` 未指定时使用监听地址
+async fn pac_script(cfg: &AppConfig, proxy_addr: Option<&str>) -> Result<String> {
+    let (manager, invalid) = build_proxy_manager(&cfg.proxy_manager).await?;
+    for r in invalid {
+        warn!("invalid rule in config, skipped: {:?}", r);
+    }
+    let listen = format!("{}:{}", cfg.listen.host, cfg.listen.port);
+    let proxy_addr = proxy_addr.unwrap_or(&listen);
+    let pac = Pac::new(proxy_addr)
+        .hosts(&cfg.pac_hosts)
+        .map_err(|e| anyhow::anyhow!("invalid pac host: {}", e))?;
+    Ok(pac.script(&manager, proxy_addr))
+}
+
+pub(crate) async fn pac(cfg: &AppConfig, proxy_addr: Option<&str>) -> Result<()> {
+    print!("{}", pac_script(cfg, proxy_addr).await?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pac_script;
+    use crate::config::{AppConfigBuilder, ListenAddrBuilder, ProxyManagerRuntimeBuilder};
+
+    #[tokio::test]
+    async fn pac_script_uses_rules_and_listen_addr() {
+        let cfg = AppConfigBuilder::default()
+            .listen(ListenAddrBuilder::default().port(8899).build().unwrap())
+            .proxy_manager(
+                ProxyManagerRuntimeBuilder::default()
+                    .rules(vec![
+                        "https://api.example.com -> http://localhost:8080"
+                            .parse()
+                            .unwrap(),
+                    ])
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let script = pac_script(&cfg, None).await.unwrap();
+        assert!(script.contains(r#"host == "api.example.com""#));
+        assert!(script.contains("PROXY 127.0.0.1:8899"));
+
+        let script = pac_script(&cfg, Some("192.168.1.10:8899")).await.unwrap();
+        assert!(script.contains("PROXY 192.168.1.10:8899"));
+
+        let mut cfg = cfg;
+        cfg.pac_hosts = vec!["re:(".into()];
+        assert!(pac_script(&cfg, None).await.is_err());
+    }
+}
@@ -1,23 +1,38 @@
 use std::{
     net::{IpAddr, SocketAddr, ToSocketAddrs},
+    path::PathBuf,
     str::FromStr,
     sync::Arc,
+    time::{Duration, SystemTime},
 };
 
+use anyhow::Context;
 use proxy_fork_core::{
-    Address, AddressBuilder, AddressPattern, CaEnum, CertInput, NoCa, PathTransformMode, Protocol,
-    Proxy, ProxyHandlerBuilder, ProxyManager, load_ca_from_sources, rustls::crypto::aws_lc_rs,
+    AddressBuilder, AddressPattern, AdminServer, AdminServerBuilder, Block, CaEnum, CertInput,
+    FaultRng, HarRecorder, HostHeader, MockResponse, NetworkProfile, NoCa, NoProxy, PAC_PATH, Pac,
+    PathTransformMode, Protocol, Proxy, ProxyHandlerBuilder, ProxyManager, ProxyRule, RecordMode,
+    Redirect, RuleAction, UpstreamConnector, UpstreamProxy, UpstreamProxyConfig, Via,
+    load_ca_from_sources, rustls::crypto::aws_lc_rs,
 };
 use sysproxy::Sysproxy;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::signal::unix::SignalKind;
 use tokio::sync::{Mutex, RwLock};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
     args::RuleItem,
-    config::AppConfig,
+    config::{AppConfig, ProxyManagerRuntime, split_host_port},
     dirs::{APP_NAME, default_cert_path, default_private_key_path},
 };
 
+/// 轮询配置文件变化的间隔
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 重新加载配置的函数（按启动时的命令行参数重新合并各配置文件）
+pub(crate) type ConfigLoader = Box<dyn Fn() -> anyhow::Result<AppConfig> + Send + Sync>;
+
 fn resolve_listen_ip(host: &str) -> anyhow::Result<IpAddr> {
     if host.eq_ignore_ascii_case("localhost") {
         return Ok(IpAddr::from([127, 0, 0, 1]));
@@ -46,6 +61,164 @@ fn resolve_listen_ip(host: &str) -> anyhow::Result<IpAddr> {
     Ok(resolved[0].ip())
 }
 
+/// 启动管理接口：与代理共享同一个规则管理器
+async fn start_admin_server(
+    listen: &str,
+    token: Option<String>,
+    proxy_manager: Arc<RwLock<ProxyManager>>,
+) -> anyhow::Result<()> {
+    let (host, port) = split_host_port(listen)
+        .ok_or_else(|| anyhow::anyhow!("invalid admin listen address '{}'", listen))?;
+    let addr = SocketAddr::from((resolve_listen_ip(&host)?, port));
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind admin API on {}", addr))?;
+
+    let token = match token {
+        Some(token) => token,
+        None => {
+            let token = AdminServer::random_token();
+            info!("Admin API token: {}", token);
+            token
+        }
+    };
+    let admin = AdminServerBuilder::default()
+        .proxy_manager(proxy_manager)
+        .token(token)
+        .build()?;
+    info!("Admin API listening on http://{}", addr);
+    tokio::spawn(admin.serve(listener));
+    Ok(())
+}
+
+/// 绑定 SOCKS5 入站监听地址
+async fn bind_socks5(listen: &str) -> anyhow::Result<TcpListener> {
+    let (host, port) = split_host_port(listen)
+        .ok_or_else(|| anyhow::anyhow!("invalid socks5 listen address '{}'", listen))?;
+    let addr = SocketAddr::from((resolve_listen_ip(&host)?, port));
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind SOCKS5 listener on {}", addr))?;
+    info!("SOCKS5 proxy listening on {}", addr);
+    Ok(listener)
+}
+
+/// 按配置构造规则管理器，同时返回无法转换为运行时规则的配置项
+pub(crate) async fn build_proxy_manager(
+    cfg: &ProxyManagerRuntime,
+) -> anyhow::Result<(ProxyManager, Vec<&RuleItem>)> {
+    let mut proxy_manager = ProxyManager::from_config(
+        ProxyManager::builder()
+            .cache_size(cfg.cache_size)
+            .build()
+            .unwrap(),
+    )
+    .map_err(|e| anyhow::anyhow!("failed to construct ProxyManager from config: {}", e))?;
+
+    // 从配置添加规则
+    let mut invalid = Vec::new();
+    for r in cfg.rules.iter() {
+        match rule_item_to_runtime(r) {
+            Some(rule) => proxy_manager.add_proxy_rule(rule).await,
+            None => invalid.push(r),
+        }
+    }
+
+    // 按 profile 仅启用指定分组
+    if !cfg.profiles.is_empty() {
+        proxy_manager.enable_only_groups(&cfg.profiles).await;
+    }
+    Ok((proxy_manager, invalid))
+}
+
+/// 重新加载配置并原子地替换规则；配置无效时返回错误，保留原有规则
+///
+/// 与启动时跳过无效规则不同，重载时只要有一条规则无效就整体拒绝，
+/// 避免编辑到一半的规则让正在使用的规则失效
+///
+/// 只有规则相关的配置（规则、规则文件、分组与缓存大小）会生效，
+/// 通过管理接口做的运行时修改会被配置覆盖
+pub(crate) async fn reload_rules(
+    reload: &ConfigLoader,
+    proxy_manager: &RwLock<ProxyManager>,
+) -> anyhow::Result<AppConfig> {
+    let cfg = reload()?;
+    let (next, invalid) = build_proxy_manager(&cfg.proxy_manager).await?;
+    if let Some(r) = invalid.first() {
+        anyhow::bail!(
+            "invalid rule in config (reload rejects the whole config instead of skipping it as on startup): {:?}",
+            r
+        );
+    }
+    let mut manager = proxy_manager.write().await;
+    if manager.is_runtime_modified() {
+        warn!("Reloading config discards rule changes made through the admin API");
+    }
+    manager.replace_rules_with(next);
+    Ok(cfg)
+}
+
+/// 配置文件的修改时间与大小，用于轮询检测变化
+fn file_stamps(files: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    files
+        .iter()
+        .map(|p| {
+            let meta = fs_err::metadata(p).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        })
+        .collect()
+}
+
+/// 监听配置文件变化与 SIGHUP，触发规则热重载
+async fn watch_config(
+    reload: ConfigLoader,
+    mut files: Vec<PathBuf>,
+    proxy_manager: Arc<RwLock<ProxyManager>>,
+) {
+    #[cfg(unix)]
+    let mut hangup = match tokio::signal::unix::signal(SignalKind::hangup()) {
+        Ok(signal) => Some(signal),
+        Err(e) => {
+            warn!("Failed to listen for SIGHUP: {}", e);
+            None
+        }
+    };
+    let mut stamps = file_stamps(&files);
+    let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+    loop {
+        #[cfg(unix)]
+        let hangup_recv = async {
+            match hangup.as_mut() {
+                Some(signal) => signal.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        #[cfg(not(unix))]
+        let hangup_recv = std::future::pending::<Option<()>>();
+
+        tokio::select! {
+            _ = interval.tick() => {
+                let current = file_stamps(&files);
+                if current == stamps {
+                    continue;
+                }
+                info!("Config file changed, reloading rules");
+            }
+            _ = hangup_recv => info!("SIGHUP received, reloading rules"),
+        }
+
+        match reload_rules(&reload, &proxy_manager).await {
+            Ok(cfg) => {
+                files = cfg.config_files;
+                info!("{}", proxy_manager.read().await);
+            }
+            Err(e) => error!("Failed to reload config, keeping previous rules: {:#}", e),
+        }
+        // 无论成功与否都更新快照，避免对同一次无效修改反复报错
+        stamps = file_stamps(&files);
+    }
+}
+
 async fn shutdown_signal(sysproxy: Option<Arc<Mutex<Sysproxy>>>) {
     // 支持两种关闭方式，一种是 Ctrl+C，另一种是通过 channel 发送关闭信号
     tokio::select! {
@@ -62,22 +235,42 @@ async fn shutdown_signal(sysproxy: Option<Arc<Mutex<Sysproxy>>>) {
     }
 }
 
-fn rule_item_to_runtime(r: &RuleItem) -> Option<(AddressPattern, Address)> {
+fn rule_item_to_runtime(r: &RuleItem) -> Option<ProxyRule> {
     let protocol = parse_rule_protocol(&r.protocol)?;
     let pattern = AddressPattern::new(protocol, &r.host, r.port, r.path.as_deref()).ok()?;
 
     let target_protocol = match r.target_protocol.as_deref() {
+        // Unix 域套接字目标：target_host 为套接字路径
+        Some(protocol) if protocol.trim().eq_ignore_ascii_case("unix") => Protocol::Unix,
+        // 本地目录目标：target_host 为目录路径
+        Some(protocol) if protocol.trim().eq_ignore_ascii_case("file") => Protocol::File,
         Some(protocol) => parse_rule_protocol(protocol)?,
         None => Protocol::Http,
     };
 
+    // 不连接目标的规则未指定 target_host 时以 localhost 占位
+    let action = rule_item_action(r)?;
+    if let RuleAction::Mock(mock) = &action {
+        mock.validate().ok()?;
+    }
+    let needs_target = match &action {
+        RuleAction::Forward => true,
+        RuleAction::Redirect(redirect) => redirect.location.is_none(),
+        RuleAction::Mock(_) | RuleAction::Block(_) => false,
+    };
+    let target_host = match r.target_host.trim() {
+        "" if needs_target => return None,
+        "" => "localhost".to_string(),
+        host => host.to_string(),
+    };
+
     let mut builder = AddressBuilder::default()
         .protocol(target_protocol)
-        .host(r.target_host.clone())
+        .host(target_host)
         .port(r.target_port);
 
     builder = if let Some(mode) = r.path_transform.as_deref() {
-        let mode = PathTransformMode::from_str(mode).unwrap_or_default();
+        let mode = PathTransformMode::from_str(mode).ok()?;
         builder.path_transform_mode(mode)
     } else {
         builder
@@ -89,7 +282,82 @@ fn rule_item_to_runtime(r: &RuleItem) -> Option<(AddressPattern, Address)> {
         builder
     };
 
-    Some((pattern, builder.build().ok()?))
+    let host_header = match r.host_header.as_deref() {
+        Some(value) => value.parse().ok()?,
+        None => HostHeader::default(),
+    };
+
+    let via = match r.via.as_deref() {
+        Some(value) => value.parse().ok()?,
+        None => Via::default(),
+    };
+
+    let request_headers = r.request_headers.clone().unwrap_or_default();
+    request_headers.validate().ok()?;
+
+    let response_presets = r
+        .response_presets
+        .iter()
+        .flatten()
+        .map(|preset| preset.parse())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    let response_headers = r.response_headers.clone().unwrap_or_default();
+    response_headers.validate().ok()?;
+
+    let fault = r.fault.clone().unwrap_or_default();
+    fault.validate().ok()?;
+
+    let throttle = match r.throttle.as_deref() {
+        Some(value) => Some(value.parse().ok()?),
+        None => None,
+    };
+
+    Some(ProxyRule {
+        group: r.group.clone(),
+        query: r.query.clone().unwrap_or_default(),
+        request_headers,
+        request_body: r.request_body.clone().unwrap_or_default(),
+        response_presets,
+        response_headers,
+        response_body: r.response_body.clone().unwrap_or_default(),
+        cookies: r.cookies.clone().unwrap_or_default(),
+        host_header,
+        via,
+        spa_fallback: r.spa_fallback.unwrap_or_default(),
+        action,
+        fault,
+        throttle,
+        ..ProxyRule::new(pattern, builder.build().ok()?)
+    })
+}
+
+/// 规则的处理方式：未指定 action 时，设置了 mock 则返回预设响应，否则转发
+fn rule_item_action(r: &RuleItem) -> Option<RuleAction> {
+    let Some(action) = r.action.as_deref() else {
+        return Some(r.mock.clone().map_or(RuleAction::Forward, RuleAction::Mock));
+    };
+
+    match action.trim().to_ascii_lowercase().as_str() {
+        "forward" => Some(RuleAction::Forward),
+        "mock" => {
+            Some(RuleAction::Mock(r.mock.clone().unwrap_or_else(|| {
+                MockResponse::new(r.status.unwrap_or(200))
+            })))
+        }
+        "redirect" => {
+            let redirect = Redirect::new(r.status.unwrap_or(302)).ok()?;
+            Some(RuleAction::Redirect(match &r.location {
+                Some(location) => redirect.location(location.clone()),
+                None => redirect,
+            }))
+        }
+        "block" if r.reset.unwrap_or_default() => Some(RuleAction::Block(Block::reset())),
+        "block" => Block::new(r.status.unwrap_or(403))
+            .ok()
+            .map(RuleAction::Block),
+        _ => None,
+    }
 }
 
 fn parse_rule_protocol(protocol: &str) -> Option<Protocol> {
@@ -100,33 +368,55 @@ fn parse_rule_protocol(protocol: &str) -> Option<Protocol> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::rule_item_to_runtime;
-    use crate::args::RuleItem;
-
-    #[test]
-    fn rule_item_rejects_websocket_protocols() {
-        let mut rule = RuleItem {
-            protocol: "https".into(),
-            host: "example.com".into(),
-            path: None,
-            port: None,
-            target_protocol: Some("ws".into()),
-            target_host: "127.0.0.1".into(),
-            target_port: None,
-            path_transform: None,
-            target_path: None,
-        };
-        assert!(rule_item_to_runtime(&rule).is_none());
+/// 计算全局上游代理配置：CLI/配置文件优先，否则读取环境变量
+///
+/// 来自环境变量且指向本代理监听地址的代理会被忽略，避免请求在自身上循环
+fn resolve_upstream_config(
+    cfg: &AppConfig,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<UpstreamProxyConfig> {
+    let mut upstream = match cfg.upstream_proxy.as_deref().map(str::trim) {
+        Some(value) if value.eq_ignore_ascii_case("direct") => UpstreamProxyConfig::default(),
+        Some(value) => UpstreamProxyConfig::all(
+            value
+                .parse::<UpstreamProxy>()
+                .map_err(|e| anyhow::anyhow!("invalid upstream proxy: {}", e))?,
+        ),
+        None => {
+            let mut upstream = UpstreamProxyConfig::from_lookup(lookup);
+            for proxy in [&mut upstream.http, &mut upstream.https] {
+                if let Some(p) = proxy.take_if(|p| points_to_listen_addr(p, cfg)) {
+                    warn!(
+                        "Ignoring upstream proxy from environment that points to this proxy: {}:{}",
+                        p.host, p.port
+                    );
+                }
+            }
+            upstream
+        }
+    };
 
-        rule.protocol = "wss".into();
-        rule.target_protocol = Some("http".into());
-        assert!(rule_item_to_runtime(&rule).is_none());
+    if let Some(no_proxy) = cfg.no_proxy.as_deref() {
+        upstream.no_proxy = no_proxy
+            .parse::<NoProxy>()
+            .map_err(|e| anyhow::anyhow!("invalid no_proxy: {}", e))?;
     }
+    Ok(upstream)
+}
+
+fn points_to_listen_addr(proxy: &UpstreamProxy, cfg: &AppConfig) -> bool {
+    let is_loopback = |host: &str| {
+        host.eq_ignore_ascii_case("localhost")
+            || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    };
+    proxy.port == cfg.listen.port
+        && (proxy.host.eq_ignore_ascii_case(&cfg.listen.host)
+            || (is_loopback(&proxy.host) && is_loopback(&cfg.listen.host)))
 }
 
-pub(crate) async fn start_proxy(cfg: &AppConfig) -> anyhow::Result<()> {
+pub(crate) async fn start_proxy(cfg: &AppConfig, reload: ConfigLoader) -> anyhow::Result<()> {
+    let upstream = resolve_upstream_config(cfg, |name| std::env::var(name).ok())?;
+
     let ca = if cfg.enable_ca {
         // 统一加载 CA 证书和私钥（优先使用系统证书，私钥从本地 PEM 文件读取）
         match (&cfg.ca_cert, &cfg.ca_key) {
@@ -164,30 +454,60 @@ pub(crate) async fn start_proxy(cfg: &AppConfig) -> anyhow::Result<()> {
     };
 
     // 初始化 proxy manager
-    let mut proxy_manager = ProxyManager::from_config(
-        ProxyManager::builder()
-            .cache_size(cfg.proxy_manager.cache_size)
-            .build()
-            .unwrap(),
-    )
-    .expect("Failed to construct ProxyManager from config");
-
-    // 从配置添加规则
-    for r in cfg.proxy_manager.rules.iter() {
-        if let Some((pattern, target)) = rule_item_to_runtime(r) {
-            proxy_manager.add_rule(pattern, target).await;
-        } else {
-            error!("invalid rule in config, skipped: {:?}", r);
-        }
+    let (proxy_manager, invalid) = build_proxy_manager(&cfg.proxy_manager).await?;
+    for r in invalid {
+        error!("invalid rule in config, skipped: {:?}", r);
     }
 
     // 创建共享的 proxy manager
     let proxy_manager_arc = Arc::new(RwLock::new(proxy_manager));
 
     // 初始化单个 proxy handler（共享同一个 proxy manager）
-    let proxy_handler = ProxyHandlerBuilder::default()
+    let mut proxy_handler = ProxyHandlerBuilder::default()
         .proxy_manager(proxy_manager_arc.clone())
-        .with_ca(cfg.enable_ca)
+        .with_ca(cfg.enable_ca);
+    // 指定种子时故障注入可复现
+    if let Some(seed) = cfg.fault_seed {
+        proxy_handler = proxy_handler.fault_rng(FaultRng::seeded(seed));
+    }
+    if let Some(profile) = cfg.throttle.as_deref() {
+        let profile = profile
+            .parse::<NetworkProfile>()
+            .map_err(|e| anyhow::anyhow!("invalid throttle: {}", e))?;
+        info!("Simulating network profile: {}", profile);
+        proxy_handler = proxy_handler.throttle(Some(profile));
+    }
+    let har = cfg.har.as_ref().map(|path| {
+        info!("Recording HAR to {}", path.display());
+        let har = HarRecorder::new();
+        match cfg.har_max_body {
+            Some(size) => har.max_body_size(size),
+            None => har,
+        }
+    });
+    if let Some(recording) = &cfg.recording {
+        match recording.mode() {
+            RecordMode::Record => info!("Recording responses to {}", recording.dir().display()),
+            RecordMode::Replay { strict } => info!(
+                "Replaying responses from {}{}",
+                recording.dir().display(),
+                if strict { " (strict)" } else { "" }
+            ),
+        }
+        // HTTPS 请求只有经 MITM 解密后才能录制与回放
+        if !cfg.enable_ca {
+            warn!(
+                "CA is disabled: HTTPS requests are tunneled and will not be recorded or replayed"
+            );
+        }
+    }
+    let pac = Pac::new(format!("{}:{}", cfg.listen.host, cfg.listen.port))
+        .hosts(&cfg.pac_hosts)
+        .map_err(|e| anyhow::anyhow!("invalid pac host: {}", e))?;
+    let proxy_handler = proxy_handler
+        .har(har.clone())
+        .recording(cfg.recording.clone())
+        .pac(Some(pac))
         .build()
         .unwrap();
 
@@ -211,17 +531,49 @@ pub(crate) async fn start_proxy(cfg: &AppConfig) -> anyhow::Result<()> {
         }
     }
 
+    for (scheme, proxy) in [("http", &upstream.http), ("https", &upstream.https)] {
+        if let Some(proxy) = proxy {
+            info!(
+                "Using upstream proxy for {} targets: {}:{}",
+                scheme, proxy.host, proxy.port
+            );
+        }
+    }
+    let connector = UpstreamConnector::new(upstream);
+
     let listen_ip = resolve_listen_ip(&cfg.listen.host)?;
-    let proxy = Proxy::builder()
+    let mut proxy = Proxy::builder()
         .with_addr(SocketAddr::from((listen_ip, cfg.listen.port)))
         // .with_ca(NoCa)
         .with_ca(ca)
-        .with_rustls_connector(aws_lc_rs::default_provider())
+        .with_rustls_connector_over(aws_lc_rs::default_provider(), connector)
         .with_http_handler(proxy_handler.clone())
         .with_websocket_handler(proxy_handler.clone())
-        .with_graceful_shutdown(shutdown_signal(sysproxy.clone()))
-        .build()
-        .expect("Failed to create proxy");
+        .with_graceful_shutdown(shutdown_signal(sysproxy.clone()));
+    // SOCKS5 连接与 HTTP CONNECT 走同一套规则与 CA 解密流程
+    if let Some(socks5_listen) = cfg.socks5_listen.as_deref() {
+        proxy = proxy.with_socks5_listener(bind_socks5(socks5_listen).await?);
+    }
+    let proxy = proxy.build().expect("Failed to create proxy");
+
+    if let Some(admin_listen) = cfg.admin_listen.as_deref() {
+        start_admin_server(
+            admin_listen,
+            cfg.admin_token.clone(),
+            proxy_manager_arc.clone(),
+        )
+        .await?;
+    }
+
+    info!(
+        "Watching {} config file(s) for changes",
+        cfg.config_files.len()
+    );
+    tokio::spawn(watch_config(
+        reload,
+        cfg.config_files.clone(),
+        proxy_manager_arc.clone(),
+    ));
 
     print_server_info(cfg, proxy_manager_arc, listen_ip).await?;
     info!("Proxy service startup complete. Ready to accept requests.");
@@ -230,6 +582,12 @@ pub(crate) async fn start_proxy(cfg: &AppConfig) -> anyhow::Result<()> {
     if let Err(e) = proxy.start().await {
         error!("{}", e);
     }
+
+    if let (Some(har), Some(path)) = (har, &cfg.har) {
+        har.write_to(path)
+            .with_context(|| format!("failed to write HAR to {}", path.display()))?;
+        info!("Wrote {} HAR entries to {}", har.len(), path.display());
+    }
     Ok(())
 }
 
@@ -242,6 +600,10 @@ async fn print_server_info(
         "Proxy server listening on {}:{}",
         listen_ip, cfg.listen.port
     );
+    info!(
+        "PAC script available at http://{}:{}{}",
+        cfg.listen.host, cfg.listen.port, PAC_PATH
+    );
     if cfg.enable_sysproxy {
         info!("System proxy is enabled");
     } else {
@@ -259,3 +621,364 @@ async fn print_server_info(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ConfigLoader, build_proxy_manager, reload_rules, resolve_upstream_config,
+        rule_item_to_runtime,
+    };
+    use crate::args::{GlobalConfigArgs, RuleItem, StartProxyArgs};
+    use crate::config::{
+        AppConfig, AppConfigBuilder, ListenAddrBuilder, ProxyManagerRuntimeBuilder,
+    };
+    use proxy_fork_core::{
+        HeaderPreset, HostHeader, MockResponse, NetworkProfile, RuleAction, UpstreamProxyKind, Via,
+    };
+    use tokio::sync::RwLock;
+
+    fn app_config(upstream_proxy: Option<&str>, no_proxy: Option<&str>) -> AppConfig {
+        AppConfigBuilder::default()
+            .listen(ListenAddrBuilder::default().build().unwrap())
+            .proxy_manager(ProxyManagerRuntimeBuilder::default().build().unwrap())
+            .upstream_proxy(upstream_proxy.map(String::from))
+            .no_proxy(no_proxy.map(String::from))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn rule_item_rejects_websocket_protocols() {
+        let mut rule = RuleItem {
+            protocol: "https".into(),
+            host: "example.com".into(),
+            target_protocol: Some("ws".into()),
+            target_host: "127.0.0.1".into(),
+            ..Default::default()
+        };
+        assert!(rule_item_to_runtime(&rule).is_none());
+
+        rule.protocol = "wss".into();
+        rule.target_protocol = Some("http".into());
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[test]
+    fn rule_item_keeps_group() {
+        let rule = RuleItem {
+            protocol: "https".into(),
+            host: "api.example.com".into(),
+            target_host: "127.0.0.1".into(),
+            target_port: Some(8080),
+            group: Some("local-backend".into()),
+            ..Default::default()
+        };
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(runtime.group.as_deref(), Some("local-backend"));
+    }
+
+    #[test]
+    fn rule_item_parses_host_header() {
+        let mut rule = RuleItem {
+            protocol: "http".into(),
+            host: "app.example.com".into(),
+            target_host: "127.0.0.1".into(),
+            target_port: Some(8080),
+            host_header: Some("preserve".into()),
+            ..Default::default()
+        };
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(runtime.host_header, HostHeader::Preserve);
+
+        rule.host_header = Some("api.internal:8080".into());
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(
+            runtime.host_header,
+            HostHeader::Value("api.internal:8080".into())
+        );
+
+        rule.host_header = Some("bad\nvalue".into());
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[test]
+    fn rule_item_accepts_unix_socket_target() {
+        let rule = RuleItem {
+            protocol: "http".into(),
+            host: "app.example.com".into(),
+            target_protocol: Some("unix".into()),
+            target_host: "/run/app.sock".into(),
+            ..Default::default()
+        };
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(
+            runtime.target.unix_socket_path(),
+            Some(std::path::Path::new("/run/app.sock"))
+        );
+    }
+
+    #[test]
+    fn rule_item_parses_via() {
+        let mut rule = RuleItem {
+            protocol: "https".into(),
+            host: "api.example.com".into(),
+            target_protocol: Some("https".into()),
+            target_host: "api.example.com".into(),
+            via: Some("direct".into()),
+            ..Default::default()
+        };
+        assert_eq!(rule_item_to_runtime(&rule).unwrap().via, Via::Direct);
+
+        rule.via = Some("ftp://proxy".into());
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[test]
+    fn upstream_config_prefers_explicit_setting() {
+        let env = |name: &str| (name == "HTTPS_PROXY").then(|| "http://env-proxy:3128".to_string());
+
+        let upstream = resolve_upstream_config(&app_config(None, None), env).unwrap();
+        assert_eq!(upstream.https.unwrap().host, "env-proxy");
+        assert!(upstream.http.is_none());
+
+        let upstream = resolve_upstream_config(
+            &app_config(Some("socks5://127.0.0.1:1080"), Some("localhost")),
+            env,
+        )
+        .unwrap();
+        let proxy = upstream.http.clone().unwrap();
+        assert_eq!(proxy.kind, UpstreamProxyKind::Socks5);
+        assert_eq!(upstream.https, Some(proxy));
+        assert!(upstream.no_proxy.matches("localhost"));
+
+        let upstream = resolve_upstream_config(&app_config(Some("direct"), None), env).unwrap();
+        assert!(upstream.is_empty());
+
+        assert!(resolve_upstream_config(&app_config(Some("ftp://proxy"), None), env).is_err());
+    }
+
+    #[test]
+    fn upstream_config_ignores_env_proxy_pointing_to_itself() {
+        let env = |name: &str| (name == "http_proxy").then(|| "http://localhost:7898".to_string());
+        let upstream = resolve_upstream_config(&app_config(None, None), env).unwrap();
+        assert!(upstream.http.is_none());
+    }
+
+    #[test]
+    fn rule_item_accepts_local_directory_target() {
+        let rule = RuleItem {
+            protocol: "https".into(),
+            host: "app.example.com".into(),
+            target_protocol: Some("file".into()),
+            target_host: "/srv/dist".into(),
+            spa_fallback: Some(true),
+            ..Default::default()
+        };
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(
+            runtime.target.local_root(),
+            Some(std::path::Path::new("/srv/dist"))
+        );
+        assert!(runtime.spa_fallback);
+    }
+
+    #[test]
+    fn rule_item_mock_does_not_need_target() {
+        let mut rule = RuleItem {
+            protocol: "https".into(),
+            host: "api.example.com".into(),
+            target_host: String::new(),
+            mock: Some(MockResponse::new(204)),
+            ..Default::default()
+        };
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert!(matches!(runtime.action, RuleAction::Mock(ref m) if m.status == 204));
+
+        // 无效的状态码与响应头在加载时即被拒绝
+        rule.mock = Some(MockResponse::new(1000));
+        assert!(rule_item_to_runtime(&rule).is_none());
+        rule.mock = Some(MockResponse::new(200).header("bad header", "x"));
+        assert!(rule_item_to_runtime(&rule).is_none());
+
+        rule.mock = None;
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[test]
+    fn rule_item_redirect_and_block_actions() {
+        let mut rule: RuleItem = toml::from_str(
+            r#"
+            protocol = "https"
+            host = "old.example.com"
+            action = "redirect"
+            status = 301
+            location = "https://new.example.com{{path}}"
+            "#,
+        )
+        .unwrap();
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert!(matches!(
+            runtime.action,
+            RuleAction::Redirect(ref r)
+                if r.status == 301 && r.location.as_deref() == Some("https://new.example.com{{path}}")
+        ));
+
+        // 未指定 location 的重定向需要目标地址
+        rule.location = None;
+        assert!(rule_item_to_runtime(&rule).is_none());
+        rule.target_host = "new.example.com".into();
+        assert!(rule_item_to_runtime(&rule).is_some());
+        rule.status = Some(200);
+        assert!(rule_item_to_runtime(&rule).is_none());
+
+        rule.action = Some("block".into());
+        rule.status = None;
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert!(matches!(runtime.action, RuleAction::Block(ref b) if b.status == 403 && !b.reset));
+        rule.reset = Some(true);
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert!(matches!(runtime.action, RuleAction::Block(ref b) if b.reset));
+
+        rule.action = Some("drop".into());
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[test]
+    fn rule_item_fault_is_validated() {
+        let mut rule: RuleItem = toml::from_str(
+            r#"
+            protocol = "https"
+            host = "api.example.com"
+            target_host = "127.0.0.1"
+            fault = { latency_ms = [500, 2000], error_rate = 0.1, error_status = 502 }
+            "#,
+        )
+        .unwrap();
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(runtime.fault.latency_ms, Some((500, 2000)));
+        assert_eq!(runtime.fault.error_status, 502);
+        assert_eq!(RuleItem::from(&runtime).fault, Some(runtime.fault.clone()));
+
+        rule.fault.as_mut().unwrap().error_rate = 2.0;
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[test]
+    fn rule_item_throttle() {
+        let mut rule: RuleItem =
+            "https://api.example.com/* -> http://127.0.0.1:8080 [throttle=slow-3g]"
+                .parse()
+                .unwrap();
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(runtime.throttle, NetworkProfile::preset("slow-3g"));
+
+        rule.throttle = Some("fast".into());
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[test]
+    fn rule_item_request_headers_are_validated() {
+        let mut rule: RuleItem = toml::from_str(
+            r#"
+            protocol = "https"
+            host = "api.example.com"
+            target_host = "127.0.0.1"
+            request_headers = { set = { Authorization = "Bearer dev" }, remove = ["If-None-Match"] }
+            "#,
+        )
+        .unwrap();
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(
+            runtime.request_headers.set,
+            vec![("Authorization".to_string(), "Bearer dev".to_string())]
+        );
+        assert_eq!(
+            RuleItem::from(&runtime)
+                .request_headers
+                .unwrap()
+                .remove
+                .len(),
+            1
+        );
+
+        let headers = rule.request_headers.take().unwrap();
+        rule.request_headers = Some(headers.set("Bad Name", "1"));
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[test]
+    fn rule_item_response_headers() {
+        let mut rule: RuleItem = toml::from_str(
+            r#"
+            protocol = "https"
+            host = "api.example.com"
+            target_host = "127.0.0.1"
+            response_presets = ["cors=http://localhost:3000", "strip-csp"]
+            response_headers = { remove = ["Server"] }
+            "#,
+        )
+        .unwrap();
+        let runtime = rule_item_to_runtime(&rule).unwrap();
+        assert_eq!(
+            runtime.response_presets,
+            vec![
+                HeaderPreset::Cors(Some("http://localhost:3000".into())),
+                HeaderPreset::StripCsp,
+            ]
+        );
+        assert_eq!(runtime.response_headers.remove.len(), 1);
+        assert_eq!(
+            RuleItem::from(&runtime).response_presets,
+            rule.response_presets
+        );
+
+        rule.response_presets = Some(vec!["allow-all".into()]);
+        assert!(rule_item_to_runtime(&rule).is_none());
+    }
+
+    #[tokio::test]
+    async fn reload_rules_keeps_previous_rules_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("proxy-fork.toml");
+        let write = |rule: &str| {
+            fs_err::write(&path, format!("[proxy_manager]\nrules = [\"{}\"]\n", rule)).unwrap()
+        };
+        write("https://reload.example.com/ -> http://127.0.0.1:8080");
+        let global = GlobalConfigArgs {
+            config: Some(path.clone()),
+            ..Default::default()
+        };
+        let reload: ConfigLoader = Box::new(move || {
+            crate::config::reload_start_proxy_config(&global, &StartProxyArgs::default())
+        });
+        let cfg = reload().unwrap();
+        assert!(cfg.config_files.contains(&path));
+        let manager = RwLock::new(build_proxy_manager(&cfg.proxy_manager).await.unwrap().0);
+        let port = async || {
+            let manager = manager.read().await;
+            manager
+                .get_rule("https://reload.example.com/")
+                .map(|r| r.target.port)
+        };
+        assert_eq!(port().await, Some(Some(8080)));
+
+        write("https://reload.example.com/ -> http://127.0.0.1:9090");
+        reload_rules(&reload, &manager).await.unwrap();
+        assert_eq!(port().await, Some(Some(9090)));
+
+        // 无法解析的修改不影响当前规则
+        fs_err::write(&path, "[proxy_manager\n").unwrap();
+        assert!(reload_rules(&reload, &manager).await.is_err());
+        assert_eq!(port().await, Some(Some(9090)));
+
+        // 与启动时不同，包含无效规则的配置整体被拒绝，有效规则也不生效
+        fs_err::write(
+            &path,
+            "[proxy_manager]\nrules = [\n  \"https://reload.example.com/ -> http://127.0.0.1:7070\",\n  { protocol = \"ftp\", host = \"bad.example.com\", target_host = \"localhost\" },\n]\n",
+        )
+        .unwrap();
+        let err = reload_rules(&reload, &manager).await.unwrap_err();
+        assert!(err.to_string().contains("bad.example.com"));
+        assert_eq!(port().await, Some(Some(9090)));
+    }
+}
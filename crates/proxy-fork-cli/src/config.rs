@@ -1,9 +1,11 @@
 use std::path::{Path, PathBuf};
 
+use anyhow::Context;
 use derive_builder::Builder;
 use fs_err as fs;
-use serde::Deserialize;
-use tracing::debug;
+use proxy_fork_core::RecordReplay;
+use serde::{Deserialize, Deserializer};
+use tracing::{debug, warn};
 
 use crate::args::{GlobalConfigArgs, RuleItem, StartProxyArgs};
 use crate::dirs::user_proxy_fork_config_dir;
@@ -18,16 +20,41 @@ pub struct FileConfig {
     pub listen: Option<String>,
     /// 禁用 CA 证书（无证书模式）
     pub noca: Option<bool>,
+    /// 全局上游代理 URL，或 `direct` 表示忽略环境变量直连
+    pub upstream_proxy: Option<String>,
+    /// 不经过上游代理的主机列表（逗号分隔）
+    pub no_proxy: Option<String>,
+    /// 故障注入的随机数种子（未设置时每次运行随机）
+    pub fault_seed: Option<u64>,
+    /// 全局模拟的网络状况（预设名或 `<下行 kbps>/<上行 kbps>[/<延迟 ms>]`）
+    pub throttle: Option<String>,
+    /// 记录 HAR 的文件路径（相对路径基于当前工作目录）
+    pub har: Option<PathBuf>,
+    /// HAR 中每个消息体最多记录的字节数
+    pub har_max_body: Option<usize>,
+    /// 管理接口的监听地址（未设置时不启动）
+    pub admin_listen: Option<String>,
+    /// 管理接口的访问令牌
+    pub admin_token: Option<String>,
+    /// SOCKS5 入站监听地址（未设置时不启动）
+    pub socks5_listen: Option<String>,
+    /// PAC 脚本中走代理的主机（未设置时使用规则的主机）
+    pub pac_hosts: Option<Vec<String>>,
     /// 代理规则
     pub proxy_manager: Option<ProxyManagerSection>,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct ProxyManagerSection {
-    /// 规则列表
+    /// 规则列表（每项可以是完整的表格，也可以是单行 DSL 字符串）
+    #[serde(default, deserialize_with = "deserialize_rule_items")]
     pub rules: Option<Vec<RuleItem>>,
+    /// 规则文件列表（每行一条 DSL 规则）；相对路径基于所在配置文件的目录
+    pub rules_files: Option<Vec<PathBuf>>,
     /// LRU 缓存大小
     pub cache_size: Option<usize>,
+    /// 启动时启用的规则分组（未设置时启用全部分组）
+    pub profile: Option<Vec<String>>,
 }
 
 /// 运行时合并后的配置
@@ -46,6 +73,42 @@ pub struct AppConfig {
     pub debug: u8,
     #[builder(default = "true")]
     pub enable_ca: bool,
+    /// 全局上游代理 URL 或 `direct`；未设置时读取 HTTP_PROXY 等环境变量
+    #[builder(default)]
+    pub upstream_proxy: Option<String>,
+    /// 不经过上游代理的主机列表；未设置时读取 NO_PROXY 环境变量
+    #[builder(default)]
+    pub no_proxy: Option<String>,
+    /// 故障注入的随机数种子
+    #[builder(default)]
+    pub fault_seed: Option<u64>,
+    /// 全局模拟的网络状况；规则中的 throttle 优先
+    #[builder(default)]
+    pub throttle: Option<String>,
+    /// 记录 HAR 的文件路径；未设置时不记录
+    #[builder(default)]
+    pub har: Option<PathBuf>,
+    /// HAR 中每个消息体最多记录的字节数
+    #[builder(default)]
+    pub har_max_body: Option<usize>,
+    /// 管理接口的监听地址；未设置时不启动
+    #[builder(default)]
+    pub admin_listen: Option<String>,
+    /// 管理接口的访问令牌；未设置时随机生成
+    #[builder(default)]
+    pub admin_token: Option<String>,
+    /// SOCKS5 入站监听地址；未设置时不启动
+    #[builder(default)]
+    pub socks5_listen: Option<String>,
+    /// PAC 脚本中走代理的主机；为空时使用已启用规则的主机
+    #[builder(default)]
+    pub pac_hosts: Vec<String>,
+    /// 录制或回放上游响应（由 record / replay 子命令设置）
+    #[builder(default)]
+    pub recording: Option<RecordReplay>,
+    /// 已加载的配置文件与规则文件（热重载时监听其变化）
+    #[builder(default)]
+    pub config_files: Vec<PathBuf>,
 }
 
 #[derive(Debug, Clone, Builder)]
@@ -55,6 +118,9 @@ pub struct ProxyManagerRuntime {
     pub cache_size: usize,
     #[builder(default)]
     pub rules: Vec<RuleItem>,
+    /// 启动时启用的规则分组；为空表示启用全部分组
+    #[builder(default)]
+    pub profiles: Vec<String>,
 }
 
 fn default_cache_size() -> usize {
@@ -79,9 +145,28 @@ fn default_listen_port() -> u16 {
 }
 
 /// 加载配置的入口：按优先级合并 CLI > CWD 文件 > 用户目录文件
+///
+/// 启动时无法解析的用户目录与 CWD 配置会被跳过，`--config` 指定的文件必须有效
 pub fn load_start_proxy_config(
     global: &GlobalConfigArgs,
     start_args: &StartProxyArgs,
+) -> Result<AppConfig> {
+    load_config(global, start_args, false)
+}
+
+/// 热重载时加载配置：任何存在但无法解析的配置文件都视为错误，
+/// 以免悄悄丢掉其中的规则
+pub fn reload_start_proxy_config(
+    global: &GlobalConfigArgs,
+    start_args: &StartProxyArgs,
+) -> Result<AppConfig> {
+    load_config(global, start_args, true)
+}
+
+fn load_config(
+    global: &GlobalConfigArgs,
+    start_args: &StartProxyArgs,
+    strict: bool,
 ) -> Result<AppConfig> {
     // 1. 用户目录默认配置路径：~/.config/proxy-fork/config.toml（或平台对应路径）
     let user_cfg_path = user_proxy_fork_config_dir().map(|p| p.join("config.toml"));
@@ -95,23 +180,22 @@ pub fn load_start_proxy_config(
     // 3. 如果 CLI 指定 --config 则优先使用
     let cli_cfg_path = global.config.clone();
 
-    // 依次读取（后读覆盖前读）
+    // 依次读取（后读覆盖前读）；跳过的文件同样记录下来，修复后可触发热重载
     let mut file_cfg = FileConfig::default();
-    if let Some(p) = user_cfg_path.as_ref().filter(|p| p.exists()) {
-        if let Ok(c) = read_toml_file(p) {
-            file_cfg = merge_file_cfg(file_cfg, c);
-        }
-    }
-    if let Some(p) = cwd_cfg_path.as_ref() {
-        if let Ok(c) = read_toml_file(p) {
-            file_cfg = merge_file_cfg(file_cfg, c);
-        }
-    }
-    if let Some(p) = cli_cfg_path.as_ref() {
-        if p.exists() {
-            let c = read_toml_file(p)?;
-            file_cfg = merge_file_cfg(file_cfg, c);
+    let mut config_files = Vec::new();
+    let candidates = [(user_cfg_path, strict), (cwd_cfg_path, strict)]
+        .into_iter()
+        .chain([(cli_cfg_path, true)]);
+    for (p, required) in candidates {
+        let Some(p) = p.filter(|p| p.exists()) else {
+            continue;
+        };
+        match read_toml_file(&p) {
+            Ok(c) => file_cfg = merge_file_cfg(file_cfg, c),
+            Err(e) if !required => warn!("skipping invalid config {}: {:#}", p.display(), e),
+            Err(e) => return Err(e),
         }
+        config_files.push(p);
     }
 
     // 构造运行时配置，应用 CLI 覆盖
@@ -144,15 +228,45 @@ pub fn load_start_proxy_config(
     // 计算是否启用 CA：CLI noca 或文件 noca 设为 true 时禁用
     let enable_ca = !start_args.noca && !file_cfg.noca.unwrap_or(false);
 
+    let upstream_proxy = start_args
+        .upstream_proxy
+        .clone()
+        .or_else(|| file_cfg.upstream_proxy.clone());
+    let no_proxy = start_args
+        .no_proxy
+        .clone()
+        .or_else(|| file_cfg.no_proxy.clone());
+
+    let throttle = start_args
+        .throttle
+        .clone()
+        .or_else(|| file_cfg.throttle.clone());
+
     let pm_section = file_cfg.proxy_manager.unwrap_or_default();
-    // 合并规则：文件中的规则先加入，再追加 CLI 规则
+    // 合并规则：文件中的规则先加入，然后是规则文件，最后追加 CLI 规则
     let mut rules = pm_section.rules.unwrap_or_default();
+    for p in pm_section
+        .rules_files
+        .iter()
+        .flatten()
+        .chain(&start_args.rules_files)
+    {
+        rules.extend(read_rules_file(p)?);
+        config_files.push(p.clone());
+    }
     if !start_args.rules.is_empty() {
-        rules.extend(start_args.rules.clone().into_iter());
+        rules.extend(start_args.rules.clone());
     }
+    // 分组：CLI --profile 优先于文件配置
+    let profiles = if start_args.profiles.is_empty() {
+        pm_section.profile.unwrap_or_default()
+    } else {
+        start_args.profiles.clone()
+    };
     let proxy_manager = ProxyManagerRuntimeBuilder::default()
         .cache_size(pm_section.cache_size.unwrap_or_else(default_cache_size))
         .rules(rules)
+        .profiles(profiles)
         .build()
         .unwrap();
 
@@ -164,17 +278,123 @@ pub fn load_start_proxy_config(
         .enable_sysproxy(start_args.enable_sysproxy)
         .debug(global.debug)
         .enable_ca(enable_ca)
+        .upstream_proxy(upstream_proxy)
+        .no_proxy(no_proxy)
+        .fault_seed(start_args.fault_seed.or(file_cfg.fault_seed))
+        .throttle(throttle)
+        .har(start_args.har.clone().or(file_cfg.har))
+        .har_max_body(start_args.har_max_body.or(file_cfg.har_max_body))
+        .admin_listen(start_args.admin_listen.clone().or(file_cfg.admin_listen))
+        .admin_token(start_args.admin_token.clone().or(file_cfg.admin_token))
+        .socks5_listen(start_args.socks5_listen.clone().or(file_cfg.socks5_listen))
+        .pac_hosts(if start_args.pac_hosts.is_empty() {
+            file_cfg.pac_hosts.unwrap_or_default()
+        } else {
+            start_args.pac_hosts.clone()
+        })
+        .config_files(config_files)
         .build()
         .unwrap())
 }
 
 fn read_toml_file(path: &Path) -> Result<FileConfig> {
     let text = fs::read_to_string(path)?;
-    let cfg: FileConfig = toml::from_str(&text)?;
+    let mut cfg: FileConfig =
+        toml::from_str(&text).with_context(|| format!("invalid config file {}", path.display()))?;
+
+    // 规则文件、预设响应体文件以及本地目录、Unix 域套接字目标的相对路径基于配置文件所在目录
+    if let Some(base) = path.parent()
+        && let Some(pm) = cfg.proxy_manager.as_mut()
+    {
+        for rule in pm.rules.iter_mut().flatten() {
+            let is_path_target = rule.target_protocol.as_deref().is_some_and(|protocol| {
+                let protocol = protocol.trim();
+                protocol.eq_ignore_ascii_case("file") || protocol.eq_ignore_ascii_case("unix")
+            });
+            let target = Path::new(rule.target_host.trim());
+            if is_path_target && !target.as_os_str().is_empty() && target.is_relative() {
+                rule.target_host = base.join(target).to_string_lossy().into_owned();
+            }
+        }
+
+        let body_files = pm
+            .rules
+            .iter_mut()
+            .flatten()
+            .filter_map(|rule| rule.mock.as_mut()?.body_file.as_mut());
+        let files = pm.rules_files.iter_mut().flatten().chain(body_files);
+        for file in files.filter(|f| f.is_relative()) {
+            *file = base.join(&*file);
+        }
+    }
+
     debug!("loaded config from {}", path.display());
     Ok(cfg)
 }
 
+/// 读取规则文件：每行一条 DSL 规则，忽略空行与 `#` 注释
+pub fn read_rules_file(path: &Path) -> Result<Vec<RuleItem>> {
+    let text = fs::read_to_string(path)?;
+    let rules = parse_rules_text(&text)
+        .with_context(|| format!("invalid rules file {}", path.display()))?;
+    debug!("loaded {} rules from {}", rules.len(), path.display());
+    Ok(rules)
+}
+
+/// 解析规则文本（`.rules` 文件内容）
+pub fn parse_rules_text(text: &str) -> Result<Vec<RuleItem>> {
+    text.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(lineno, line)| {
+            line.parse::<RuleItem>()
+                .map_err(|e| anyhow::anyhow!("line {}: {}", lineno, e))
+        })
+        .collect()
+}
+
+/// 反序列化规则列表：每项可以是表格形式的 `RuleItem`，也可以是单行 DSL 字符串
+fn deserialize_rule_items<'de, D>(deserializer: D) -> Result<Option<Vec<RuleItem>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct RuleEntry(RuleItem);
+
+    impl<'de> Deserialize<'de> for RuleEntry {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct RuleEntryVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for RuleEntryVisitor {
+                type Value = RuleEntry;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str(
+                        "a rule table or a rule string like 'https://host/* -> http://target'",
+                    )
+                }
+
+                fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<RuleEntry, E> {
+                    v.parse().map(RuleEntry).map_err(E::custom)
+                }
+
+                fn visit_map<A: serde::de::MapAccess<'de>>(
+                    self,
+                    map: A,
+                ) -> Result<RuleEntry, A::Error> {
+                    RuleItem::deserialize(serde::de::value::MapAccessDeserializer::new(map))
+                        .map(RuleEntry)
+                }
+            }
+
+            deserializer.deserialize_any(RuleEntryVisitor)
+        }
+    }
+
+    let entries = Option::<Vec<RuleEntry>>::deserialize(deserializer)?;
+    Ok(entries.map(|entries| entries.into_iter().map(|e| e.0).collect()))
+}
+
 fn merge_file_cfg(mut base: FileConfig, other: FileConfig) -> FileConfig {
     if other.cert.is_some() {
         base.cert = other.cert;
@@ -188,6 +408,36 @@ fn merge_file_cfg(mut base: FileConfig, other: FileConfig) -> FileConfig {
     if other.noca.is_some() {
         base.noca = other.noca;
     }
+    if other.upstream_proxy.is_some() {
+        base.upstream_proxy = other.upstream_proxy;
+    }
+    if other.no_proxy.is_some() {
+        base.no_proxy = other.no_proxy;
+    }
+    if other.fault_seed.is_some() {
+        base.fault_seed = other.fault_seed;
+    }
+    if other.throttle.is_some() {
+        base.throttle = other.throttle;
+    }
+    if other.har.is_some() {
+        base.har = other.har;
+    }
+    if other.har_max_body.is_some() {
+        base.har_max_body = other.har_max_body;
+    }
+    if other.admin_listen.is_some() {
+        base.admin_listen = other.admin_listen;
+    }
+    if other.admin_token.is_some() {
+        base.admin_token = other.admin_token;
+    }
+    if other.socks5_listen.is_some() {
+        base.socks5_listen = other.socks5_listen;
+    }
+    if other.pac_hosts.is_some() {
+        base.pac_hosts = other.pac_hosts;
+    }
 
     match (base.proxy_manager.take(), other.proxy_manager) {
         (None, x) => base.proxy_manager = x,
@@ -198,6 +448,12 @@ fn merge_file_cfg(mut base: FileConfig, other: FileConfig) -> FileConfig {
             if b.rules.is_some() {
                 a.rules = b.rules;
             }
+            if b.rules_files.is_some() {
+                a.rules_files = b.rules_files;
+            }
+            if b.profile.is_some() {
+                a.profile = b.profile;
+            }
             base.proxy_manager = Some(a);
         }
         (Some(a), None) => base.proxy_manager = Some(a),
@@ -215,10 +471,5 @@ fn find_first_existing<I>(candidates: I) -> Option<PathBuf>
 where
     I: IntoIterator<Item = PathBuf>,
 {
-    for p in candidates {
-        if p.exists() {
-            return Some(p);
-        }
-    }
-    None
+    candidates.into_iter().find(|p| p.exists())
 }
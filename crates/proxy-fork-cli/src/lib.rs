@@ -4,24 +4,57 @@ pub mod config;
 pub mod dirs;
 use crate::{
     args::{CliArgs, Commands, GlobalConfigArgs, StartProxyArgs},
-    config::load_start_proxy_config,
+    commands::start_proxy::ConfigLoader,
+    config::{load_start_proxy_config, reload_start_proxy_config},
 };
 use anyhow::Result;
+use proxy_fork_core::{RecordMode, RecordReplay};
 
 pub async fn run(CliArgs { command, global }: CliArgs) -> Result<()> {
     // 默认命令为 start-proxy
-    let command = command.unwrap_or(Commands::StartProxy(StartProxyArgs::default()));
+    let command = command.unwrap_or(Commands::StartProxy(Box::default()));
 
     // 执行对应命令
     match command {
         Commands::StartProxy(ref start_args) => start_proxy(start_args, &global).await,
+        Commands::Record(ref args) => {
+            let recording = args.matching.to_recording(RecordMode::Record);
+            record_replay(&args.proxy, &global, recording).await
+        }
+        Commands::Replay(ref args) => {
+            let mode = RecordMode::Replay {
+                strict: args.strict,
+            };
+            let recording = args.matching.to_recording(mode);
+            record_replay(&args.proxy, &global, recording).await
+        }
+        Commands::Pac(ref args) => {
+            let cfg = load_start_proxy_config(&global, &args.proxy)?;
+            commands::pac::pac(&cfg, args.proxy_addr.as_deref()).await
+        }
         Commands::GenCa(ref gen_args) => commands::gen_ca::gen_ca(gen_args).await,
     }
 }
 
 async fn start_proxy(start_args: &StartProxyArgs, global: &GlobalConfigArgs) -> Result<()> {
     // 加载配置：CLI > CWD > 用户目录
-    let cfg = load_start_proxy_config(&global, start_args)?;
+    let cfg = load_start_proxy_config(global, start_args)?;
     // 启动代理服务
-    commands::start_proxy::start_proxy(&cfg).await
+    commands::start_proxy::start_proxy(&cfg, config_loader(start_args, global)).await
+}
+
+async fn record_replay(
+    start_args: &StartProxyArgs,
+    global: &GlobalConfigArgs,
+    recording: Result<RecordReplay, String>,
+) -> Result<()> {
+    let mut cfg = load_start_proxy_config(global, start_args)?;
+    cfg.recording = Some(recording.map_err(anyhow::Error::msg)?);
+    commands::start_proxy::start_proxy(&cfg, config_loader(start_args, global)).await
+}
+
+/// 热重载时按相同的命令行参数重新加载配置
+fn config_loader(start_args: &StartProxyArgs, global: &GlobalConfigArgs) -> ConfigLoader {
+    let (start_args, global) = (start_args.clone(), global.clone());
+    Box::new(move || reload_start_proxy_config(&global, &start_args))
 }
@@ -17,7 +17,11 @@ async fn main() {
     // 创建过滤器，只显示 proxy-fork 相关的日志
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
 
-    tracing_subscriber::fmt().with_env_filter(filter).init();
+    // 日志输出到标准错误，标准输出留给 `pac` 等子命令的结果
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
 
     match run(args).await {
         Ok(_) => {}
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use proxy_fork_cli::config::*;
+    use std::path::Path;
 
     #[test]
     fn test_listen_addr_default() {
@@ -16,7 +17,7 @@ mod tests {
             .proxy_manager(ProxyManagerRuntimeBuilder::default().build().unwrap())
             .build()
             .unwrap();
-        assert_eq!(cfg.enable_ca, true);
+        assert!(cfg.enable_ca);
     }
 
     #[test]
@@ -26,4 +27,422 @@ mod tests {
         assert_eq!(p, 9999);
         assert!(split_host_port("bad").is_none());
     }
+
+    #[test]
+    fn test_rules_accept_dsl_strings() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            [proxy_manager]
+            rules = [
+              "https://*.example.com/api/* -> http://localhost:8080/local",
+              { protocol = "https", host = "service.example.com", target_host = "localhost", target_port = 5001 },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].host, "*.example.com");
+        assert_eq!(rules[0].target_path.as_deref(), Some("/local"));
+        assert_eq!(rules[1].host, "service.example.com");
+
+        let err = toml::from_str::<FileConfig>(
+            r#"
+            [proxy_manager]
+            rules = ["https://example.com => http://localhost"]
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("missing '->'"));
+    }
+
+    #[test]
+    fn test_parse_rules_text() {
+        let rules = parse_rules_text(
+            "# 本地后端\n\
+             https://api.example.com/v1/* -> http://127.0.0.1:9090/v2 [replace]\n\
+             \n\
+             https://pay.example.com -> http://127.0.0.1:7001 [group=mock-payments]\n",
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].path_transform.as_deref(), Some("replace"));
+        assert_eq!(rules[1].group.as_deref(), Some("mock-payments"));
+
+        let err =
+            parse_rules_text("https://ok.example.com -> http://localhost\nbad rule\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_rule_query_rewrite() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "example.com"
+            target_host = "localhost"
+            target_port = 3000
+            query = { remove = ["utm_*"], set = { debug = "1" } }
+            "#,
+        )
+        .unwrap();
+
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        let query = rules[0].query.as_ref().unwrap();
+        assert_eq!(query.apply("/?a=1&utm_source=x"), "/?a=1&debug=1");
+    }
+
+    #[test]
+    fn test_upstream_proxy_fields() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            upstream_proxy = "socks5://127.0.0.1:1080"
+            no_proxy = "localhost,.internal"
+
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "api.example.com"
+            target_protocol = "https"
+            target_host = "api.example.com"
+            via = "direct"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cfg.upstream_proxy.as_deref(),
+            Some("socks5://127.0.0.1:1080")
+        );
+        assert_eq!(cfg.no_proxy.as_deref(), Some("localhost,.internal"));
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        assert_eq!(rules[0].via.as_deref(), Some("direct"));
+    }
+
+    #[test]
+    fn test_rule_mock_response() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "api.example.com"
+            path = "/v2/users/*"
+            mock = { status = 201, headers = { content-type = "application/json" }, body = '{"id": "{{path.2}}"}' }
+            "#,
+        )
+        .unwrap();
+
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        assert!(rules[0].target_host.is_empty());
+        let mock = rules[0].mock.as_ref().unwrap();
+        assert_eq!(mock.status, 201);
+        assert_eq!(
+            mock.headers,
+            vec![("content-type".to_string(), "application/json".to_string())]
+        );
+        assert_eq!(mock.body.as_deref(), Some(r#"{"id": "{{path.2}}"}"#));
+    }
+
+    #[test]
+    fn test_har_config() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            har = "captures/session.har"
+            har_max_body = 65536
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cfg.har.as_deref(),
+            Some(std::path::Path::new("captures/session.har"))
+        );
+        assert_eq!(cfg.har_max_body, Some(65536));
+    }
+
+    #[test]
+    fn test_admin_config() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            admin_listen = "127.0.0.1:7899"
+            admin_token = "secret"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(cfg.admin_listen.as_deref(), Some("127.0.0.1:7899"));
+        assert_eq!(cfg.admin_token.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_socks5_listen() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            socks5_listen = "127.0.0.1:7899"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(cfg.socks5_listen.as_deref(), Some("127.0.0.1:7899"));
+    }
+
+    #[test]
+    fn test_pac_hosts() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            pac_hosts = ["*.corp.example", "intranet"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cfg.pac_hosts,
+            Some(vec!["*.corp.example".to_string(), "intranet".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_rule_fault_injection() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            fault_seed = 42
+
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "api.example.com"
+            target_host = "127.0.0.1"
+            fault = { latency_ms = [500, 2000], error_rate = 0.1 }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(cfg.fault_seed, Some(42));
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        let fault = rules[0].fault.as_ref().unwrap();
+        assert_eq!(fault.latency_ms, Some((500, 2000)));
+        assert_eq!(fault.error_rate, 0.1);
+        assert_eq!(fault.error_status, 503);
+        assert!(fault.validate().is_ok());
+    }
+
+    #[test]
+    fn test_throttle() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            throttle = "3g"
+
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "api.example.com"
+            target_host = "127.0.0.1"
+            throttle = "400/400/2000"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(cfg.throttle.as_deref(), Some("3g"));
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        assert_eq!(rules[0].throttle.as_deref(), Some("400/400/2000"));
+    }
+
+    #[test]
+    fn test_rule_request_headers() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "api.example.com"
+            target_host = "127.0.0.1"
+
+            [proxy_manager.rules.request_headers]
+            remove = ["If-None-Match", "re:^x-debug-"]
+            replace = { Cookie = "s/session=[^;]+/session=dev/" }
+            set = { Authorization = "Bearer dev-token", X-Forwarded-User = "alice" }
+            append = { X-Feature = "beta" }
+            "#,
+        )
+        .unwrap();
+
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        let headers = rules[0].request_headers.as_ref().unwrap();
+        assert_eq!(headers.remove.len(), 2);
+        assert_eq!(headers.replace[0].0, "Cookie");
+        assert_eq!(
+            headers.set,
+            vec![
+                ("Authorization".to_string(), "Bearer dev-token".to_string()),
+                ("X-Forwarded-User".to_string(), "alice".to_string()),
+            ]
+        );
+        assert_eq!(headers.append.len(), 1);
+    }
+
+    #[test]
+    fn test_rule_response_body() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "www.example.com"
+            target_protocol = "https"
+            target_host = "www.example.com"
+
+            [proxy_manager.rules.response_body]
+            replace = { "https://cdn.example.com" = "http://localhost:3000" }
+            regex = ['s#/static/js/main\.[0-9a-f]+\.js#/static/js/bundle.js#']
+            inject_head = '<script src="http://localhost:3000/dev.js"></script>'
+            max_size = 1048576
+            "#,
+        )
+        .unwrap();
+
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        let body = rules[0].response_body.as_ref().unwrap();
+        assert_eq!(
+            body.replace,
+            vec![(
+                "https://cdn.example.com".to_string(),
+                "http://localhost:3000".to_string()
+            )]
+        );
+        assert_eq!(body.regex[0].replacement(), "/static/js/bundle.js");
+        assert!(body.inject_head.is_some() && body.inject_body.is_none());
+        assert_eq!(body.max_size, 1048576);
+    }
+
+    #[test]
+    fn test_rule_cookies() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "app.example.com"
+            target_host = "127.0.0.1"
+            target_port = 3000
+            cookies = { auto = false, domain = "", same_site = "lax" }
+            "#,
+        )
+        .unwrap();
+
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        let cookies = rules[0].cookies.as_ref().unwrap();
+        assert!(!cookies.auto);
+        assert_eq!(cookies.domain.as_deref(), Some(""));
+        assert_eq!(cookies.same_site.unwrap().to_string(), "Lax");
+        assert!(cookies.path.is_none() && cookies.secure.is_none());
+
+        assert!(
+            toml::from_str::<FileConfig>(
+                r#"
+                [[proxy_manager.rules]]
+                protocol = "https"
+                host = "app.example.com"
+                target_host = "127.0.0.1"
+                cookies = { same_site = "sometimes" }
+                "#,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rule_request_body() {
+        let cfg: FileConfig = toml::from_str(
+            r#"
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "api.example.com"
+            target_host = "127.0.0.1"
+
+            [proxy_manager.rules.request_body]
+            remove = ["$.debug"]
+            set = { "$.user.role" = "admin", "$.limits.max" = 100 }
+            patch = [{ op = "add", path = "/flags/-", value = "beta" }]
+            form = { set = { debug = "1" } }
+            "#,
+        )
+        .unwrap();
+
+        let rules = cfg.proxy_manager.unwrap().rules.unwrap();
+        let body = rules[0].request_body.as_ref().unwrap();
+        assert_eq!(body.remove[0].to_string(), "$.debug");
+        let value = |path: &str| {
+            body.set
+                .iter()
+                .find(|(p, _)| p.to_string() == path)
+                .map(|(_, v)| v.clone())
+                .unwrap()
+        };
+        assert_eq!(value("$.user.role").as_str(), Some("admin"));
+        assert_eq!(value("$.limits.max").as_i64(), Some(100));
+        assert_eq!(body.patch.as_ref().unwrap().0.len(), 1);
+        assert!(!body.form.is_empty());
+
+        let invalid: Result<FileConfig, _> = toml::from_str(
+            r#"
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "api.example.com"
+            target_host = "127.0.0.1"
+            request_body = { remove = ["debug"] }
+            "#,
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_relative_paths_follow_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("proxy-fork.toml");
+        fs_err::write(
+            &path,
+            r#"
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "api.example.com"
+            mock = { body_file = "mocks/order.json" }
+
+            [[proxy_manager.rules]]
+            protocol = "https"
+            host = "app.example.com"
+            target_protocol = "file"
+            target_host = "dist"
+
+            [[proxy_manager.rules]]
+            protocol = "http"
+            host = "gunicorn.local"
+            target_protocol = "unix"
+            target_host = "run/app.sock"
+
+            [[proxy_manager.rules]]
+            protocol = "http"
+            host = "api.example.com"
+            target_host = "localhost"
+            "#,
+        )
+        .unwrap();
+        let global = proxy_fork_cli::args::GlobalConfigArgs {
+            config: Some(path),
+            ..Default::default()
+        };
+
+        let cfg = load_start_proxy_config(&global, &Default::default()).unwrap();
+        let mock = cfg.proxy_manager.rules[0].mock.as_ref().unwrap();
+        assert_eq!(
+            mock.body_file.as_deref(),
+            Some(dir.path().join("mocks/order.json").as_path())
+        );
+        let rules = &cfg.proxy_manager.rules;
+        assert_eq!(
+            Path::new(&rules[1].target_host),
+            dir.path().join("dist").as_path()
+        );
+        assert_eq!(
+            Path::new(&rules[2].target_host),
+            dir.path().join("run/app.sock").as_path()
+        );
+        // 普通主机名不受影响
+        assert_eq!(rules[3].target_host, "localhost");
+    }
 }
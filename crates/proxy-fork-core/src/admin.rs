@@ -0,0 +1,300 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use derive_builder::Builder;
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::{Method, Request, Response, StatusCode};
+use hudsucker::Body;
+use hudsucker::hyper::service::service_fn;
+use hudsucker::hyper_util::rt::{TokioExecutor, TokioIo};
+use hudsucker::hyper_util::server::conn::auto::Builder as ServerBuilder;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+use tracing::{debug, error, info};
+
+use crate::body_rewrite::read_body;
+use crate::url_encoding::percent_decode;
+use crate::{ProxyManager, ProxyRule};
+
+/// 管理接口请求体大小上限
+const MAX_ADMIN_BODY_SIZE: usize = 1024 * 1024;
+
+type ApiResult = Result<(StatusCode, Value), (StatusCode, String)>;
+
+/// 运行时管理规则的 REST 接口（JSON），与代理共享同一个 [`ProxyManager`]
+///
+/// 除 `GET /health` 外均需携带 `Authorization: Bearer <token>`：
+///
+/// | 方法与路径 | 说明 |
+/// |---|---|
+/// | `GET /health` | 健康检查 |
+/// | `GET /rules` | 列出规则 |
+/// | `POST /rules` | 添加规则（DSL 字符串或规则对象） |
+/// | `GET /rules/{id}` | 查看规则 |
+/// | `PUT /rules/{id}` | 替换规则 |
+/// | `DELETE /rules/{id}` | 删除规则 |
+/// | `POST /rules/{id}/enable`、`POST /rules/{id}/disable` | 单独启用/禁用规则 |
+/// | `GET /stats`、`POST /stats/reset` | 读取/重置匹配统计 |
+/// | `POST /cache/clear` | 清空匹配缓存 |
+///
+/// `{id}` 为规则的标识（见 [`ProxyRule::id`]），可按 URL 编码传入，其中的 `#` 需编码为 `%23`
+#[derive(Clone, Builder)]
+#[builder(pattern = "owned", name = "AdminServerBuilder")]
+pub struct AdminServer {
+    // 与代理共享的规则管理器（必须）
+    proxy_manager: Arc<RwLock<ProxyManager>>,
+    // 访问令牌（必须）；为空时拒绝所有需要认证的请求
+    #[builder(setter(into))]
+    token: String,
+}
+
+impl AdminServer {
+    /// 随机生成的访问令牌（32 位十六进制）
+    pub fn random_token() -> String {
+        let bytes: [u8; 16] = StdRng::from_os_rng().random();
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// 在 `listener` 上提供管理接口（HTTP/1.1 与 HTTP/2）
+    pub async fn serve(self, listener: TcpListener) {
+        let server = ServerBuilder::new(TokioExecutor::new());
+        loop {
+            let (stream, addr) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!("Failed to accept admin connection: {}", e);
+                    continue;
+                }
+            };
+            let admin = self.clone();
+            let server = server.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |req: Request<_>| {
+                    let admin = admin.clone();
+                    async move { Ok::<_, Infallible>(admin.handle(req.map(Body::from)).await) }
+                });
+                if let Err(e) = server.serve_connection(TokioIo::new(stream), service).await {
+                    debug!("Admin connection from {} closed: {}", addr, e);
+                }
+            });
+        }
+    }
+
+    /// 处理一个管理请求
+    pub async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        if method == Method::GET && path == "/health" {
+            return json_response(StatusCode::OK, &json!({ "status": "ok" }));
+        }
+        if !self.authorized(&req) {
+            return error_response(StatusCode::UNAUTHORIZED, "missing or invalid token");
+        }
+
+        let body = req.into_body();
+        let result = match (&method, path.as_str()) {
+            (&Method::GET, "/rules") => self.list_rules().await,
+            (&Method::POST, "/rules") => self.add_rule(body).await,
+            (&Method::GET, "/stats") => self.stats().await,
+            (&Method::POST, "/stats/reset") => {
+                self.proxy_manager.read().await.reset_stats().await;
+                info!("Admin: reset stats");
+                Ok((StatusCode::OK, json!({ "status": "ok" })))
+            }
+            (&Method::POST, "/cache/clear") => {
+                self.proxy_manager.read().await.clear_cache().await;
+                info!("Admin: cleared cache");
+                Ok((StatusCode::OK, json!({ "status": "ok" })))
+            }
+            (_, path) => match path.strip_prefix("/rules/") {
+                Some(rest) => self.rule_route(&method, rest, body).await,
+                None => Err((StatusCode::NOT_FOUND, format!("no route for {}", path))),
+            },
+        };
+        match result {
+            Ok((status, value)) => json_response(status, &value),
+            Err((status, message)) => error_response(status, &message),
+        }
+    }
+
+    /// `/rules/{id}` 与 `/rules/{id}/enable|disable`
+    async fn rule_route(&self, method: &Method, rest: &str, body: Body) -> ApiResult {
+        let (id, action) = match rest.rsplit_once('/') {
+            Some((id, action @ ("enable" | "disable"))) => (id, Some(action)),
+            _ => (rest, None),
+        };
+        let id = percent_decode(id);
+        match (method, action) {
+            (&Method::GET, None) => {
+                let manager = self.proxy_manager.read().await;
+                let rule = manager.get_rule(&id).ok_or_else(|| not_found(&id))?;
+                Ok((StatusCode::OK, rule_entry(&manager, &rule)))
+            }
+            (&Method::PUT, None) => {
+                let rule = parse_rule(body).await?;
+                let new_id = rule.id();
+                let mut manager = self.proxy_manager.write().await;
+                let old = manager.get_rule(&id).ok_or_else(|| not_found(&id))?;
+                if new_id != old.id() && manager.get_rule(&new_id).is_some() {
+                    return Err(conflict(&new_id));
+                }
+                manager.replace_rule(&id, rule).await;
+                manager.mark_runtime_modified();
+                info!("Admin: replaced rule {} with {}", id, new_id);
+                let rule = manager
+                    .get_rule(&new_id)
+                    .ok_or_else(|| not_found(&new_id))?;
+                Ok((StatusCode::OK, rule_entry(&manager, &rule)))
+            }
+            (&Method::DELETE, None) => {
+                let mut manager = self.proxy_manager.write().await;
+                let rule = manager
+                    .remove_rule(&id)
+                    .await
+                    .ok_or_else(|| not_found(&id))?;
+                manager.mark_runtime_modified();
+                info!("Admin: removed rule {}", id);
+                Ok((
+                    StatusCode::OK,
+                    json!({ "id": rule.id(), "rule": rule.to_string() }),
+                ))
+            }
+            (&Method::POST, Some(action)) => {
+                let mut manager = self.proxy_manager.write().await;
+                let rule = manager.get_rule(&id).ok_or_else(|| not_found(&id))?;
+                manager.set_rule_enabled(&id, action == "enable").await;
+                manager.mark_runtime_modified();
+                info!("Admin: {}d rule {}", action, id);
+                Ok((StatusCode::OK, rule_entry(&manager, &rule)))
+            }
+            _ => Err((
+                StatusCode::METHOD_NOT_ALLOWED,
+                format!("method {} not allowed", method),
+            )),
+        }
+    }
+
+    async fn list_rules(&self) -> ApiResult {
+        let manager = self.proxy_manager.read().await;
+        let rules: Vec<Value> = manager
+            .all_rules()
+            .iter()
+            .map(|rule| rule_entry(&manager, rule))
+            .collect();
+        Ok((StatusCode::OK, json!({ "rules": rules })))
+    }
+
+    async fn add_rule(&self, body: Body) -> ApiResult {
+        let rule = parse_rule(body).await?;
+        let id = rule.id();
+        let mut manager = self.proxy_manager.write().await;
+        if manager.get_rule(&id).is_some() {
+            return Err(conflict(&id));
+        }
+        manager.add_proxy_rule(rule.clone()).await;
+        manager.mark_runtime_modified();
+        info!("Admin: added rule {}", rule);
+        Ok((StatusCode::CREATED, rule_entry(&manager, &rule)))
+    }
+
+    async fn stats(&self) -> ApiResult {
+        let manager = self.proxy_manager.read().await;
+        let stats = manager.stats().await;
+        Ok((
+            StatusCode::OK,
+            json!({
+                "total_lookups": stats.total_lookups,
+                "cache_hits": stats.cache_hits,
+                "exact_hits": stats.exact_hits,
+                "pattern_hits": stats.pattern_hits,
+                "misses": stats.misses,
+                "hit_rate": stats.hit_rate(),
+                "cache_hit_rate": stats.cache_hit_rate(),
+                "exact_rules": manager.exact_rule_count(),
+                "pattern_rules": manager.pattern_rule_count(),
+            }),
+        ))
+    }
+
+    fn authorized(&self, req: &Request<Body>) -> bool {
+        let Some(token) = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        // 逐字节比较全部内容，耗时与不匹配的位置无关
+        !self.token.is_empty()
+            && token.len() == self.token.len()
+            && token
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+/// 列表与单条规则的表示：`enabled` 为规则本身的状态，`active` 还考虑所在分组
+fn rule_entry(manager: &ProxyManager, rule: &ProxyRule) -> Value {
+    let id = rule.id();
+    let enabled = !manager.is_rule_disabled(&id);
+    let group_enabled = rule
+        .group
+        .as_deref()
+        .is_none_or(|group| manager.is_group_enabled(group));
+    json!({
+        "id": id,
+        "rule": rule.to_string(),
+        "group": rule.group,
+        "enabled": enabled,
+        "active": enabled && group_enabled,
+    })
+}
+
+/// 请求体中的规则：DSL 字符串，或（启用 `serde` 时）规则对象
+async fn parse_rule(body: Body) -> Result<ProxyRule, (StatusCode, String)> {
+    let data = read_body(body, MAX_ADMIN_BODY_SIZE).await.map_err(|_| {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("body exceeds {} bytes", MAX_ADMIN_BODY_SIZE),
+        )
+    })?;
+    let value: Value = serde_json::from_slice(&data)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid JSON: {}", e)))?;
+    let rule = match value {
+        Value::String(dsl) => dsl.parse(),
+        #[cfg(feature = "serde")]
+        value => serde_json::from_value(value).map_err(|e| e.to_string()),
+        #[cfg(not(feature = "serde"))]
+        _ => Err("rule must be a DSL string".to_string()),
+    };
+    rule.map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid rule: {}", e)))
+}
+
+fn not_found(id: &str) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, format!("no rule with id '{}'", id))
+}
+
+fn conflict(id: &str) -> (StatusCode, String) {
+    (
+        StatusCode::CONFLICT,
+        format!("a rule with id '{}' already exists", id),
+    )
+}
+
+fn json_response(status: StatusCode, value: &Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(value.to_string()))
+        .expect("Failed to build response")
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(status, &json!({ "error": message }))
+}
@@ -0,0 +1,268 @@
+use http::header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, HeaderValue};
+use http::{HeaderMap, Response, StatusCode};
+use http_body_util::{BodyDataStream, BodyExt};
+use hudsucker::Body;
+use hudsucker::decode_response;
+use hudsucker::futures::{StreamExt, stream};
+use hudsucker::hyper::body::Bytes;
+use tracing::{debug, error};
+
+use crate::{PathRegex, PatternMatcher};
+
+/// 改写的响应体大小上限默认值（解压后）
+pub const DEFAULT_BODY_REWRITE_MAX_SIZE: usize = 10 * 1024 * 1024;
+
+/// 响应体改写：字面量替换、正则替换与 HTML 片段注入
+///
+/// ```toml
+/// response_body = { replace = { "https://cdn.example.com" = "http://localhost:3000" }, inject_head = "<script src=\"http://localhost:3000/dev.js\"></script>" }
+/// ```
+///
+/// 按 字面量替换 -> 正则替换 -> 注入 的顺序应用；压缩的响应体先解压，改写后以未压缩形式返回
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct BodyRewrite {
+    /// 字面量替换：原文本 -> 替换文本（替换所有出现）
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub replace: Vec<(String, String)>,
+    /// 正则替换：`s#正则#替换模板#`（替换所有匹配）
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub regex: Vec<PathRegex>,
+    /// 插入到 HTML 的 `</head>` 之前的片段
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inject_head: Option<String>,
+    /// 插入到 HTML 的 `</body>` 之前的片段
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inject_body: Option<String>,
+    /// 需要改写的 `Content-Type`（不区分大小写，支持通配符与 `re:` 正则）
+    pub content_types: Vec<PatternMatcher>,
+    /// 改写的响应体大小上限（字节，解压后），超过时不改写
+    pub max_size: usize,
+}
+
+impl Default for BodyRewrite {
+    fn default() -> Self {
+        Self {
+            replace: Vec::new(),
+            regex: Vec::new(),
+            inject_head: None,
+            inject_body: None,
+            content_types: [
+                "text/*",
+                "application/javascript",
+                "application/json",
+                "application/xml",
+                "*+json",
+                "*+xml",
+            ]
+            .into_iter()
+            .map(|ty| PatternMatcher::from_str(ty).expect("valid content type pattern"))
+            .collect(),
+            max_size: DEFAULT_BODY_REWRITE_MAX_SIZE,
+        }
+    }
+}
+
+impl BodyRewrite {
+    pub fn is_empty(&self) -> bool {
+        self.replace.is_empty()
+            && self.regex.is_empty()
+            && self.inject_head.is_none()
+            && self.inject_body.is_none()
+    }
+
+    /// 添加字面量替换
+    pub fn replace(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.replace.push((from.into(), to.into()));
+        self
+    }
+
+    /// 添加正则替换，`expression` 形如 `s#正则#替换模板#`
+    pub fn regex(mut self, expression: &str) -> Result<Self, String> {
+        self.regex.push(expression.parse()?);
+        Ok(self)
+    }
+
+    /// 设置插入到 `</head>` 之前的片段
+    pub fn inject_head(mut self, snippet: impl Into<String>) -> Self {
+        self.inject_head = Some(snippet.into());
+        self
+    }
+
+    /// 设置插入到 `</body>` 之前的片段
+    pub fn inject_body(mut self, snippet: impl Into<String>) -> Self {
+        self.inject_body = Some(snippet.into());
+        self
+    }
+
+    /// 替换需要改写的 `Content-Type` 列表
+    pub fn content_types(mut self, patterns: &[&str]) -> Result<Self, regex::Error> {
+        self.content_types = patterns
+            .iter()
+            .map(|ty| PatternMatcher::from_str(ty))
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// 改写文本；`html` 为 true 时注入片段（找不到对应标签时跳过）
+    pub fn rewrite_text(&self, text: &str, html: bool) -> String {
+        let mut text = text.to_string();
+        for (from, to) in &self.replace {
+            if !from.is_empty() {
+                text = text.replace(from.as_str(), to);
+            }
+        }
+        for regex in &self.regex {
+            if let Some(replaced) = regex.apply_all(&text) {
+                text = replaced;
+            }
+        }
+        if html {
+            // 小写化只改变 ASCII 字节，下标与原文一致
+            if let Some(snippet) = &self.inject_head
+                && let Some(idx) = text.to_ascii_lowercase().find("</head>")
+            {
+                text.insert_str(idx, snippet);
+            }
+            if let Some(snippet) = &self.inject_body
+                && let Some(idx) = text.to_ascii_lowercase().rfind("</body>")
+            {
+                text.insert_str(idx, snippet);
+            }
+        }
+        text
+    }
+
+    /// 对响应应用改写
+    ///
+    /// 以下情况不改写：没有响应体的状态码、`Content-Type` 不匹配、压缩方式不支持、
+    /// 响应体超过 `max_size` 或不是 UTF-8 文本（后两种情况下响应体已被解压）
+    pub async fn apply(&self, res: Response<Body>) -> Response<Body> {
+        if self.is_empty() || !self.should_rewrite(&res) {
+            return res;
+        }
+        let html = media_type(res.headers()).is_some_and(|ty| ty.eq_ignore_ascii_case("text/html"));
+
+        let res = match decode_response(res) {
+            Ok(res) => res,
+            Err(e) => {
+                error!("Failed to decode response body for rewriting: {}", e);
+                return Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(Body::empty())
+                    .expect("Failed to build response");
+            }
+        };
+
+        let (mut parts, body) = res.into_parts();
+        let data = match read_body(body, self.max_size).await {
+            Ok(data) => data,
+            Err(body) => {
+                parts.headers.remove(CONTENT_LENGTH);
+                return Response::from_parts(parts, body);
+            }
+        };
+
+        let data = match String::from_utf8(data) {
+            Ok(text) => self.rewrite_text(&text, html).into_bytes(),
+            Err(e) => {
+                debug!("Response body is not UTF-8, not rewriting");
+                e.into_bytes()
+            }
+        };
+        parts
+            .headers
+            .insert(CONTENT_LENGTH, HeaderValue::from(data.len()));
+        Response::from_parts(parts, Body::from(data))
+    }
+
+    fn should_rewrite(&self, res: &Response<Body>) -> bool {
+        let status = res.status();
+        if status.is_informational()
+            || matches!(
+                status,
+                StatusCode::NO_CONTENT | StatusCode::PARTIAL_CONTENT | StatusCode::NOT_MODIFIED
+            )
+        {
+            return false;
+        }
+
+        let Some(media_type) = media_type(res.headers()) else {
+            return false;
+        };
+        if !self
+            .content_types
+            .iter()
+            .any(|ty| ty.matches_ignore_ascii_case(media_type))
+        {
+            return false;
+        }
+
+        if !supported_encoding(res.headers()) {
+            debug!("Unsupported content encoding, not rewriting response body");
+            return false;
+        }
+
+        // 压缩的响应体解压后更大，这里只排除明显超限的响应
+        let length = res
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok());
+        length.is_none_or(|len| len <= self.max_size)
+    }
+}
+
+/// 读取不超过 `max_size` 字节的完整消息体
+///
+/// 超过上限或读取出错时不再等待，返回内容相同的流式消息体（已读取的部分在前）
+pub(crate) async fn read_body(mut body: Body, max_size: usize) -> Result<Vec<u8>, Body> {
+    let mut data = Vec::new();
+    while let Some(frame) = body.frame().await {
+        let chunk = match frame {
+            Ok(frame) => match frame.into_data() {
+                Ok(chunk) => chunk,
+                Err(_) => continue,
+            },
+            Err(e) => {
+                error!("Failed to read body for rewriting: {}", e);
+                let chunks = stream::iter([Ok(Bytes::from(data)), Err(e)]);
+                return Err(Body::from_stream(chunks));
+            }
+        };
+        data.extend_from_slice(&chunk);
+        if data.len() > max_size {
+            debug!("Body exceeds {} bytes, not rewriting", max_size);
+            let prefix = stream::once(async move { Ok(Bytes::from(data)) });
+            return Err(Body::from_stream(prefix.chain(BodyDataStream::new(body))));
+        }
+    }
+    Ok(data)
+}
+
+/// `Content-Encoding` 均可由 hudsucker 解压（与其解码一致，区分大小写）
+pub(crate) fn supported_encoding(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CONTENT_ENCODING)
+        .iter()
+        .flat_map(|v| v.to_str().unwrap_or("unsupported").split(','))
+        .all(|encoding| {
+            matches!(
+                encoding.trim(),
+                "gzip" | "x-gzip" | "deflate" | "br" | "zstd" | "identity"
+            )
+        })
+}
+
+/// 消息的媒体类型（不含参数），如 `text/html; charset=utf-8` -> `text/html`
+pub(crate) fn media_type(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    Some(value.split(';').next().unwrap_or(value).trim())
+}
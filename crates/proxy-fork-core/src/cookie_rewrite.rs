@@ -0,0 +1,271 @@
+use http::header::{HeaderValue, SET_COOKIE};
+use http::{HeaderMap, Uri};
+
+/// `Set-Cookie` 的 `SameSite` 属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl std::str::FromStr for SameSite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "strict" => Ok(Self::Strict),
+            "lax" => Ok(Self::Lax),
+            "none" => Ok(Self::None),
+            other => Err(format!(
+                "invalid SameSite '{}': expected Strict, Lax or None",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SameSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Strict => write!(f, "Strict"),
+            Self::Lax => write!(f, "Lax"),
+            Self::None => write!(f, "None"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SameSite {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SameSite {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 改写 `Set-Cookie` 时参照的原始请求：浏览器看到的主机、协议，以及转发时路径前缀的对应关系
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieOrigin {
+    /// 原始请求的主机（不含端口）
+    pub host: String,
+    /// 原始请求是否为 HTTPS
+    pub secure: bool,
+    /// 转发后的路径前缀 -> 原始路径前缀（两者相同时为 None）
+    pub path_prefix: Option<(String, String)>,
+}
+
+impl CookieOrigin {
+    /// 根据原始 URI 与转发 URI 推导：两条路径去掉共同的后缀（按 `/` 分段）后剩下的部分即前缀的对应关系
+    pub fn new(original: &Uri, forwarded: &Uri) -> Self {
+        let original_path = original.path();
+        let forwarded_path = forwarded.path();
+        let common = original_path
+            .bytes()
+            .rev()
+            .zip(forwarded_path.bytes().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        // 共同后缀从某个 `/` 开始，避免把一个路径段拆开
+        let suffix = &original_path[original_path.len() - common..];
+        let common = suffix.find('/').map_or(0, |idx| common - idx);
+
+        let original_prefix = &original_path[..original_path.len() - common];
+        let forwarded_prefix = &forwarded_path[..forwarded_path.len() - common];
+        Self {
+            host: original.host().unwrap_or_default().to_string(),
+            secure: original.scheme_str() == Some("https"),
+            path_prefix: (original_prefix != forwarded_prefix)
+                .then(|| (forwarded_prefix.to_string(), original_prefix.to_string())),
+        }
+    }
+}
+
+/// `Set-Cookie` 改写：让改道后的服务端设置的 Cookie 在浏览器中对原始站点生效
+///
+/// ```toml
+/// cookies = { domain = "", path = "/", secure = false, same_site = "Lax" }
+/// ```
+///
+/// 默认自动改写：
+/// - `Domain` 不包含原始主机时删除（成为原始主机的 host-only Cookie）
+/// - `Path` 按转发时的路径前缀映射回原始路径
+/// - 原始请求为 HTTP 时删除 `Secure`（`SameSite=None` 随之改为 `Lax`）；为 HTTPS 且 `SameSite=None` 时补上 `Secure`
+///
+/// 显式配置的属性在自动改写之后应用
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct CookieRewrite {
+    /// 是否按原始请求自动改写（默认开启）
+    pub auto: bool,
+    /// 固定的 `Domain`；空字符串表示删除
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub domain: Option<String>,
+    /// 固定的 `Path`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub path: Option<String>,
+    /// 添加（true）或删除（false）`Secure`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub secure: Option<bool>,
+    /// 固定的 `SameSite`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub same_site: Option<SameSite>,
+}
+
+impl Default for CookieRewrite {
+    fn default() -> Self {
+        Self {
+            auto: true,
+            domain: None,
+            path: None,
+            secure: None,
+            same_site: None,
+        }
+    }
+}
+
+impl CookieRewrite {
+    /// 不做任何改写
+    pub fn disabled() -> Self {
+        Self {
+            auto: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// 改写响应中的所有 `Set-Cookie`
+    pub fn apply(&self, headers: &mut HeaderMap, origin: &CookieOrigin) {
+        if !headers.contains_key(SET_COOKIE) {
+            return;
+        }
+        let cookies: Vec<HeaderValue> = headers
+            .get_all(SET_COOKIE)
+            .iter()
+            .map(|value| {
+                value
+                    .to_str()
+                    .ok()
+                    .and_then(|cookie| HeaderValue::from_str(&self.rewrite(cookie, origin)).ok())
+                    .unwrap_or_else(|| value.clone())
+            })
+            .collect();
+        headers.remove(SET_COOKIE);
+        for cookie in cookies {
+            headers.append(SET_COOKIE, cookie);
+        }
+    }
+
+    /// 改写单个 `Set-Cookie` 的取值，未涉及的属性保持原样
+    pub fn rewrite(&self, set_cookie: &str, origin: &CookieOrigin) -> String {
+        let mut parts = set_cookie.split(';').map(str::trim);
+        let name_value = parts.next().unwrap_or_default();
+        let mut attributes: Vec<(String, Option<String>)> = parts
+            .filter(|attr| !attr.is_empty())
+            .map(|attr| match attr.split_once('=') {
+                Some((name, value)) => (name.trim().to_string(), Some(value.trim().to_string())),
+                None => (attr.to_string(), None),
+            })
+            .collect();
+
+        if self.auto {
+            Self::rewrite_auto(&mut attributes, origin);
+        }
+        match self.domain.as_deref() {
+            Some("") => remove_attr(&mut attributes, "Domain"),
+            Some(domain) => set_attr(&mut attributes, "Domain", Some(domain)),
+            None => {}
+        }
+        if let Some(path) = &self.path {
+            set_attr(&mut attributes, "Path", Some(path));
+        }
+        match self.secure {
+            Some(true) => set_attr(&mut attributes, "Secure", None),
+            Some(false) => remove_attr(&mut attributes, "Secure"),
+            None => {}
+        }
+        if let Some(same_site) = self.same_site {
+            set_attr(&mut attributes, "SameSite", Some(&same_site.to_string()));
+        }
+
+        let mut cookie = name_value.to_string();
+        for (name, value) in attributes {
+            cookie.push_str("; ");
+            cookie.push_str(&name);
+            if let Some(value) = value {
+                cookie.push('=');
+                cookie.push_str(&value);
+            }
+        }
+        cookie
+    }
+
+    fn rewrite_auto(attributes: &mut Vec<(String, Option<String>)>, origin: &CookieOrigin) {
+        if let Some(domain) = get_attr(attributes, "Domain") {
+            let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+            let host = origin.host.to_ascii_lowercase();
+            if host != domain && !host.ends_with(&format!(".{}", domain)) {
+                remove_attr(attributes, "Domain");
+            }
+        }
+
+        if let (Some(path), Some((forwarded, original))) =
+            (get_attr(attributes, "Path"), &origin.path_prefix)
+            && let Some(rest) = path.strip_prefix(forwarded.as_str())
+            && (rest.is_empty() || rest.starts_with('/') || forwarded.ends_with('/'))
+        {
+            let path = format!("{}{}", original.trim_end_matches('/'), rest);
+            let path = if path.starts_with('/') {
+                path
+            } else {
+                format!("/{}", path)
+            };
+            set_attr(attributes, "Path", Some(&path));
+        }
+
+        let same_site_none =
+            get_attr(attributes, "SameSite").is_some_and(|v| v.eq_ignore_ascii_case("none"));
+        if !origin.secure {
+            remove_attr(attributes, "Secure");
+            // 没有 Secure 的 SameSite=None 会被浏览器拒绝
+            if same_site_none {
+                set_attr(attributes, "SameSite", Some("Lax"));
+            }
+        } else if same_site_none {
+            set_attr(attributes, "Secure", None);
+        }
+    }
+}
+
+fn get_attr(attributes: &[(String, Option<String>)], name: &str) -> Option<String> {
+    attributes
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .and_then(|(_, v)| v.clone())
+}
+
+fn remove_attr(attributes: &mut Vec<(String, Option<String>)>, name: &str) {
+    attributes.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+}
+
+/// 覆盖已有属性的取值（保留其位置），不存在时追加
+fn set_attr(attributes: &mut Vec<(String, Option<String>)>, name: &str, value: Option<&str>) {
+    let value = value.map(str::to_string);
+    match attributes
+        .iter_mut()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+    {
+        Some((_, v)) => *v = value,
+        None => attributes.push((name.to_string(), value)),
+    }
+}
@@ -0,0 +1,181 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::header::CONTENT_LENGTH;
+use http::{Response, StatusCode};
+use http_body_util::BodyDataStream;
+use hudsucker::Body;
+use hudsucker::futures::{StreamExt, stream};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// 按规则注入的故障：附加延迟、按概率返回错误、中断响应体或断开连接
+///
+/// ```toml
+/// fault = { latency_ms = [500, 2000], error_rate = 0.1, error_status = 503, abort_rate = 0.05, reset_rate = 0.01 }
+/// ```
+///
+/// 每个请求先附加延迟，再依次按 `reset_rate`、`error_rate`、`abort_rate` 抽样，至多触发一种故障
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct FaultInjection {
+    /// 附加延迟的范围（毫秒，闭区间），在其中均匀抽样
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub latency_ms: Option<(u64, u64)>,
+    /// 直接返回 `error_status` 的概率（0.0 - 1.0）
+    pub error_rate: f64,
+    /// 注入错误时的状态码（默认 503）
+    pub error_status: u16,
+    /// 转发后只返回部分响应体即中断的概率
+    pub abort_rate: f64,
+    /// 不返回响应直接断开连接的概率
+    pub reset_rate: f64,
+}
+
+impl Default for FaultInjection {
+    fn default() -> Self {
+        Self {
+            latency_ms: None,
+            error_rate: 0.0,
+            error_status: 503,
+            abort_rate: 0.0,
+            reset_rate: 0.0,
+        }
+    }
+}
+
+/// 单个请求抽样得到的故障
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// 返回指定状态码
+    Error(StatusCode),
+    /// 响应体中途中断
+    Abort,
+    /// 断开连接
+    Reset,
+}
+
+impl FaultInjection {
+    pub fn is_empty(&self) -> bool {
+        self.latency_ms.is_none()
+            && self.error_rate <= 0.0
+            && self.abort_rate <= 0.0
+            && self.reset_rate <= 0.0
+    }
+
+    /// 校验延迟范围、概率与状态码
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some((min, max)) = self.latency_ms
+            && min > max
+        {
+            return Err(format!("invalid latency range: {}-{} ms", min, max));
+        }
+        for (name, rate) in [
+            ("error_rate", self.error_rate),
+            ("abort_rate", self.abort_rate),
+            ("reset_rate", self.reset_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(format!("{} must be between 0 and 1: {}", name, rate));
+            }
+        }
+        StatusCode::from_u16(self.error_status)
+            .map_err(|_| format!("invalid error status: {}", self.error_status))?;
+        Ok(())
+    }
+
+    /// 为一个请求抽样延迟与故障
+    pub fn sample(&self, rng: &FaultRng) -> (Option<Duration>, Option<Fault>) {
+        let mut rng = rng.0.lock().unwrap_or_else(|e| e.into_inner());
+        let latency = self
+            .latency_ms
+            .map(|(min, max)| Duration::from_millis(rng.random_range(min..=max.max(min))));
+
+        let fault = if rng.random_bool(self.reset_rate.clamp(0.0, 1.0)) {
+            Some(Fault::Reset)
+        } else if rng.random_bool(self.error_rate.clamp(0.0, 1.0)) {
+            Some(Fault::Error(
+                StatusCode::from_u16(self.error_status).unwrap_or(StatusCode::SERVICE_UNAVAILABLE),
+            ))
+        } else if rng.random_bool(self.abort_rate.clamp(0.0, 1.0)) {
+            Some(Fault::Abort)
+        } else {
+            None
+        };
+        (latency, fault)
+    }
+}
+
+/// 故障注入共用的随机数生成器
+///
+/// 指定种子时同样的请求序列得到同样的故障序列（并发请求的先后顺序本身不确定）
+#[derive(Debug, Clone)]
+pub struct FaultRng(Arc<Mutex<StdRng>>);
+
+impl FaultRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self(Arc::new(Mutex::new(StdRng::seed_from_u64(seed))))
+    }
+}
+
+impl Default for FaultRng {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(StdRng::from_os_rng())))
+    }
+}
+
+/// 注入错误状态码的响应
+pub(crate) fn error_response(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .expect("Failed to build response")
+}
+
+/// 只返回前一半响应体后以错误结束；长度未知时取第一个数据块的一半
+///
+/// `Content-Length` 保持不变，客户端会收到不完整的响应
+pub(crate) fn abort_mid_body(res: Response<Body>) -> Response<Body> {
+    let limit = res
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(|len| len / 2);
+    let (parts, body) = res.into_parts();
+
+    // 状态：(响应体, 剩余可发送的字节数, 是否已截断)
+    let stream = stream::unfold(
+        Some((BodyDataStream::new(body), limit, false)),
+        |state| async move {
+            let (mut body, remaining, truncated) = state?;
+            let aborted = || {
+                Err(hudsucker::Error::from(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "response aborted by fault injection",
+                )))
+            };
+            if truncated {
+                // 先让出一次，使已发送的部分响应刷新到客户端后再中断
+                tokio::task::yield_now().await;
+                return Some((aborted(), None));
+            }
+            match body.next().await {
+                Some(Ok(mut chunk)) => {
+                    let remaining = remaining.unwrap_or(chunk.len() as u64 / 2);
+                    if (chunk.len() as u64) < remaining {
+                        let remaining = remaining - chunk.len() as u64;
+                        return Some((Ok(chunk), Some((body, Some(remaining), false))));
+                    }
+                    chunk.truncate(usize::try_from(remaining).unwrap_or(usize::MAX));
+                    Some((Ok(chunk), Some((body, Some(0), true))))
+                }
+                Some(Err(e)) => Some((Err(e), None)),
+                None => Some((aborted(), None)),
+            }
+        },
+    );
+    Response::from_parts(parts, Body::from_stream(stream))
+}
@@ -0,0 +1,376 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use fs_err as fs;
+use http::header::{CONTENT_ENCODING, CONTENT_TYPE, LOCATION};
+use http::{HeaderMap, Request, Response, Uri, Version};
+use http_body_util::BodyDataStream;
+use hudsucker::Body;
+use hudsucker::futures::{StreamExt, stream};
+use hudsucker::hyper::body::{Body as _, Bytes};
+use serde_json::{Value, json};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// HAR 中记录的消息体大小上限默认值
+pub const DEFAULT_HAR_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// HAR 1.2 记录器：收集经过 [`crate::ProxyHandler`] 的请求与响应
+///
+/// 克隆的记录器共享同一份记录；消息体边转发边复制（超过 `max_body_size` 的部分只计大小），
+/// 响应体传输结束后才写入一条记录
+///
+/// ```ignore
+/// let har = HarRecorder::new().max_body_size(64 * 1024);
+/// let handler = ProxyHandlerBuilder::default()
+///     .proxy_manager(manager)
+///     .har(Some(har.clone()))
+///     .build()?;
+/// // ...
+/// har.write_to("out.har")?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct HarRecorder {
+    entries: Arc<Mutex<Vec<Value>>>,
+    max_body_size: usize,
+}
+
+impl Default for HarRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HarRecorder {
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(Vec::new())),
+            max_body_size: DEFAULT_HAR_MAX_BODY_SIZE,
+        }
+    }
+
+    /// 设置记录的消息体大小上限（字节）
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// 已完成的记录（HAR 的 `log.entries`）
+    pub fn entries(&self) -> Vec<Value> {
+        self.lock().clone()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// 完整的 HAR 文档
+    pub fn to_har(&self) -> Value {
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": "proxy-fork",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "pages": [],
+                "entries": self.entries(),
+            }
+        })
+    }
+
+    /// 把 HAR 文档写入文件
+    pub fn write_to(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let har = serde_json::to_vec_pretty(&self.to_har())?;
+        fs::write(path.as_ref(), har)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Value>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// 开始记录一个请求：记录请求行与请求头，并复制转发中的请求体
+    pub(crate) fn start(&self, req: &mut Request<Body>) -> HarEntry {
+        let request_body = Arc::new(Mutex::new(Capture::default()));
+        if !req.body().is_end_stream() {
+            let body = std::mem::replace(req.body_mut(), Body::empty());
+            *req.body_mut() = tee_body(body, self.max_body_size, request_body.clone(), None);
+        }
+
+        HarEntry {
+            recorder: self.clone(),
+            started_at: OffsetDateTime::now_utc(),
+            start: Instant::now(),
+            request: json!({
+                "method": req.method().as_str(),
+                "url": req.uri().to_string(),
+                "httpVersion": http_version(req.version()),
+                "cookies": [],
+                "headers": headers(req.headers()),
+                "queryString": query_string(req.uri()),
+                "headersSize": -1,
+            }),
+            request_mime_type: mime_type(req.headers()),
+            request_body,
+            rule: None,
+            rewritten_url: None,
+        }
+    }
+}
+
+/// 进行中的一条记录
+#[derive(Debug, Clone)]
+pub(crate) struct HarEntry {
+    recorder: HarRecorder,
+    started_at: OffsetDateTime,
+    start: Instant,
+    request: Value,
+    request_mime_type: String,
+    request_body: Arc<Mutex<Capture>>,
+    rule: Option<String>,
+    rewritten_url: Option<String>,
+}
+
+impl HarEntry {
+    /// 记录命中规则的标识与改写后的地址
+    pub(crate) fn matched(&mut self, rule: String, rewritten_url: &Uri) {
+        self.rule = Some(rule);
+        self.rewritten_url = Some(rewritten_url.to_string());
+    }
+
+    /// 记录响应：复制转发中的响应体，传输结束（或被丢弃）时写入记录器
+    pub(crate) fn finish(self, res: Response<Body>) -> Response<Body> {
+        let wait = self.start.elapsed();
+        let response = json!({
+            "status": res.status().as_u16(),
+            "statusText": res.status().canonical_reason().unwrap_or_default(),
+            "httpVersion": http_version(res.version()),
+            "cookies": [],
+            "headers": headers(res.headers()),
+            "redirectURL": res
+                .headers()
+                .get(LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default(),
+            "headersSize": -1,
+        });
+        let mime_type = mime_type(res.headers());
+        let encoded = res.headers().contains_key(CONTENT_ENCODING);
+        let max_body_size = self.recorder.max_body_size;
+        let commit = move |body: Capture| {
+            let receive = self.start.elapsed().saturating_sub(wait);
+            self.commit(response, &mime_type, encoded, &body, wait, receive);
+        };
+
+        if res.body().is_end_stream() {
+            commit(Capture::default());
+            return res;
+        }
+        let (parts, body) = res.into_parts();
+        let capture = Arc::new(Mutex::new(Capture::default()));
+        let body = tee_body(body, max_body_size, capture, Some(Box::new(commit)));
+        Response::from_parts(parts, body)
+    }
+
+    fn commit(
+        self,
+        mut response: Value,
+        mime_type: &str,
+        encoded: bool,
+        body: &Capture,
+        wait: Duration,
+        receive: Duration,
+    ) {
+        let mut request = self.request;
+        let request_body = self
+            .request_body
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+        request["bodySize"] = json!(request_body.size);
+        if request_body.size > 0 {
+            let mut post_data = json!({ "mimeType": self.request_mime_type });
+            request_body.write_text(&mut post_data, false);
+            request["postData"] = post_data;
+        }
+
+        let mut content = json!({ "size": body.size, "mimeType": mime_type });
+        body.write_text(&mut content, encoded);
+        response["content"] = content;
+        response["bodySize"] = json!(body.size);
+
+        let wait_ms = millis(wait);
+        let receive_ms = millis(receive);
+        let mut entry = json!({
+            "startedDateTime": self.started_at.format(&Rfc3339).unwrap_or_default(),
+            "time": wait_ms + receive_ms,
+            "request": request,
+            "response": response,
+            "cache": {},
+            "timings": { "send": 0, "wait": wait_ms, "receive": receive_ms },
+        });
+        if let Some(rule) = self.rule {
+            entry["_rule"] = json!(rule);
+        }
+        if let Some(url) = self.rewritten_url {
+            entry["_rewrittenUrl"] = json!(url);
+        }
+        self.recorder.lock().push(entry);
+    }
+}
+
+/// 复制下来的消息体：最多 `limit` 字节的内容、完整大小，以及是否已读完
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Capture {
+    pub(crate) data: Vec<u8>,
+    pub(crate) size: usize,
+    pub(crate) complete: bool,
+}
+
+impl Capture {
+    fn push(&mut self, chunk: &Bytes, limit: usize) {
+        self.size += chunk.len();
+        let room = limit.saturating_sub(self.data.len());
+        self.data.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+
+    /// 消息体完整读完且没有被截断；按 `Content-Length` 发送的消息体读够长度即视为读完
+    pub(crate) fn is_whole(&self, content_length: Option<usize>) -> bool {
+        (self.complete || content_length == Some(self.size)) && self.data.len() == self.size
+    }
+
+    /// 写入 `text`：UTF-8 文本原样写入，压缩或二进制内容以 base64 写入
+    fn write_text(&self, target: &mut Value, encoded: bool) {
+        if self.data.is_empty() {
+            return;
+        }
+        match std::str::from_utf8(&self.data) {
+            Ok(text) if !encoded => target["text"] = json!(text),
+            _ => {
+                target["text"] = json!(BASE64.encode(&self.data));
+                target["encoding"] = json!("base64");
+            }
+        }
+        if self.data.len() < self.size {
+            target["_truncated"] = json!(true);
+        }
+    }
+}
+
+pub(crate) type OnEnd = Box<dyn FnOnce(Capture) + Send + Sync>;
+
+/// 消息体被读完或丢弃时把复制的内容交给回调
+struct TeeGuard {
+    capture: Arc<Mutex<Capture>>,
+    limit: usize,
+    on_end: Option<OnEnd>,
+}
+
+impl TeeGuard {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Capture> {
+        self.capture
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Drop for TeeGuard {
+    fn drop(&mut self) {
+        if let Some(on_end) = self.on_end.take() {
+            let capture = self.lock().clone();
+            on_end(capture);
+        }
+    }
+}
+
+/// 边转发边复制消息体，不缓冲完整内容
+pub(crate) fn tee_body(
+    body: Body,
+    limit: usize,
+    capture: Arc<Mutex<Capture>>,
+    on_end: Option<OnEnd>,
+) -> Body {
+    let guard = TeeGuard {
+        capture,
+        limit,
+        on_end,
+    };
+    let stream = stream::unfold(
+        Some((BodyDataStream::new(body), guard)),
+        |state| async move {
+            let (mut body, guard) = state?;
+            match body.next().await {
+                Some(Ok(chunk)) => {
+                    guard.lock().push(&chunk, guard.limit);
+                    Some((Ok(chunk), Some((body, guard))))
+                }
+                // 出错时丢弃 guard，记录未读完的内容
+                Some(Err(e)) => Some((Err(e), None)),
+                None => {
+                    guard.lock().complete = true;
+                    None
+                }
+            }
+        },
+    );
+    Body::from_stream(stream)
+}
+
+pub(crate) fn headers(headers: &HeaderMap) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            json!({
+                "name": name.as_str(),
+                "value": String::from_utf8_lossy(value.as_bytes()),
+            })
+        })
+        .collect()
+}
+
+fn query_string(uri: &Uri) -> Vec<Value> {
+    uri.query()
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            json!({ "name": name, "value": value })
+        })
+        .collect()
+}
+
+fn mime_type(headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn http_version(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_2 => "HTTP/2.0",
+        Version::HTTP_3 => "HTTP/3.0",
+        _ => "HTTP/1.1",
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,312 @@
+use http::header::{
+    ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE,
+    ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, CACHE_CONTROL,
+    CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY_REPORT_ONLY, ETAG, EXPIRES, HeaderName,
+    HeaderValue, LAST_MODIFIED, ORIGIN, PRAGMA, STRICT_TRANSPORT_SECURITY, VARY,
+};
+use http::{HeaderMap, Method, Request, Response, StatusCode};
+use hudsucker::Body;
+
+use crate::{PathRegex, PatternMatcher};
+
+/// 请求头/响应头改写：按 删除 -> 替换 -> 设置 -> 追加 的顺序应用
+///
+/// ```toml
+/// request_headers = { remove = ["If-None-Match"], replace = { Cookie = "s/session=[^;]+/session=dev/" }, set = { Authorization = "Bearer dev" }, append = { X-Feature = "beta" } }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct HeaderRewrite {
+    /// 删除的头名称（不区分大小写），支持精确、通配符（如 `x-debug-*`）与 `re:` 正则
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub remove: Vec<PatternMatcher>,
+    /// 对头的每个值做 sed 风格的正则替换：头名称 -> `s#正则#替换模板#`
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub replace: Vec<(String, PathRegex)>,
+    /// 设置头：覆盖同名头的所有值
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub set: Vec<(String, String)>,
+    /// 追加头：保留同名头已有的值
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub append: Vec<(String, String)>,
+}
+
+impl HeaderRewrite {
+    pub fn is_empty(&self) -> bool {
+        self.remove.is_empty()
+            && self.replace.is_empty()
+            && self.set.is_empty()
+            && self.append.is_empty()
+    }
+
+    /// 添加删除规则
+    pub fn remove(mut self, name: &str) -> Result<Self, regex::Error> {
+        self.remove.push(PatternMatcher::from_str(name)?);
+        Ok(self)
+    }
+
+    /// 添加正则替换规则，`expression` 形如 `s#正则#替换模板#`
+    pub fn replace(mut self, name: impl Into<String>, expression: &str) -> Result<Self, String> {
+        self.replace.push((name.into(), expression.parse()?));
+        Ok(self)
+    }
+
+    /// 添加设置规则
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set.push((name.into(), value.into()));
+        self
+    }
+
+    /// 添加追加规则
+    pub fn append(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.append.push((name.into(), value.into()));
+        self
+    }
+
+    /// 校验头名称与取值是否合法
+    pub fn validate(&self) -> Result<(), String> {
+        for name in self.replace.iter().map(|(name, _)| name) {
+            parse_name(name)?;
+        }
+        for (name, value) in self.set.iter().chain(&self.append) {
+            parse_name(name)?;
+            parse_value(value)?;
+        }
+        Ok(())
+    }
+
+    /// 对头部应用改写；不合法的名称或取值会被跳过（可先用 [`HeaderRewrite::validate`] 校验）
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        if !self.remove.is_empty() {
+            let removed: Vec<HeaderName> = headers
+                .keys()
+                .filter(|name| {
+                    self.remove
+                        .iter()
+                        .any(|m| m.matches_ignore_ascii_case(name.as_str()))
+                })
+                .cloned()
+                .collect();
+            for name in removed {
+                headers.remove(name);
+            }
+        }
+
+        for (name, regex) in &self.replace {
+            let Ok(name) = parse_name(name) else {
+                continue;
+            };
+            let values: Vec<HeaderValue> = headers
+                .get_all(&name)
+                .iter()
+                .map(|value| {
+                    value
+                        .to_str()
+                        .ok()
+                        .and_then(|v| regex.apply(v))
+                        .and_then(|v| HeaderValue::from_str(&v).ok())
+                        .unwrap_or_else(|| value.clone())
+                })
+                .collect();
+            headers.remove(&name);
+            for value in values {
+                headers.append(&name, value);
+            }
+        }
+
+        for (name, value) in &self.set {
+            if let (Ok(name), Ok(value)) = (parse_name(name), parse_value(value)) {
+                headers.insert(name, value);
+            }
+        }
+
+        for (name, value) in &self.append {
+            if let (Ok(name), Ok(value)) = (parse_name(name), parse_value(value)) {
+                headers.append(name, value);
+            }
+        }
+    }
+}
+
+fn parse_name(name: &str) -> Result<HeaderName, String> {
+    HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| format!("invalid header name: {}", name))
+}
+
+fn parse_value(value: &str) -> Result<HeaderValue, String> {
+    HeaderValue::from_str(value).map_err(|_| format!("invalid header value: {}", value))
+}
+
+/// 响应头预设，可写作 `cors`、`cors=<origin>`、`strip-csp` 或 `no-cache`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderPreset {
+    /// 放开跨域：允许指定来源（未指定时回显请求的 `Origin`）携带凭据访问，并直接应答预检请求
+    Cors(Option<String>),
+    /// 删除 `Content-Security-Policy`（含 Report-Only）与 `Strict-Transport-Security`
+    StripCsp,
+    /// 禁用缓存：删除校验头并要求不缓存
+    NoCache,
+}
+
+impl HeaderPreset {
+    pub fn is_cors(&self) -> bool {
+        matches!(self, Self::Cors(_))
+    }
+
+    /// 对响应头应用预设；`origin` 为请求中的 `Origin` 头
+    pub fn apply(&self, headers: &mut HeaderMap, origin: Option<&HeaderValue>) {
+        match self {
+            Self::Cors(allowed) => {
+                let allowed = match allowed {
+                    Some(allowed) => HeaderValue::from_str(allowed).ok(),
+                    None => origin.cloned(),
+                };
+                let Some(allowed) = allowed else {
+                    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+                    headers.remove(ACCESS_CONTROL_ALLOW_CREDENTIALS);
+                    return;
+                };
+
+                // 携带凭据时 `*` 不作为通配符，逐个列出响应头
+                let exposed: Vec<&str> = headers
+                    .keys()
+                    .map(HeaderName::as_str)
+                    .filter(|name| !name.starts_with("access-control-"))
+                    .collect();
+                if !exposed.is_empty()
+                    && let Ok(exposed) = HeaderValue::from_str(&exposed.join(", "))
+                {
+                    headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, exposed);
+                }
+                // 回显来源时响应随 Origin 变化
+                if self == &Self::Cors(None) {
+                    headers.append(VARY, HeaderValue::from_static("Origin"));
+                }
+                headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allowed);
+                headers.insert(
+                    ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                    HeaderValue::from_static("true"),
+                );
+            }
+            Self::StripCsp => {
+                headers.remove(CONTENT_SECURITY_POLICY);
+                headers.remove(CONTENT_SECURITY_POLICY_REPORT_ONLY);
+                headers.remove(STRICT_TRANSPORT_SECURITY);
+            }
+            Self::NoCache => {
+                headers.remove(ETAG);
+                headers.remove(LAST_MODIFIED);
+                headers.insert(
+                    CACHE_CONTROL,
+                    HeaderValue::from_static("no-store, no-cache, must-revalidate, max-age=0"),
+                );
+                headers.insert(PRAGMA, HeaderValue::from_static("no-cache"));
+                headers.insert(EXPIRES, HeaderValue::from_static("0"));
+            }
+        }
+    }
+
+    /// CORS 预检请求：带有 `Origin` 与 `Access-Control-Request-Method` 的 `OPTIONS` 请求
+    pub fn is_preflight<B>(req: &Request<B>) -> bool {
+        req.method() == Method::OPTIONS
+            && req.headers().contains_key(ORIGIN)
+            && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+    }
+
+    /// 直接应答预检请求：允许所请求的方法与头；允许的来源由 [`HeaderPreset::Cors`] 补充
+    pub fn preflight_response<B>(req: &Request<B>) -> Response<Body> {
+        let mut res = Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .header(ACCESS_CONTROL_MAX_AGE, "86400");
+        if let Some(method) = req.headers().get(ACCESS_CONTROL_REQUEST_METHOD) {
+            res = res.header(ACCESS_CONTROL_ALLOW_METHODS, method);
+        }
+        if let Some(headers) = req.headers().get(ACCESS_CONTROL_REQUEST_HEADERS) {
+            res = res.header(ACCESS_CONTROL_ALLOW_HEADERS, headers);
+        }
+        // Chrome 的私有网络访问：公网页面访问本地地址时需要额外许可
+        if req
+            .headers()
+            .get("access-control-request-private-network")
+            .is_some_and(|v| v == "true")
+        {
+            res = res.header("access-control-allow-private-network", "true");
+        }
+        res.body(Body::empty())
+            .expect("Failed to build preflight response")
+    }
+}
+
+impl std::str::FromStr for HeaderPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(origin) = s
+            .split_once('=')
+            .and_then(|(name, origin)| name.trim().eq_ignore_ascii_case("cors").then_some(origin))
+        {
+            let origin = origin.trim();
+            if origin.is_empty() || HeaderValue::from_str(origin).is_err() {
+                return Err(format!("invalid CORS origin: {}", origin));
+            }
+            return Ok(Self::Cors(Some(origin.to_string())));
+        }
+
+        match s.to_ascii_lowercase().as_str() {
+            "cors" => Ok(Self::Cors(None)),
+            "strip-csp" => Ok(Self::StripCsp),
+            "no-cache" => Ok(Self::NoCache),
+            _ => Err(format!(
+                "unknown header preset '{}': expected cors[=<origin>], strip-csp or no-cache",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for HeaderPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cors(None) => write!(f, "cors"),
+            Self::Cors(Some(origin)) => write!(f, "cors={}", origin),
+            Self::StripCsp => write!(f, "strip-csp"),
+            Self::NoCache => write!(f, "no-cache"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HeaderPreset {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HeaderPreset {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
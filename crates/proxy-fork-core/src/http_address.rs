@@ -1,15 +1,30 @@
 use std::convert::TryFrom;
+use std::path::Path;
 
 use derive_builder::Builder;
 use http::Uri;
+use regex::Regex;
 use std::error::Error;
 
 use crate::{PatternMatcher, PatternType};
 
+/// 地址协议；匹配模式只支持 `Http` 与 `Https`，其余变体仅用于目标地址
+///
+/// 之后可能增加新的目标类型，因此标记为 `non_exhaustive`
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[non_exhaustive]
 pub enum Protocol {
     Http,
     Https,
+    /// Unix 域套接字，仅用于目标地址：`host` 为套接字路径
+    Unix,
+    /// 本地目录（Map Local），仅用于目标地址：`host` 为目录路径
+    File,
 }
 
 impl std::fmt::Display for Protocol {
@@ -17,6 +32,20 @@ impl std::fmt::Display for Protocol {
         match self {
             Protocol::Http => write!(f, "http"),
             Protocol::Https => write!(f, "https"),
+            Protocol::Unix => write!(f, "unix"),
+            Protocol::File => write!(f, "file"),
+        }
+    }
+}
+
+impl std::str::FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "http" => Ok(Protocol::Http),
+            "https" => Ok(Protocol::Https),
+            _ => Err(format!("Invalid protocol: {} (expected http or https)", s)),
         }
     }
 }
@@ -41,7 +70,7 @@ impl TryFrom<&Uri> for Protocol {
 }
 
 /// 路径转换模式
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum PathTransformMode {
     /// 保留原始路径：只改变协议、主机和端口
     /// 例: https://example.com/api/users -> http://localhost:8080/api/users
@@ -55,49 +84,435 @@ pub enum PathTransformMode {
     /// 前缀替换：将匹配的路径前缀替换为新的前缀
     /// 例: https://example.com/api/v1/users -> http://localhost:8080/api/v2/users
     Replace,
+
+    /// 去除前缀：去掉匹配的路径前缀，不再拼接新前缀
+    /// 例: https://example.com/api/users (pattern="/api/*") -> http://localhost:8080/users
+    StripPrefix,
+
+    /// 正则替换：对原始路径（含查询参数）应用正则替换模板
+    /// 例: `s#^/v1/(.*)#/api/v2/$1#`，https://example.com/v1/users -> http://localhost:8080/api/v2/users
+    Regex(PathRegex),
 }
 
 impl std::str::FromStr for PathTransformMode {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // sed 风格的正则替换表达式：s<分隔符><正则><分隔符><替换模板><分隔符>
+        if is_sed_expression(s) {
+            return s.parse().map(PathTransformMode::Regex);
+        }
+
         match s.to_lowercase().as_str() {
             "preserve" => Ok(PathTransformMode::Preserve),
             "prepend" => Ok(PathTransformMode::Prepend),
             "replace" => Ok(PathTransformMode::Replace),
+            "strip_prefix" | "strip-prefix" => Ok(PathTransformMode::StripPrefix),
             _ => Err(format!("Invalid PathTransformMode: {}", s)),
         }
     }
 }
 
+impl std::fmt::Display for PathTransformMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathTransformMode::Preserve => write!(f, "preserve"),
+            PathTransformMode::Prepend => write!(f, "prepend"),
+            PathTransformMode::Replace => write!(f, "replace"),
+            PathTransformMode::StripPrefix => write!(f, "strip_prefix"),
+            PathTransformMode::Regex(regex) => write!(f, "{}", regex),
+        }
+    }
+}
+
+// 与 PatternMatcher 一致：序列化为字符串形式，反序列化时按 FromStr 解析
+#[cfg(feature = "serde")]
+impl serde::Serialize for PathTransformMode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PathTransformMode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 路径正则替换：正则 + 替换模板（支持 `$1`、`${name}` 等捕获组引用）
+#[derive(Debug, Clone)]
+pub struct PathRegex {
+    compiled: Regex,
+    replacement: String,
+}
+
+impl PathRegex {
+    pub fn new(pattern: &str, replacement: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            compiled: Regex::new(pattern)?,
+            replacement: replacement.to_string(),
+        })
+    }
+
+    pub fn pattern(&self) -> &str {
+        self.compiled.as_str()
+    }
+
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// 对路径应用替换（仅替换第一个匹配，与 sed 默认行为一致）；不匹配时返回 None
+    pub fn apply(&self, path_and_query: &str) -> Option<String> {
+        self.compiled.is_match(path_and_query).then(|| {
+            self.compiled
+                .replace(path_and_query, self.replacement.as_str())
+                .into_owned()
+        })
+    }
+
+    /// 替换所有匹配；不匹配时返回 None
+    pub fn apply_all(&self, text: &str) -> Option<String> {
+        self.compiled.is_match(text).then(|| {
+            self.compiled
+                .replace_all(text, self.replacement.as_str())
+                .into_owned()
+        })
+    }
+}
+
+impl PartialEq for PathRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern() == other.pattern() && self.replacement == other.replacement
+    }
+}
+
+impl Eq for PathRegex {}
+
+impl std::hash::Hash for PathRegex {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.pattern().hash(state);
+        self.replacement.hash(state);
+    }
+}
+
+/// 是否为形如 `s#regex#replacement#` 的 sed 风格表达式（分隔符不能是字母、数字或空白）
+///
+/// 规则 DSL 与 TOML 配置共用这一判断，保证两处对同一写法的解析一致
+pub(crate) fn is_sed_expression(s: &str) -> bool {
+    s.strip_prefix('s')
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|d| !d.is_alphanumeric() && !d.is_whitespace())
+}
+
+/// 解析 sed 风格表达式，例如 `s#^/v1/(.*)#/api/v2/$1#`；分隔符为 `s` 之后的第一个字符
+impl std::str::FromStr for PathRegex {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid regex path transform: {} (expected s#regex#replacement#)",
+                s
+            )
+        };
+
+        if !is_sed_expression(s) {
+            return Err(invalid());
+        }
+        let rest = &s[1..];
+        let delimiter = rest.chars().next().ok_or_else(invalid)?;
+        let parts: Vec<&str> = rest[delimiter.len_utf8()..].split(delimiter).collect();
+        let [pattern, replacement, ""] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        if pattern.is_empty() {
+            return Err(invalid());
+        }
+
+        Self::new(pattern, replacement).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PathRegex {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PathRegex {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl std::fmt::Display for PathRegex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 选择一个不出现在正则与模板中的分隔符
+        let delimiter = ['#', '|', '!', '@', '%', '~', ';']
+            .into_iter()
+            .find(|d| !self.pattern().contains(*d) && !self.replacement.contains(*d))
+            .unwrap_or('#');
+        write!(
+            f,
+            "s{d}{}{d}{}{d}",
+            self.pattern(),
+            self.replacement,
+            d = delimiter
+        )
+    }
+}
+
+/// 转发请求时 `Host` 请求头的取值方式
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum HostHeader {
+    /// 使用目标地址的 `host[:port]`（默认）
+    #[default]
+    Target,
+    /// 保留客户端请求中的原始 `Host`，适用于按虚拟主机区分站点的本地后端
+    Preserve,
+    /// 使用指定的值
+    Value(String),
+}
+
+impl HostHeader {
+    pub fn is_target(&self) -> bool {
+        matches!(self, HostHeader::Target)
+    }
+}
+
+impl std::str::FromStr for HostHeader {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "target" => Ok(HostHeader::Target),
+            "preserve" => Ok(HostHeader::Preserve),
+            "" => Err("Host header value must not be empty".to_string()),
+            _ => {
+                http::HeaderValue::from_str(s)
+                    .map_err(|_| format!("Invalid Host header value: {}", s))?;
+                Ok(HostHeader::Value(s.to_string()))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for HostHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostHeader::Target => write!(f, "target"),
+            HostHeader::Preserve => write!(f, "preserve"),
+            HostHeader::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HostHeader {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HostHeader {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 拆分 URL 风格字符串：`scheme://authority[/path]`，返回 (协议, authority, path)
+///
+/// 这里不使用 `Uri` 解析，因为模式中的主机可能包含 `*` 或 `re:` 正则
+fn split_url_like(s: &str) -> Result<(Protocol, &str, Option<&str>), String> {
+    let s = s.trim();
+    let (scheme, rest) = s.split_once("://").ok_or_else(|| {
+        format!(
+            "missing scheme in '{}' (expected e.g. https://host/path)",
+            s
+        )
+    })?;
+    let protocol: Protocol = scheme.parse()?;
+
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], Some(&rest[idx..])),
+        None => (rest, None),
+    };
+    if authority.is_empty() {
+        return Err(format!("missing host in '{}'", s));
+    }
+    Ok((protocol, authority, path))
+}
+
+/// 拆分 `host[:port]`，支持 `[::1]:8080` 形式的 IPv6 地址；不带方括号的 IPv6 地址视为没有端口
+pub(crate) fn split_authority(authority: &str) -> Result<(&str, Option<u16>), String> {
+    if let Some(rest) = authority.strip_prefix('[')
+        && let Some((host, tail)) = rest.split_once(']')
+    {
+        let port = match tail.strip_prefix(':') {
+            Some(p) => Some(p.parse().map_err(|_| format!("invalid port: {}", p))?),
+            None if tail.is_empty() => None,
+            None => return Err(format!("invalid authority: {}", authority)),
+        };
+        return Ok((host, port));
+    }
+
+    // 多个 `:` 只可能是不带方括号的 IPv6 地址，例如 `::1`
+    if authority.matches(':').count() > 1 {
+        return Ok((authority, None));
+    }
+    match authority.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+            let port = port
+                .parse()
+                .map_err(|_| format!("invalid port: {}", port))?;
+            Ok((host, Some(port)))
+        }
+        _ => Ok((authority, None)),
+    }
+}
+
 // 地址结构体
 #[derive(Builder, Debug, Clone, PartialEq, Eq, Hash)]
 #[builder(pattern = "owned")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Address {
     pub protocol: Protocol,
     pub host: String,
     #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub port: Option<u16>,
     #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub path: Option<String>,
     /// 路径转换模式（默认为 Preserve）
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
     pub path_transform_mode: PathTransformMode,
 }
 
 impl std::fmt::Display for Address {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let authority = if let Some(port) = self.port {
-            format!("{}:{}", self.host, port)
-        } else {
-            self.host.clone()
-        };
+        if let Some(socket) = self.unix_socket_path() {
+            write!(f, "unix://{}", socket.display())?;
+            if let Some(path) = &self.path {
+                write!(f, ":{}", path)?;
+            }
+            return Ok(());
+        }
+        if let Some(root) = self.local_root() {
+            return write!(f, "file://{}", root.display());
+        }
+
         let path = self.path.as_deref().unwrap_or("/");
-        write!(f, "{}://{}{}", self.protocol, authority, path)
+        write!(f, "{}://{}{}", self.protocol, self.authority(), path)
+    }
+}
+
+/// 从 URL 风格字符串解析目标地址，例如 `http://localhost:8080/local`
+///
+/// 路径为空或为 `/` 时视为未指定路径；路径转换模式保持默认（Preserve）
+///
+/// Unix 域套接字写作 `unix:///run/app.sock`，需要路径前缀时与 nginx 一样用 `:` 分隔，
+/// 例如 `unix:///run/app.sock:/api`
+///
+/// 本地目录写作 `file:///home/me/build/dist`，请求路径（经路径转换后）映射为该目录下的文件
+impl std::str::FromStr for Address {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(root) = s.trim().strip_prefix("file://") {
+            // Windows 路径：file:///C:/dist -> C:/dist
+            let root = match root.as_bytes() {
+                [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &root[1..],
+                _ => root,
+            };
+            if root.is_empty() {
+                return Err(format!("missing directory in '{}'", s.trim()));
+            }
+            return Ok(Self::local(root));
+        }
+
+        if let Some(rest) = s.trim().strip_prefix("unix://") {
+            let (socket, path) = match rest.split_once(":/") {
+                Some((socket, path)) => (socket, Some(format!("/{}", path))),
+                None => (rest, None),
+            };
+            if socket.is_empty() {
+                return Err(format!("missing socket path in '{}'", s.trim()));
+            }
+            return Ok(Self::unix(socket, path.filter(|p| p != "/")));
+        }
+
+        let (protocol, authority, path) = split_url_like(s)?;
+        let (host, port) = split_authority(authority)?;
+        if host.contains('*') || host.starts_with("re:") {
+            return Err(format!("target host must be a concrete host: {}", host));
+        }
+
+        Ok(Self {
+            protocol,
+            host: host.to_string(),
+            port,
+            path: path.filter(|p| *p != "/").map(ToString::to_string),
+            path_transform_mode: PathTransformMode::default(),
+        })
     }
 }
 
 impl Address {
+    /// 创建指向 Unix 域套接字的目标地址
+    pub fn unix(socket: impl Into<String>, path: Option<String>) -> Self {
+        Self {
+            protocol: Protocol::Unix,
+            host: socket.into(),
+            port: None,
+            path,
+            path_transform_mode: PathTransformMode::default(),
+        }
+    }
+
+    /// 创建指向本地目录的目标地址（Map Local）
+    pub fn local(root: impl Into<String>) -> Self {
+        Self {
+            protocol: Protocol::File,
+            host: root.into(),
+            port: None,
+            path: None,
+            path_transform_mode: PathTransformMode::default(),
+        }
+    }
+
+    /// 本地目录路径；非 `file://` 目标返回 None
+    pub fn local_root(&self) -> Option<&Path> {
+        match self.protocol {
+            Protocol::File => Some(Path::new(&self.host)),
+            _ => None,
+        }
+    }
+
+    /// Unix 域套接字路径；TCP 目标返回 None
+    pub fn unix_socket_path(&self) -> Option<&Path> {
+        match self.protocol {
+            Protocol::Unix => Some(Path::new(&self.host)),
+            _ => None,
+        }
+    }
+
     /// 从 Uri 创建 Address
     pub fn from_uri(uri: &Uri) -> Result<Self, Box<dyn Error>> {
         let protocol = Protocol::try_from(uri).map_err(|_| "Invalid protocol")?;
@@ -114,6 +529,19 @@ impl Address {
         })
     }
 
+    /// `host[:port]`，IPv6 地址加上方括号
+    fn authority(&self) -> String {
+        let host = if self.host.contains(':') && !self.host.starts_with('[') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        match self.port {
+            Some(port) => format!("{}:{}", host, port),
+            None => host,
+        }
+    }
+
     // /// 从生成器构建 Address，并将构建错误统一为 `Box<dyn Error>`
     // pub fn from_builder(builder: AddressBuilder) -> Result<Self, Box<dyn Error>> {
     //     builder.build().map_err(|e| Box::new(e) as Box<dyn Error>)
@@ -145,7 +573,7 @@ impl Address {
     ///
     /// # 参数
     /// - `original_uri`: 原始请求的 URI
-    /// - `matched_prefix`: 匹配到的路径前缀（从 pattern 中提取，仅在 Replace 模式下使用）
+    /// - `matched_prefix`: 匹配到的路径前缀（从 pattern 中提取，仅在 Replace/StripPrefix 模式下使用）
     ///
     /// # 路径转换模式
     ///
@@ -179,20 +607,42 @@ impl Address {
     /// // https://example.com/api/v1/users (matched_prefix="/api/v1")
     /// //   -> http://localhost:8080/api/v2/users
     /// ```
+    ///
+    /// ## PathTransformMode::StripPrefix (去除前缀)
+    /// ```ignore
+    /// let target = Address {
+    ///     path_transform_mode: PathTransformMode::StripPrefix,
+    ///     path: None,
+    ///     ...
+    /// };
+    /// // https://example.com/api/users (matched_prefix="/api")
+    /// //   -> http://localhost:8080/users
+    /// ```
+    ///
+    /// ## PathTransformMode::Regex (正则替换)
+    /// ```ignore
+    /// let target = Address {
+    ///     path_transform_mode: "s#^/v1/(.*)#/api/v2/$1#".parse()?,
+    ///     path: None,
+    ///     ...
+    /// };
+    /// // https://example.com/v1/users?page=2 -> http://localhost:8080/api/v2/users?page=2
+    /// ```
     pub fn to_uri_with_rewrite(
         &self,
         original_uri: &Uri,
         matched_prefix: Option<&str>,
     ) -> Result<Uri, http::Error> {
-        let scheme = match self.protocol {
-            Protocol::Http => "http",
-            Protocol::Https => "https",
-        };
-
-        let authority = if let Some(port) = self.port {
-            format!("{}:{}", self.host, port)
-        } else {
-            self.host.clone()
+        let (scheme, authority) = match self.protocol {
+            Protocol::Http => ("http", self.authority()),
+            Protocol::Https => ("https", self.authority()),
+            // Unix 域套接字与本地目录没有主机名：沿用原始请求的 authority，由它决定 Host 请求头
+            Protocol::Unix | Protocol::File => (
+                "http",
+                original_uri
+                    .authority()
+                    .map_or_else(|| "localhost".to_string(), ToString::to_string),
+            ),
         };
 
         let original_path = original_uri
@@ -200,7 +650,7 @@ impl Address {
             .map(|pq| pq.as_str())
             .unwrap_or("/");
 
-        let path_and_query = match self.path_transform_mode {
+        let path_and_query = match &self.path_transform_mode {
             PathTransformMode::Preserve => {
                 // 保留原始路径
                 original_path.to_string()
@@ -240,6 +690,27 @@ impl Address {
                     original_path.to_string()
                 }
             }
+            PathTransformMode::StripPrefix => {
+                // 去除前缀
+                let suffix = matched_prefix.and_then(|old_prefix| {
+                    original_path
+                        .strip_prefix(old_prefix.trim_end_matches('*').trim_end_matches('/'))
+                });
+                match suffix {
+                    Some(suffix) if suffix.starts_with('/') => suffix.to_string(),
+                    Some(suffix) => format!("/{}", suffix),
+                    // 如果不匹配，保留原始路径
+                    None => original_path.to_string(),
+                }
+            }
+            PathTransformMode::Regex(regex) => {
+                // 正则替换；不匹配时保留原始路径
+                match regex.apply(original_path) {
+                    Some(path) if path.starts_with('/') => path,
+                    Some(path) => format!("/{}", path),
+                    None => original_path.to_string(),
+                }
+            }
         };
 
         Uri::builder()
@@ -248,18 +719,109 @@ impl Address {
             .path_and_query(path_and_query)
             .build()
     }
+
+    /// [`Address::to_uri_with_rewrite`] 的逆变换：把指向目标地址的绝对 URI 还原为原始请求的协议、主机与路径
+    ///
+    /// 用于改写响应中的 `Location` 等头，让浏览器留在原始站点：
+    /// ```ignore
+    /// // 目标 http://localhost:8080/local（Prepend），原始请求 https://example.com/api/users
+    /// // http://localhost:8080/local/login?next=1 -> https://example.com/login?next=1
+    /// ```
+    ///
+    /// 协议、主机或端口与目标不一致、路径不在目标前缀之下，以及 Unix 域套接字与本地目录目标时返回 None；
+    /// 正则替换无法逆推，只还原协议与主机，路径保持不变
+    pub fn restore_uri(
+        &self,
+        uri: &Uri,
+        original_uri: &Uri,
+        matched_prefix: Option<&str>,
+    ) -> Option<Uri> {
+        let (scheme, default_port) = match self.protocol {
+            Protocol::Http => ("http", 80),
+            Protocol::Https => ("https", 443),
+            Protocol::Unix | Protocol::File => return None,
+        };
+        if !uri.scheme_str()?.eq_ignore_ascii_case(scheme)
+            || !uri.host()?.eq_ignore_ascii_case(&self.host)
+            || uri.port_u16().unwrap_or(default_port) != self.port.unwrap_or(default_port)
+        {
+            return None;
+        }
+
+        let path = uri.path();
+        let old_prefix = matched_prefix.map(|p| p.trim_end_matches('*').trim_end_matches('/'));
+        let path = match (&self.path_transform_mode, &self.path, old_prefix) {
+            (PathTransformMode::Prepend, Some(prefix), _) => {
+                strip_path_prefix(path, prefix)?.to_string()
+            }
+            (PathTransformMode::Replace, Some(new_prefix), Some(old_prefix)) => {
+                format!("{}{}", old_prefix, strip_path_prefix(path, new_prefix)?)
+            }
+            (PathTransformMode::StripPrefix, _, Some(old_prefix)) => {
+                format!("{}{}", old_prefix, path)
+            }
+            _ => path.to_string(),
+        };
+        let path = if path.starts_with('/') {
+            path
+        } else {
+            format!("/{}", path)
+        };
+        let path_and_query = match uri.query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path,
+        };
+
+        Uri::builder()
+            .scheme(original_uri.scheme_str().unwrap_or("http"))
+            .authority(original_uri.authority()?.as_str())
+            .path_and_query(path_and_query)
+            .build()
+            .ok()
+    }
+}
+
+/// 去掉路径前缀（按 `/` 分段匹配），剩余部分为空或以 `/` 开头
+fn strip_path_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(prefix.trim_end_matches('/'))?;
+    (rest.is_empty() || rest.starts_with('/')).then_some(rest)
 }
 
 // 地址模式匹配器
 #[derive(Builder, Debug, Clone)]
 #[builder(pattern = "owned")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AddressPattern {
+    /// 匹配的协议，仅支持 http/https
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "deserialize_pattern_protocol")
+    )]
     pub protocol: Protocol,
     #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub port: Option<u16>,
+    #[cfg_attr(feature = "serde", serde(flatten))]
     pub pattern_type: PatternType,
 }
 
+/// 反序列化匹配模式的协议：Unix 域套接字与本地目录只能作为目标地址
+#[cfg(feature = "serde")]
+fn deserialize_pattern_protocol<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Protocol, D::Error> {
+    match <Protocol as serde::Deserialize>::deserialize(deserializer)? {
+        protocol @ (Protocol::Http | Protocol::Https) => Ok(protocol),
+        protocol => Err(serde::de::Error::custom(format!(
+            "Invalid protocol: {} (expected http or https)",
+            protocol
+        ))),
+    }
+}
+
 impl std::fmt::Display for AddressPattern {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let port = self.port.map_or_else(String::new, |p| format!(":{}", p));
@@ -269,11 +831,24 @@ impl std::fmt::Display for AddressPattern {
             .as_ref()
             .map(ToString::to_string)
             .unwrap_or_default();
-        write!(
-            f,
-            "{}://{}{}{}",
-            self.protocol, self.pattern_type.host, port, path
-        )
+        let host = match &self.pattern_type.host {
+            PatternMatcher::Exact(host) if host.contains(':') => format!("[{}]", host),
+            host => host.to_string(),
+        };
+        write!(f, "{}://{}{}{}", self.protocol, host, port, path)
+    }
+}
+
+/// 从 URL 风格字符串解析地址模式，例如 `https://*.example.com:8443/api/*`
+///
+/// 主机与路径支持精确、通配符与 `re:` 正则写法；省略路径表示不约束路径
+impl std::str::FromStr for AddressPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (protocol, authority, path) = split_url_like(s)?;
+        let (host, port) = split_authority(authority)?;
+        Self::new(protocol, host, port, path).map_err(|e| e.to_string())
     }
 }
 
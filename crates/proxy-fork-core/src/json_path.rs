@@ -0,0 +1,246 @@
+use serde_json::{Map, Value};
+
+/// JSONPath 的一个片段
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// 对象的键：`.name` 或 `['name']`
+    Key(String),
+    /// 数组下标：`[0]`，负数从末尾计数（`[-1]` 为最后一个元素）
+    Index(i64),
+    /// 所有子元素：`.*` 或 `[*]`
+    Wildcard,
+}
+
+/// 定位到某个值的具体路径（对象键或数组下标）
+#[derive(Debug, Clone)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// JSONPath 的子集：`$`、`.name`、`['name']`、`[0]`、`[-1]`、`.*` 与 `[*]`
+///
+/// 例如 `$.user.roles[0]`、`$.items[*].price`、`$['content-type']`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// 不含通配符时路径唯一确定
+    fn is_definite(&self) -> bool {
+        !self.segments.contains(&Segment::Wildcard)
+    }
+
+    /// 设置匹配到的值；路径唯一确定且不存在时创建（中间缺失的对象一并创建）。返回设置的个数
+    pub fn set(&self, root: &mut Value, value: &Value) -> usize {
+        let targets = self.select(root);
+        if targets.is_empty() && self.is_definite() {
+            return usize::from(create(root, &self.segments, value.clone()));
+        }
+        for target in &targets {
+            if let Some(slot) = resolve_mut(root, target) {
+                *slot = value.clone();
+            }
+        }
+        targets.len()
+    }
+
+    /// 仅替换已存在的值，返回替换的个数
+    pub fn replace(&self, root: &mut Value, value: &Value) -> usize {
+        let targets = self.select(root);
+        for target in &targets {
+            if let Some(slot) = resolve_mut(root, target) {
+                *slot = value.clone();
+            }
+        }
+        targets.len()
+    }
+
+    /// 删除匹配到的值，返回删除的个数；不能删除根
+    pub fn remove(&self, root: &mut Value) -> usize {
+        let mut targets = self.select(root);
+        // 同一数组中的多个下标从后往前删除，避免前面的删除改变后面的下标
+        targets.sort_by_key(|target| match target.last() {
+            Some(Step::Index(idx)) => std::cmp::Reverse(*idx),
+            _ => std::cmp::Reverse(0),
+        });
+
+        let mut removed = 0;
+        for mut target in targets {
+            let Some(last) = target.pop() else {
+                continue;
+            };
+            let removed_one = match (resolve_mut(root, &target), last) {
+                (Some(Value::Object(map)), Step::Key(key)) => map.remove(&key).is_some(),
+                (Some(Value::Array(items)), Step::Index(idx)) if idx < items.len() => {
+                    items.remove(idx);
+                    true
+                }
+                _ => false,
+            };
+            removed += usize::from(removed_one);
+        }
+        removed
+    }
+
+    /// 查找所有匹配的具体路径
+    fn select(&self, root: &Value) -> Vec<Vec<Step>> {
+        let mut matches = vec![(Vec::new(), root)];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for (path, value) in matches {
+                match (segment, value) {
+                    (Segment::Key(key), Value::Object(map)) => {
+                        if let Some(child) = map.get(key) {
+                            next.push((push(&path, Step::Key(key.clone())), child));
+                        }
+                    }
+                    (Segment::Index(idx), Value::Array(items)) => {
+                        if let Some(idx) = resolve_index(*idx, items.len()) {
+                            next.push((push(&path, Step::Index(idx)), &items[idx]));
+                        }
+                    }
+                    (Segment::Wildcard, Value::Object(map)) => {
+                        for (key, child) in map {
+                            next.push((push(&path, Step::Key(key.clone())), child));
+                        }
+                    }
+                    (Segment::Wildcard, Value::Array(items)) => {
+                        for (idx, child) in items.iter().enumerate() {
+                            next.push((push(&path, Step::Index(idx)), child));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            matches = next;
+        }
+        matches.into_iter().map(|(path, _)| path).collect()
+    }
+}
+
+fn push(path: &[Step], step: Step) -> Vec<Step> {
+    let mut path = path.to_vec();
+    path.push(step);
+    path
+}
+
+fn resolve_index(idx: i64, len: usize) -> Option<usize> {
+    let idx = if idx < 0 {
+        len.checked_sub(usize::try_from(idx.unsigned_abs()).ok()?)?
+    } else {
+        usize::try_from(idx).ok()?
+    };
+    (idx < len).then_some(idx)
+}
+
+fn resolve_mut<'a>(root: &'a mut Value, path: &[Step]) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(root, |value, step| match (step, value) {
+            (Step::Key(key), Value::Object(map)) => map.get_mut(key),
+            (Step::Index(idx), Value::Array(items)) => items.get_mut(*idx),
+            _ => None,
+        })
+}
+
+/// 按路径创建值：缺失的对象键逐级创建，数组下标必须已存在
+fn create(value: &mut Value, segments: &[Segment], new_value: Value) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        *value = new_value;
+        return true;
+    };
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(map)) => {
+            let child = map
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            create(child, rest, new_value)
+        }
+        (Segment::Index(idx), Value::Array(items)) => match resolve_index(*idx, items.len()) {
+            Some(idx) => create(&mut items[idx], rest, new_value),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+impl std::str::FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = s.trim();
+        let invalid = |reason: &str| format!("invalid JSONPath '{}': {}", raw, reason);
+        let mut rest = raw
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("must start with '$'"))?;
+
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if rest.starts_with("..") {
+                return Err(invalid("recursive descent is not supported"));
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let len = after.find(['.', '[']).unwrap_or(after.len());
+                let name = &after[..len];
+                segments.push(match name {
+                    "" => return Err(invalid("empty member name")),
+                    "*" => Segment::Wildcard,
+                    name => Segment::Key(name.to_string()),
+                });
+                rest = &after[len..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let after = after.trim_start();
+                if let Some(quote) = after.chars().next().filter(|c| *c == '\'' || *c == '"') {
+                    let quoted = &after[1..];
+                    let end = quoted
+                        .find(quote)
+                        .ok_or_else(|| invalid("unterminated quoted name"))?;
+                    segments.push(Segment::Key(quoted[..end].to_string()));
+                    rest = quoted[end + 1..]
+                        .trim_start()
+                        .strip_prefix(']')
+                        .ok_or_else(|| invalid("expected ']'"))?;
+                } else {
+                    let end = after.find(']').ok_or_else(|| invalid("expected ']'"))?;
+                    segments.push(match after[..end].trim() {
+                        "*" => Segment::Wildcard,
+                        idx => Segment::Index(
+                            idx.parse()
+                                .map_err(|_| invalid("expected an index, '*' or a quoted name"))?,
+                        ),
+                    });
+                    rest = &after[end + 1..];
+                }
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+
+        Ok(Self {
+            raw: raw.to_string(),
+            segments,
+        })
+    }
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for JsonPath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for JsonPath {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
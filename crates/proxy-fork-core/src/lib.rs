@@ -1,12 +1,42 @@
+pub mod admin;
+pub use admin::*;
+
+pub mod body_rewrite;
+pub use body_rewrite::*;
+
 pub mod certification;
 pub use certification::*;
 
+pub mod cookie_rewrite;
+pub use cookie_rewrite::*;
+
+pub mod fault;
+pub use fault::*;
+
+pub mod har;
+pub use har::*;
+
+pub mod header_rewrite;
+pub use header_rewrite::*;
+
 pub mod http_address;
 pub use http_address::*;
 
+pub mod json_path;
+pub use json_path::*;
+
+pub mod local_files;
+pub use local_files::*;
+
+pub mod network_profile;
+pub use network_profile::*;
+
 pub mod match_strategy;
 pub use match_strategy::*;
 
+pub mod pac;
+pub use pac::*;
+
 pub mod proxy_manage_stats;
 pub use proxy_manage_stats::*;
 
@@ -16,6 +46,26 @@ pub use proxy_manage::*;
 pub mod proxy_handler;
 pub use proxy_handler::*;
 
+pub mod query_rewrite;
+pub use query_rewrite::*;
+
+pub mod recording;
+pub use recording::*;
+
+pub mod request_body;
+pub use request_body::*;
+
+pub mod rule_action;
+pub use rule_action::*;
+
+pub mod upstream_proxy;
+pub use upstream_proxy::*;
+
+#[cfg(feature = "serde")]
+mod serde_helpers;
+
+mod url_encoding;
+
 // Re-export hudsucker and tokio-rustls for easier access
 pub use hudsucker::Proxy;
 pub use tokio_rustls::rustls;
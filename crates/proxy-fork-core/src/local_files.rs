@@ -0,0 +1,325 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use fs_err::tokio as fs;
+use http::header::{
+    ACCEPT_RANGES, ALLOW, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, LOCATION, RANGE,
+};
+use http::{HeaderMap, Method, Request, Response, StatusCode};
+use httpdate::HttpDate;
+use hudsucker::Body;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+use tracing::debug;
+
+use crate::url_encoding::percent_decode;
+
+/// Map Local：从本地目录提供响应，不再转发到上游
+///
+/// - 请求路径（经路径转换后）映射为 `root` 下的文件，`..` 等越界路径返回 403
+/// - 目录请求返回其中的 `index.html`；缺少结尾 `/` 时先 301 重定向
+/// - 按扩展名识别 `Content-Type`，支持 `ETag`/`Last-Modified` 条件请求与单段 `Range`
+/// - 开启 `spa_fallback` 时，不含扩展名且不存在的路径回退到根目录的 `index.html`
+#[derive(Debug, Clone)]
+pub struct LocalFiles {
+    root: PathBuf,
+    spa_fallback: bool,
+}
+
+/// 已定位的文件及其元数据
+struct ResolvedFile {
+    path: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl LocalFiles {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            spa_fallback: false,
+        }
+    }
+
+    pub fn with_spa_fallback(mut self, spa_fallback: bool) -> Self {
+        self.spa_fallback = spa_fallback;
+        self
+    }
+
+    /// 为请求生成响应；`path_and_query` 为路径转换后的目标路径
+    pub async fn serve(&self, req: &Request<Body>, path_and_query: &str) -> Response<Body> {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return Response::builder()
+                .status(StatusCode::METHOD_NOT_ALLOWED)
+                .header(ALLOW, "GET, HEAD")
+                .body(Body::empty())
+                .expect("Failed to build response");
+        }
+
+        let path = path_and_query
+            .split_once('?')
+            .map_or(path_and_query, |(p, _)| p);
+        let Some(relative) = sanitize_path(path) else {
+            return status_response(StatusCode::FORBIDDEN);
+        };
+
+        let candidate = self.root.join(&relative);
+        let file = match fs::metadata(&candidate).await {
+            Ok(meta) if meta.is_dir() => {
+                // 重定向地址由客户端解析，需基于原始请求路径而非转换后的路径
+                let request_path = req.uri().path();
+                if !path.ends_with('/') && !request_path.ends_with('/') {
+                    let location = match req.uri().query() {
+                        Some(query) => format!("{}/?{}", request_path, query),
+                        None => format!("{}/", request_path),
+                    };
+                    return Response::builder()
+                        .status(StatusCode::MOVED_PERMANENTLY)
+                        .header(LOCATION, location)
+                        .body(Body::empty())
+                        .expect("Failed to build response");
+                }
+                resolve_file(candidate.join("index.html")).await
+            }
+            Ok(meta) if meta.is_file() => Some(ResolvedFile {
+                path: candidate,
+                len: meta.len(),
+                modified: meta.modified().ok(),
+            }),
+            _ => None,
+        };
+
+        let file = match file {
+            Some(file) => file,
+            None if self.spa_fallback && !has_extension(&relative) => {
+                match resolve_file(self.root.join("index.html")).await {
+                    Some(file) => file,
+                    None => return status_response(StatusCode::NOT_FOUND),
+                }
+            }
+            None => return status_response(StatusCode::NOT_FOUND),
+        };
+
+        debug!("Serving local file {}", file.path.display());
+        match respond_with_file(req, &file).await {
+            Ok(response) => response,
+            Err(e) => {
+                debug!("Failed to read {}: {}", file.path.display(), e);
+                status_response(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+}
+
+async fn resolve_file(path: PathBuf) -> Option<ResolvedFile> {
+    let meta = fs::metadata(&path).await.ok()?;
+    meta.is_file().then(|| ResolvedFile {
+        len: meta.len(),
+        modified: meta.modified().ok(),
+        path,
+    })
+}
+
+async fn respond_with_file(
+    req: &Request<Body>,
+    file: &ResolvedFile,
+) -> std::io::Result<Response<Body>> {
+    let last_modified = file.modified.map(HttpDate::from);
+    let etag = format!(
+        "\"{:x}-{:x}\"",
+        file.len,
+        file.modified
+            .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos())
+    );
+
+    let mut builder = Response::builder()
+        .header(CONTENT_TYPE, content_type(&file.path))
+        .header(ACCEPT_RANGES, "bytes")
+        .header(ETAG, &etag);
+    if let Some(last_modified) = last_modified {
+        builder = builder.header(LAST_MODIFIED, last_modified.to_string());
+    }
+
+    if is_not_modified(req.headers(), &etag, last_modified) {
+        return Ok(builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .expect("Failed to build response"));
+    }
+
+    let range = req
+        .headers()
+        .get(RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| if_range_matches(req.headers(), &etag, last_modified))
+        .and_then(|v| parse_range(v, file.len));
+    let (status, start, len) = match range {
+        None => (StatusCode::OK, 0, file.len),
+        Some(Ok((start, end))) => {
+            builder = builder.header(
+                CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, file.len),
+            );
+            (StatusCode::PARTIAL_CONTENT, start, end - start + 1)
+        }
+        Some(Err(())) => {
+            return Ok(builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{}", file.len))
+                .body(Body::empty())
+                .expect("Failed to build response"));
+        }
+    };
+
+    let builder = builder.status(status).header(CONTENT_LENGTH, len);
+    if req.method() == Method::HEAD {
+        return Ok(builder
+            .body(Body::empty())
+            .expect("Failed to build response"));
+    }
+
+    let mut handle = fs::File::open(&file.path).await?;
+    if start > 0 {
+        handle.seek(std::io::SeekFrom::Start(start)).await?;
+    }
+    let body = Body::from_stream(ReaderStream::new(handle.take(len)));
+    Ok(builder.body(body).expect("Failed to build response"))
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .expect("Failed to build response")
+}
+
+/// 解码并校验请求路径，返回相对于根目录的路径；包含 `..` 等越界片段时返回 None
+fn sanitize_path(path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(path);
+    let mut relative = PathBuf::new();
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            s if s.contains(['\\', '\0']) || Path::new(s).is_absolute() => return None,
+            s if cfg!(windows) && s.contains(':') => return None,
+            s => relative.push(s),
+        }
+    }
+    Some(relative)
+}
+
+fn has_extension(relative: &Path) -> bool {
+    relative.extension().is_some()
+}
+
+fn is_not_modified(headers: &HeaderMap, etag: &str, last_modified: Option<HttpDate>) -> bool {
+    // If-None-Match 优先于 If-Modified-Since
+    if let Some(value) = headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return value
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+    }
+    match (
+        headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<HttpDate>().ok()),
+        last_modified,
+    ) {
+        (Some(since), Some(modified)) => modified <= since,
+        _ => false,
+    }
+}
+
+/// `If-Range` 不存在，或与当前 `ETag`/`Last-Modified` 一致时才应用 `Range`
+fn if_range_matches(headers: &HeaderMap, etag: &str, last_modified: Option<HttpDate>) -> bool {
+    let Some(value) = headers.get(IF_RANGE).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+    let value = value.trim();
+    if value.starts_with('"') {
+        return value == etag;
+    }
+    value
+        .parse::<HttpDate>()
+        .ok()
+        .zip(last_modified)
+        .is_some_and(|(date, modified)| date == modified)
+}
+
+/// 解析单段 `Range: bytes=...`，返回闭区间 `(start, end)`
+///
+/// 无法识别或包含多段时返回 None（按完整响应处理），无法满足时返回 `Some(Err(()))`
+fn parse_range(value: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = value.trim().strip_prefix("bytes=")?.trim();
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        // 后缀区间：最后 n 个字节
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(Err(()));
+        }
+        return Some(Ok((len.saturating_sub(suffix), len - 1)));
+    }
+
+    let start: u64 = start.parse().ok()?;
+    let end = if end.is_empty() {
+        None
+    } else {
+        Some(end.parse::<u64>().ok()?)
+    };
+    if end.is_some_and(|end| end < start) {
+        return None;
+    }
+    if start >= len {
+        return Some(Err(()));
+    }
+    Some(Ok((start, end.map_or(len - 1, |end| end.min(len - 1)))))
+}
+
+/// 按扩展名识别 `Content-Type`，未知类型返回 `application/octet-stream`
+pub(crate) fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match ext.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs" | "cjs") => "text/javascript; charset=utf-8",
+        Some("json" | "map") => "application/json",
+        Some("webmanifest") => "application/manifest+json",
+        Some("txt" | "log") => "text/plain; charset=utf-8",
+        Some("md") => "text/markdown; charset=utf-8",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("xml") => "application/xml",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        Some("wasm") => "application/wasm",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("mp3") => "audio/mpeg",
+        Some("wav") => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
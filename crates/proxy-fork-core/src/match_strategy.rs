@@ -43,6 +43,34 @@ impl PatternMatcher {
             PatternMatcher::Regex { compiled, .. } => compiled.is_match(value),
         }
     }
+
+    /// 不区分大小写的匹配（用于 HTTP 头名称）；正则仍按原样匹配，可用 `(?i)` 忽略大小写
+    pub(crate) fn matches_ignore_ascii_case(&self, value: &str) -> bool {
+        match self {
+            PatternMatcher::Exact(pattern) => value.eq_ignore_ascii_case(pattern),
+            PatternMatcher::Wildcard(pattern) => {
+                PatternMatcher::Wildcard(pattern.to_ascii_lowercase())
+                    .matches(&value.to_ascii_lowercase())
+            }
+            PatternMatcher::Regex { .. } => self.matches(value),
+        }
+    }
+}
+
+// 序列化为原始模式字符串（正则带 `re:` 前缀），反序列化时按同样规则解析
+#[cfg(feature = "serde")]
+impl serde::Serialize for PatternMatcher {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PatternMatcher {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        PatternMatcher::from_str(&s).map_err(serde::de::Error::custom)
+    }
 }
 
 impl std::fmt::Display for PatternMatcher {
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use hudsucker::Throttle;
+
+/// 模拟的网络状况：下行/上行带宽与附加延迟
+///
+/// 可写作预设名（如 `3g`）或 `<下行 kbps>/<上行 kbps>[/<延迟 ms>]`（如 `1600/750/150`），带宽为 0 表示不限速
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct NetworkProfile {
+    /// 下行带宽（千比特每秒），0 表示不限速
+    pub download_kbps: u64,
+    /// 上行带宽（千比特每秒），0 表示不限速
+    pub upload_kbps: u64,
+    /// 附加延迟（毫秒）
+    pub latency_ms: u64,
+}
+
+/// 预设的网络状况，数值参考浏览器开发者工具中的同名配置
+pub const NETWORK_PRESETS: [(&str, NetworkProfile); 7] = [
+    ("gprs", NetworkProfile::new(50, 20, 500)),
+    ("edge", NetworkProfile::new(240, 200, 840)),
+    ("slow-3g", NetworkProfile::new(400, 400, 2000)),
+    ("3g", NetworkProfile::new(1600, 750, 563)),
+    ("4g", NetworkProfile::new(9000, 9000, 170)),
+    ("flaky-wifi", NetworkProfile::new(2000, 1000, 400)),
+    ("wifi", NetworkProfile::new(30000, 15000, 2)),
+];
+
+impl NetworkProfile {
+    pub const fn new(download_kbps: u64, upload_kbps: u64, latency_ms: u64) -> Self {
+        Self {
+            download_kbps,
+            upload_kbps,
+            latency_ms,
+        }
+    }
+
+    /// 按名称查找预设（不区分大小写）
+    pub fn preset(name: &str) -> Option<Self> {
+        NETWORK_PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name.trim()))
+            .map(|(_, profile)| *profile)
+    }
+
+    /// 与某个预设完全一致时返回其名称
+    pub fn preset_name(&self) -> Option<&'static str> {
+        NETWORK_PRESETS
+            .iter()
+            .find(|(_, profile)| profile == self)
+            .map(|(name, _)| *name)
+    }
+
+    /// 转换为 hudsucker 的限速扩展（字节每秒）
+    pub fn throttle(&self) -> Throttle {
+        let bytes_per_sec = |kbps: u64| (kbps > 0).then(|| kbps.saturating_mul(125));
+        Throttle {
+            download: bytes_per_sec(self.download_kbps),
+            upload: bytes_per_sec(self.upload_kbps),
+            latency: Duration::from_millis(self.latency_ms),
+        }
+    }
+}
+
+impl std::str::FromStr for NetworkProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(profile) = Self::preset(s) {
+            return Ok(profile);
+        }
+
+        let invalid = || {
+            let presets: Vec<_> = NETWORK_PRESETS.iter().map(|(name, _)| *name).collect();
+            format!(
+                "invalid network profile '{}': expected one of {} or <down_kbps>/<up_kbps>[/<latency_ms>]",
+                s.trim(),
+                presets.join(", ")
+            )
+        };
+        let values = s
+            .split('/')
+            .map(|v| v.trim().parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        match values[..] {
+            [download, upload] => Ok(Self::new(download, upload, 0)),
+            [download, upload, latency] => Ok(Self::new(download, upload, latency)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for NetworkProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.preset_name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(
+                f,
+                "{}/{}/{}",
+                self.download_kbps, self.upload_kbps, self.latency_ms
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for NetworkProfile {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NetworkProfile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
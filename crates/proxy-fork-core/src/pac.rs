@@ -0,0 +1,177 @@
+use http::header::{CACHE_CONTROL, CONTENT_TYPE, HOST};
+use http::{Method, Request, Response, StatusCode};
+use hudsucker::Body;
+
+use crate::{PatternMatcher, ProxyManager};
+
+/// 代理端口上提供 PAC 脚本的路径
+pub const PAC_PATH: &str = "/proxy.pac";
+
+/// PAC（Proxy Auto-Config）脚本生成
+///
+/// 只有命中主机模式的请求走代理，其余直连。未配置主机时使用当前已启用规则的主机，
+/// 因此规则变化后重新生成即可得到最新脚本。无法转换为 JavaScript 的 `re:` 正则会让所有请求走代理
+#[derive(Debug, Clone)]
+pub struct Pac {
+    // 脚本中 `PROXY` 的地址（host:port）；提供脚本时优先取请求的 `Host`
+    proxy: String,
+    // 指定走代理的主机模式；为空时取规则的主机
+    hosts: Vec<PatternMatcher>,
+}
+
+impl Pac {
+    pub fn new(proxy: impl Into<String>) -> Self {
+        Self {
+            proxy: proxy.into(),
+            hosts: Vec::new(),
+        }
+    }
+
+    /// 指定走代理的主机（精确、`*` 通配符或 `re:` 正则），代替规则中的主机
+    pub fn hosts<I, S>(mut self, hosts: I) -> Result<Self, regex::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.hosts = hosts
+            .into_iter()
+            .map(|host| PatternMatcher::from_str(host.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// 按当前规则生成脚本，`PROXY` 指向 `proxy`
+    pub fn script(&self, manager: &ProxyManager, proxy: &str) -> String {
+        let hosts = if self.hosts.is_empty() {
+            manager
+                .active_rules()
+                .map(|rule| rule.pattern.pattern_type.host.clone())
+                .collect()
+        } else {
+            self.hosts.clone()
+        };
+        let mut conditions: Vec<String> = Vec::new();
+        for host in &hosts {
+            let condition = host_condition(host);
+            if !conditions.contains(&condition) {
+                conditions.push(condition);
+            }
+        }
+        // 规则按主机排序输出，便于比较不同版本的脚本
+        conditions.sort();
+
+        let mut script = String::from("function FindProxyForURL(url, host) {\n");
+        script.push_str("  host = host.toLowerCase();\n");
+        if !conditions.is_empty() {
+            script.push_str("  if (\n    ");
+            script.push_str(&conditions.join(" ||\n    "));
+            script.push_str("\n  ) {\n");
+            script.push_str(&format!(
+                "    return {};\n",
+                js_string(&format!("PROXY {}", proxy))
+            ));
+            script.push_str("  }\n");
+        }
+        script.push_str("  return \"DIRECT\";\n}\n");
+        script
+    }
+
+    /// 直接访问代理端口的 `GET /proxy.pac` 时返回脚本，其余请求返回 `None`
+    pub(crate) fn respond(
+        &self,
+        manager: &ProxyManager,
+        req: &Request<Body>,
+    ) -> Option<Response<Body>> {
+        // 代理请求的 URI 带有目标地址；直接访问代理时为 origin-form
+        if req.uri().authority().is_some()
+            || req.uri().path() != PAC_PATH
+            || !matches!(*req.method(), Method::GET | Method::HEAD)
+        {
+            return None;
+        }
+        // 设备通过哪个地址取到脚本，就通过哪个地址连接代理
+        let proxy = req
+            .headers()
+            .get(HOST)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or(&self.proxy);
+        let script = self.script(manager, proxy);
+        let body = if req.method() == Method::HEAD {
+            Body::empty()
+        } else {
+            Body::from(script)
+        };
+        Some(
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(CONTENT_TYPE, "application/x-ns-proxy-autoconfig")
+                .header(CACHE_CONTROL, "no-cache")
+                .body(body)
+                .expect("Failed to build response"),
+        )
+    }
+}
+
+/// 主机模式对应的 JavaScript 条件，与 [`PatternMatcher`] 的匹配语义一致
+fn host_condition(host: &PatternMatcher) -> String {
+    match host {
+        PatternMatcher::Exact(pattern) => {
+            format!("host == {}", js_string(&pattern.to_ascii_lowercase()))
+        }
+        PatternMatcher::Wildcard(pattern) => {
+            let pattern = pattern.to_ascii_lowercase();
+            if let Some(suffix) = pattern.strip_prefix('*') {
+                format!("dnsDomainIs(host, {})", js_string(suffix))
+            } else if let Some(prefix) = pattern.strip_suffix('*') {
+                format!("host.indexOf({}) == 0", js_string(prefix))
+            } else {
+                format!("host == {}", js_string(&pattern))
+            }
+        }
+        PatternMatcher::Regex { compiled, .. } if is_js_compatible(compiled.as_str()) => {
+            format!("new RegExp({}).test(host)", js_string(compiled.as_str()))
+        }
+        // 含 JavaScript 不支持的语法时无法在脚本中匹配，全部交给代理判断
+        PatternMatcher::Regex { .. } => "true".to_string(),
+    }
+}
+
+/// 正则是否只使用了 Rust 与 JavaScript 含义一致的语法
+///
+/// 保守判断：内联标志、命名分组、Unicode 类别、嵌套字符类与集合运算等都视为不兼容
+fn is_js_compatible(pattern: &str) -> bool {
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('b' | 'x') if chars.peek() == Some(&'{') => return false,
+                Some('<' | '>') => return false,
+                Some(e) if "dDwWsSbBntrfvx".contains(e) || e.is_ascii_punctuation() => {}
+                _ => return false,
+            },
+            '[' if in_class => return false,
+            '[' => {
+                in_class = true;
+                chars.next_if_eq(&'^');
+                // Rust 中 `[]` 开头的 `]` 是字面量，JavaScript 中是空字符类
+                if chars.peek() == Some(&']') {
+                    return false;
+                }
+            }
+            ']' if in_class => in_class = false,
+            '&' | '-' | '~' if in_class && chars.peek() == Some(&c) => return false,
+            // `(?` 开头的分组只允许非捕获分组 `(?:`
+            '(' if !in_class && chars.next_if_eq(&'?').is_some() && chars.next() != Some(':') => {
+                return false;
+            }
+            _ => {}
+        }
+    }
+    !in_class
+}
+
+/// JavaScript 字符串字面量（JSON 字符串即合法的 JS 字符串）
+fn js_string(s: &str) -> String {
+    serde_json::Value::from(s).to_string()
+}
@@ -2,16 +2,24 @@ use std::sync::Arc;
 
 use derive_builder::Builder;
 use http::header::HeaderValue;
-use http::header::{CONNECTION, HOST, ORIGIN, UPGRADE};
-use http::{Request, Uri};
+use http::header::{CONNECTION, CONTENT_LOCATION, HOST, LOCATION, ORIGIN, REFRESH, UPGRADE};
+use http::{Method, Request, Response, Uri};
 use hudsucker::{
-    Body, HttpContext, HttpHandler, RequestOrResponse, WebSocketContext, WebSocketHandler,
-    tokio_tungstenite::tungstenite::Message,
+    Body, Dialer, HttpContext, HttpHandler, PreserveHost, RequestOrResponse, WebSocketContext,
+    WebSocketHandler, tokio_tungstenite::tungstenite::Message,
 };
 use tokio::sync::RwLock;
 use tracing::{debug, error};
 
-use crate::ProxyManager;
+use crate::fault::{abort_mid_body, error_response};
+use crate::har::HarEntry;
+use crate::recording::{RecordEntry, not_recorded};
+use crate::{
+    Address, Block, BodyRewrite, CookieOrigin, CookieRewrite, Fault, FaultRng, HarRecorder,
+    HeaderPreset, HeaderRewrite, HostHeader, LocalFiles, MatchResult, NetworkProfile, Pac,
+    ProxyManager, ProxyRule, RecordMode, RecordReplay, RequestTemplate, RuleAction,
+    UpstreamConnector,
+};
 
 #[derive(Clone, Builder)]
 #[builder(pattern = "owned", name = "ProxyHandlerBuilder")]
@@ -20,6 +28,133 @@ pub struct ProxyHandler {
     proxy_manager: Arc<RwLock<ProxyManager>>,
     #[builder(default = false)]
     with_ca: bool, // 是否启用自签名 CA 证书生成
+    // 故障注入的随机数生成器（可指定种子以复现）
+    #[builder(default)]
+    fault_rng: FaultRng,
+    // 全局模拟的网络状况（规则未单独设置 throttle 时使用）
+    #[builder(default)]
+    throttle: Option<NetworkProfile>,
+    // 记录经过代理的请求与响应（HAR）
+    #[builder(default)]
+    har: Option<HarRecorder>,
+    // 录制或回放上游响应
+    #[builder(default)]
+    recording: Option<RecordReplay>,
+    // 直接访问代理端口的 `/proxy.pac` 时按当前规则返回 PAC 脚本
+    #[builder(default)]
+    pac: Option<Pac>,
+    // 当前请求是否需要中断响应体（hudsucker 为每个请求克隆 handler）
+    #[builder(setter(skip), default)]
+    abort_response: bool,
+    // 当前请求命中规则的响应头改写
+    #[builder(setter(skip), default)]
+    response_headers: Option<ResponseHeaders>,
+    // 当前请求命中规则的响应体改写
+    #[builder(setter(skip), default)]
+    response_body: Option<BodyRewrite>,
+    // 当前请求转发后，响应中指向目标地址的 URL 的还原方式
+    #[builder(setter(skip), default)]
+    restore_location: Option<LocationRestore>,
+    // 当前请求进行中的 HAR 记录
+    #[builder(setter(skip), default)]
+    har_entry: Option<HarEntry>,
+    // 当前请求进行中的录制
+    #[builder(setter(skip), default)]
+    record_entry: Option<RecordEntry>,
+}
+
+/// 某个请求的响应头改写：命中规则的预设、Cookie 改写与显式改写，以及它们参照的原始请求
+#[derive(Clone)]
+struct ResponseHeaders {
+    presets: Vec<HeaderPreset>,
+    cookies: CookieRewrite,
+    rewrite: HeaderRewrite,
+    origin: Option<HeaderValue>,
+    cookie_origin: CookieOrigin,
+}
+
+impl ResponseHeaders {
+    fn for_rule(rule: &ProxyRule, req: &Request<Body>, new_uri: &Uri) -> Option<Self> {
+        if rule.response_presets.is_empty()
+            && rule.response_headers.is_empty()
+            && rule.cookies == CookieRewrite::disabled()
+        {
+            return None;
+        }
+        Some(Self {
+            presets: rule.response_presets.clone(),
+            cookies: rule.cookies.clone(),
+            rewrite: rule.response_headers.clone(),
+            origin: req.headers().get(ORIGIN).cloned(),
+            cookie_origin: CookieOrigin::new(req.uri(), new_uri),
+        })
+    }
+
+    /// 依次应用预设、Cookie 改写与显式的改写
+    fn apply(&self, res: &mut Response<Body>) {
+        for preset in &self.presets {
+            preset.apply(res.headers_mut(), self.origin.as_ref());
+        }
+        self.cookies.apply(res.headers_mut(), &self.cookie_origin);
+        self.rewrite.apply(res.headers_mut());
+    }
+}
+
+/// 把转发的响应中指向目标地址的 URL（`Location`、`Content-Location` 与 `Refresh`）还原回原始站点
+#[derive(Clone)]
+struct LocationRestore {
+    target: Address,
+    original_uri: Uri,
+    matched_prefix: Option<String>,
+}
+
+impl LocationRestore {
+    fn apply(&self, res: &mut Response<Body>) {
+        for name in [LOCATION, CONTENT_LOCATION, REFRESH] {
+            let restored = res
+                .headers()
+                .get(&name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|value| {
+                    if name == REFRESH {
+                        self.restore_refresh(value)
+                    } else {
+                        self.restore_url(value)
+                    }
+                })
+                .and_then(|value| HeaderValue::from_str(&value).ok());
+            if let Some(value) = restored {
+                debug!("Restoring {}: {:?}", name, value);
+                res.headers_mut().insert(name, value);
+            }
+        }
+    }
+
+    /// 还原绝对 URL；相对 URL 与不指向目标地址的 URL 返回 None
+    fn restore_url(&self, url: &str) -> Option<String> {
+        // http::Uri 不支持片段，先拆出再拼回
+        let (url, fragment) = match url.trim().split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (url.trim(), None),
+        };
+        let restored = self.target.restore_uri(
+            &url.parse().ok()?,
+            &self.original_uri,
+            self.matched_prefix.as_deref(),
+        )?;
+        Some(match fragment {
+            Some(fragment) => format!("{}#{}", restored, fragment),
+            None => restored.to_string(),
+        })
+    }
+
+    /// `Refresh: 5; url=http://localhost:8080/next`
+    fn restore_refresh(&self, value: &str) -> Option<String> {
+        let idx = value.to_ascii_lowercase().find("url=")? + "url=".len();
+        let (head, url) = value.split_at(idx);
+        let restored = self.restore_url(url.trim().trim_matches(['\'', '"']))?;
+        Some(format!("{}{}", head, restored))
+    }
 }
 
 impl ProxyHandler {
@@ -61,24 +196,80 @@ impl ProxyHandler {
     }
 
     fn rewrite_websocket_host(req: &mut Request<Body>) {
-        if let Some(authority) = req.uri().authority() {
-            if let Ok(value) = HeaderValue::from_str(authority.as_str()) {
-                req.headers_mut().insert(HOST, value);
+        if let Some(authority) = req.uri().authority()
+            && let Ok(value) = HeaderValue::from_str(authority.as_str())
+        {
+            req.headers_mut().insert(HOST, value);
+        }
+    }
+
+    /// 按规则设置转发请求的 `Host` 头
+    ///
+    /// 普通 HTTP 请求转发前 hudsucker 会删除 `Host` 并由 hyper 按 URI 重新生成，
+    /// 因此非默认取值需要附带 `PreserveHost` 标记；WebSocket 握手则直接使用请求中的 `Host`。
+    fn apply_host_header(
+        req: &mut Request<Body>,
+        host_header: &HostHeader,
+        original_host: Option<HeaderValue>,
+        is_ws_upgrade: bool,
+    ) {
+        let value = match host_header {
+            HostHeader::Target => {
+                if is_ws_upgrade {
+                    Self::rewrite_websocket_host(req);
+                }
+                return;
             }
+            HostHeader::Preserve => original_host,
+            HostHeader::Value(value) => HeaderValue::from_str(value).ok(),
+        };
+
+        if let Some(value) = value {
+            req.headers_mut().insert(HOST, value);
+            req.extensions_mut().insert(PreserveHost);
+        }
+    }
+
+    /// 客户端请求的原始 `Host`：优先取请求头（HTTP/1），否则取 URI 中的 authority（HTTP/2）
+    fn original_host(req: &Request<Body>) -> Option<HeaderValue> {
+        req.headers().get(HOST).cloned().or_else(|| {
+            req.uri()
+                .authority()
+                .and_then(|authority| HeaderValue::from_str(authority.as_str()).ok())
+        })
+    }
+
+    /// Unix 域套接字目标：通过 hudsucker 的 `UnixSocket` 扩展让连接走套接字，
+    /// URI 中保留原始 authority 作为 `Host`。返回 false 表示当前平台无法转发该目标
+    #[cfg(unix)]
+    fn route_to_unix_socket(req: &mut Request<Body>, target: &Address) -> bool {
+        if let Some(socket) = target.unix_socket_path() {
+            req.extensions_mut()
+                .insert(hudsucker::UnixSocket(socket.to_path_buf()));
         }
+        true
     }
 
-    async fn rewrite_request_uri(&self, uri: &Uri) -> Option<Uri> {
+    #[cfg(not(unix))]
+    fn route_to_unix_socket(_req: &mut Request<Body>, target: &Address) -> bool {
+        if let Some(socket) = target.unix_socket_path() {
+            error!(
+                "Unix socket targets are not supported on this platform: {}",
+                socket.display()
+            );
+            return false;
+        }
+        true
+    }
+
+    async fn rewrite_request_uri(&self, uri: &Uri) -> Option<(Uri, MatchResult)> {
         let manager = self.proxy_manager.read().await;
         let match_result = manager.find_target_with_match_info(uri).await?;
 
-        match match_result
-            .target
-            .to_uri_with_rewrite(uri, match_result.matched_path_prefix.as_deref())
-        {
+        match match_result.rewrite_uri(uri) {
             Ok(new_uri) => {
                 debug!("Proxying {} -> {}", uri, new_uri);
-                Some(new_uri)
+                Some((new_uri, match_result))
             }
             Err(e) => {
                 error!("Failed to convert target to URI: {}", e);
@@ -87,6 +278,53 @@ impl ProxyHandler {
         }
     }
 
+    /// 不经上游直接返回的响应：同样改写响应头、模拟网络状况、记录 HAR，被抽中中断响应体时同样截断
+    async fn local_response(
+        &mut self,
+        mut res: Response<Body>,
+        throttle: Option<NetworkProfile>,
+    ) -> RequestOrResponse {
+        if let Some(body) = &self.response_body {
+            res = body.apply(res).await;
+        }
+        if let Some(restore) = &self.restore_location {
+            restore.apply(&mut res);
+        }
+        if let Some(headers) = &self.response_headers {
+            headers.apply(&mut res);
+        }
+        let res = match throttle.map(|profile| profile.throttle()) {
+            Some(throttle) => {
+                tokio::time::sleep(throttle.latency).await;
+                res.map(|body| throttle.download_body(body))
+            }
+            None => res,
+        };
+        let res = self.finish_har(res);
+        if self.abort_response {
+            abort_mid_body(res).into()
+        } else {
+            res.into()
+        }
+    }
+
+    /// 结束当前请求的 HAR 记录（响应体传输结束后写入）
+    fn finish_har(&mut self, res: Response<Body>) -> Response<Body> {
+        match self.har_entry.take() {
+            Some(entry) => entry.finish(res),
+            None => res,
+        }
+    }
+
+    /// CONNECT 隧道适用的网络状况：先按规则的主机与端口查找，否则使用全局配置
+    async fn tunnel_throttle(&self, uri: &Uri) -> Option<NetworkProfile> {
+        let authority = uri.authority()?;
+        let manager = self.proxy_manager.read().await;
+        manager
+            .throttle_for_tunnel(authority.host(), authority.port_u16().unwrap_or(443))
+            .or(self.throttle)
+    }
+
     fn should_intercept_connect(&self) -> bool {
         self.with_ca
     }
@@ -98,8 +336,20 @@ impl HttpHandler for ProxyHandler {
         _ctx: &HttpContext,
         mut req: Request<Body>,
     ) -> RequestOrResponse {
+        if let Some(pac) = &self.pac
+            && let Some(res) = pac.respond(&*self.proxy_manager.read().await, &req)
+        {
+            return res.into();
+        }
         let is_ws_upgrade = Self::is_websocket_upgrade(&req);
         let original_uri = req.uri().clone();
+        // CONNECT 隧道与 WebSocket 握手不经过 handle_response，不记录
+        if let Some(har) = &self.har
+            && req.method() != Method::CONNECT
+            && !is_ws_upgrade
+        {
+            self.har_entry = Some(har.start(&mut req));
+        }
         if is_ws_upgrade {
             let stripped_extensions = Self::sanitize_websocket_upgrade(&mut req);
             debug!(
@@ -113,7 +363,24 @@ impl HttpHandler for ProxyHandler {
             );
         }
 
-        if let Some(new_uri) = self.rewrite_request_uri(&original_uri).await {
+        // 模拟网络状况：由 hudsucker 对请求/响应体、隧道与 WebSocket 消息限速并附加延迟
+        let mut throttle = if req.method() == Method::CONNECT {
+            self.tunnel_throttle(&original_uri).await
+        } else {
+            self.throttle
+        };
+
+        if let Some((new_uri, match_result)) = self.rewrite_request_uri(&original_uri).await {
+            let MatchResult {
+                rule,
+                matched_path_prefix,
+                ..
+            } = match_result;
+            throttle = rule.throttle.or(throttle);
+            if let Some(entry) = &mut self.har_entry {
+                // 只记录规则标识：规则 DSL 可能含有上游代理的凭据
+                entry.matched(rule.id(), &new_uri);
+            }
             if is_ws_upgrade {
                 debug!(
                     "WebSocket upstream rewrite: uri={} -> {}, host={:?}, origin={:?}",
@@ -123,15 +390,191 @@ impl HttpHandler for ProxyHandler {
                     req.headers().get(ORIGIN).and_then(|v| v.to_str().ok()),
                 );
             }
+            // 响应头改写在响应返回时应用；回显的 Origin 与 Cookie 参照的站点取自客户端的原始请求
+            self.response_headers = ResponseHeaders::for_rule(&rule, &req, &new_uri);
+            // HEAD 请求的响应没有响应体，不改写
+            if !rule.response_body.is_empty() && req.method() != Method::HEAD {
+                self.response_body = Some(rule.response_body.clone());
+            }
+            // 只还原转发的响应；重定向规则的 Location 本就指向目标地址
+            if rule.action.is_forward() {
+                self.restore_location = Some(LocationRestore {
+                    target: rule.target.clone(),
+                    original_uri: original_uri.clone(),
+                    matched_prefix: matched_path_prefix,
+                });
+            }
+
+            // 故障注入：先附加延迟，再按抽样结果返回错误、断开连接或标记中断响应体
+            if !rule.fault.is_empty() {
+                let (latency, fault) = rule.fault.sample(&self.fault_rng);
+                if let Some(latency) = latency {
+                    debug!("Delaying {} by {:?}", original_uri, latency);
+                    tokio::time::sleep(latency).await;
+                }
+                match fault {
+                    Some(Fault::Reset) => {
+                        debug!("Injecting connection reset for {}", original_uri);
+                        return Block::reset().respond().into();
+                    }
+                    Some(Fault::Error(status)) => {
+                        debug!("Injecting {} for {}", status, original_uri);
+                        return self.local_response(error_response(status), None).await;
+                    }
+                    // WebSocket 握手响应没有响应体，不做中断
+                    Some(Fault::Abort) if !is_ws_upgrade => {
+                        debug!("Injecting response abort for {}", original_uri);
+                        self.abort_response = true;
+                    }
+                    Some(Fault::Abort) | None => {}
+                }
+            }
+
+            // 放开跨域时直接应答预检请求，不转发
+            if rule.response_presets.iter().any(HeaderPreset::is_cors)
+                && HeaderPreset::is_preflight(&req)
+            {
+                debug!("Answering CORS preflight for {}", original_uri);
+                let response = HeaderPreset::preflight_response(&req);
+                return self.local_response(response, throttle).await;
+            }
+
+            // 请求头与请求体改写：预设响应的模板与本地目录的条件请求同样使用改写后的请求
+            rule.request_headers.apply(req.headers_mut());
+            if !rule.request_body.is_empty() {
+                req = rule.request_body.apply(req).await;
+            }
+
+            // 预设响应、重定向与拦截：直接返回，不连接目标
+            match &rule.action {
+                RuleAction::Forward => {}
+                RuleAction::Mock(mock) => {
+                    debug!("Mocking {} with status {}", original_uri, mock.status);
+                    let template = RequestTemplate::new(&req, &rule.pattern);
+                    let response = mock.respond(&template).await;
+                    return self.local_response(response, throttle).await;
+                }
+                RuleAction::Redirect(redirect) => {
+                    debug!(
+                        "Redirecting {} with status {}",
+                        original_uri, redirect.status
+                    );
+                    let template = RequestTemplate::new(&req, &rule.pattern);
+                    let response = redirect.respond(&template, &new_uri);
+                    return self.local_response(response, throttle).await;
+                }
+                RuleAction::Block(block) => {
+                    debug!("Blocking {}", original_uri);
+                    return self.local_response(block.respond(), throttle).await;
+                }
+            }
+
+            // 本地目录目标：直接读取文件作为响应，不再转发
+            if let Some(root) = rule.target.local_root() {
+                let path_and_query = new_uri.path_and_query().map_or("/", |pq| pq.as_str());
+                let response = LocalFiles::new(root)
+                    .with_spa_fallback(rule.spa_fallback)
+                    .serve(&req, path_and_query)
+                    .await;
+                return self.local_response(response, throttle).await;
+            }
+
+            let original_host = Self::original_host(&req);
             *req.uri_mut() = new_uri;
-            if is_ws_upgrade {
-                Self::rewrite_websocket_host(&mut req);
+            Self::apply_host_header(&mut req, &rule.host_header, original_host, is_ws_upgrade);
+            if !Self::route_to_unix_socket(&mut req, &rule.target) {
+                return Response::builder()
+                    .status(http::StatusCode::BAD_GATEWAY)
+                    .body(Body::empty())
+                    .expect("Failed to build response")
+                    .into();
+            }
+            // 规则指定了出站方式时随请求携带连接器，由 hudsucker 为该请求单独建立连接
+            if !rule.via.is_global() {
+                let connector = UpstreamConnector::default().with_via(rule.via.clone());
+                req.extensions_mut().insert(Dialer::new(connector));
+            }
+        }
+
+        // 录制与回放：在即将连接上游时按转发的请求计算键
+        if let Some(recording) = self.recording.clone()
+            && req.method() != Method::CONNECT
+            && !is_ws_upgrade
+        {
+            let (forwarded, key) = recording.key(req).await;
+            req = forwarded;
+            match (recording.mode(), key) {
+                (RecordMode::Record, Some(key)) => {
+                    self.record_entry = Some(recording.entry(&key, &req));
+                }
+                (RecordMode::Record, None) => {}
+                (RecordMode::Replay { strict }, key) => {
+                    let recorded = match key {
+                        Some(key) => recording.load(&key).await,
+                        None => None,
+                    };
+                    if let Some(response) = recorded {
+                        debug!("Replaying recorded response for {}", original_uri);
+                        return self.local_response(response, throttle).await;
+                    }
+                    if strict {
+                        error!("No recording for {} {}", req.method(), req.uri());
+                        let response = not_recorded(&req);
+                        return self.local_response(response, None).await;
+                    }
+                }
             }
         }
 
+        if let Some(profile) = throttle {
+            debug!(
+                "Throttling {} with network profile {}",
+                original_uri, profile
+            );
+            req.extensions_mut().insert(profile.throttle());
+        }
         req.into()
     }
 
+    async fn handle_response(
+        &mut self,
+        _ctx: &HttpContext,
+        mut res: Response<Body>,
+    ) -> Response<Body> {
+        // 录制改写之前的上游响应
+        if let Some(entry) = self.record_entry.take() {
+            res = entry.finish(res);
+        }
+        if let Some(body) = &self.response_body {
+            res = body.apply(res).await;
+        }
+        if let Some(restore) = &self.restore_location {
+            restore.apply(&mut res);
+        }
+        if let Some(headers) = &self.response_headers {
+            headers.apply(&mut res);
+        }
+        let res = self.finish_har(res);
+        if self.abort_response {
+            abort_mid_body(res)
+        } else {
+            res
+        }
+    }
+
+    async fn handle_error(
+        &mut self,
+        _ctx: &HttpContext,
+        err: hudsucker::hyper_util::client::legacy::Error,
+    ) -> Response<Body> {
+        error!("Failed to forward request: {}", err);
+        let res = Response::builder()
+            .status(http::StatusCode::BAD_GATEWAY)
+            .body(Body::empty())
+            .expect("Failed to build response");
+        self.finish_har(res)
+    }
+
     // 拦截所有 HTTPS 请求以进行证书生成
     async fn should_intercept(&mut self, _ctx: &HttpContext, _req: &Request<Body>) -> bool {
         // CONNECT 阶段通常拿不到完整 path，规则匹配可能不完整。
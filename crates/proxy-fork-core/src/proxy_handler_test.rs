@@ -32,7 +32,7 @@ async fn websocket_request_can_be_rewritten_by_http_handler() {
         .unwrap();
 
     let original_uri: Uri = "ws://ws.example.com/socket/chat?token=1".parse().unwrap();
-    let rewritten = handler.rewrite_request_uri(&original_uri).await.unwrap();
+    let (rewritten, _) = handler.rewrite_request_uri(&original_uri).await.unwrap();
 
     assert_eq!(
         rewritten.to_string(),
@@ -90,3 +90,104 @@ fn websocket_extensions_are_stripped_before_upstream() {
     assert!(ProxyHandler::sanitize_websocket_upgrade(&mut req));
     assert!(req.headers().get("sec-websocket-extensions").is_none());
 }
+
+#[tokio::test]
+async fn rewrite_request_uri_returns_rule_host_header() {
+    let mut manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .expect("Failed to construct ProxyManager from config");
+    let rule: crate::ProxyRule =
+        "http://app.example.com/api/* -> http://localhost:3000 [host=preserve]"
+            .parse()
+            .unwrap();
+    manager.add_proxy_rule(rule).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(manager)))
+        .build()
+        .unwrap();
+
+    let uri: Uri = "http://app.example.com/api/users".parse().unwrap();
+    let (rewritten, result) = handler.rewrite_request_uri(&uri).await.unwrap();
+    assert_eq!(rewritten.to_string(), "http://localhost:3000/api/users");
+    assert_eq!(result.rule.host_header, HostHeader::Preserve);
+    assert_eq!(result.matched_path_prefix.as_deref(), Some("/api/"));
+}
+
+#[test]
+fn host_header_is_applied_for_http_and_websocket() {
+    let request = |uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .header(HOST, "app.example.com")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // 默认：HTTP 交由 hyper 按目标 URI 生成 Host
+    let mut req = request("http://localhost:3000/");
+    let original = ProxyHandler::original_host(&req);
+    ProxyHandler::apply_host_header(&mut req, &HostHeader::Target, original, false);
+    assert!(req.extensions().get::<PreserveHost>().is_none());
+
+    // 默认：WebSocket 直接改写为目标 authority
+    let mut req = request("http://localhost:3000/");
+    let original = ProxyHandler::original_host(&req);
+    ProxyHandler::apply_host_header(&mut req, &HostHeader::Target, original, true);
+    assert_eq!(req.headers().get(HOST).unwrap(), "localhost:3000");
+
+    let mut req = request("http://localhost:3000/");
+    let original = ProxyHandler::original_host(&req);
+    ProxyHandler::apply_host_header(&mut req, &HostHeader::Preserve, original, true);
+    assert_eq!(req.headers().get(HOST).unwrap(), "app.example.com");
+    assert!(req.extensions().get::<PreserveHost>().is_some());
+
+    let mut req = request("http://localhost:3000/");
+    let value = HostHeader::Value("api.internal".to_string());
+    ProxyHandler::apply_host_header(&mut req, &value, None, false);
+    assert_eq!(req.headers().get(HOST).unwrap(), "api.internal");
+    assert!(req.extensions().get::<PreserveHost>().is_some());
+}
+
+#[test]
+fn original_host_falls_back_to_uri_authority() {
+    let req = Request::builder()
+        .uri("https://app.example.com:8443/")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        ProxyHandler::original_host(&req).unwrap(),
+        "app.example.com:8443"
+    );
+}
+
+#[test]
+fn location_headers_are_restored_to_original_origin() {
+    let restore = LocationRestore {
+        target: Address {
+            path_transform_mode: PathTransformMode::Prepend,
+            .."http://localhost:8080/local".parse::<Address>().unwrap()
+        },
+        original_uri: "https://app.example.com/account".parse().unwrap(),
+        matched_prefix: None,
+    };
+    let mut res = Response::builder()
+        .status(302)
+        .header(LOCATION, "http://localhost:8080/local/login?next=%2F#form")
+        .header(CONTENT_LOCATION, "http://cdn.example.com/local/a")
+        .header(REFRESH, "5; url='http://LOCALHOST:8080/local'")
+        .body(Body::empty())
+        .unwrap();
+    restore.apply(&mut res);
+
+    assert_eq!(
+        res.headers()[LOCATION],
+        "https://app.example.com/login?next=%2F#form"
+    );
+    // 不指向目标地址的 URL 保持原样
+    assert_eq!(
+        res.headers()[CONTENT_LOCATION],
+        "http://cdn.example.com/local/a"
+    );
+    assert_eq!(res.headers()[REFRESH], "5; url=https://app.example.com/");
+}
@@ -1,9 +1,13 @@
 use crate::{
-    Address, AddressPattern, PatternMatcher, Protocol, ProxyStatsSnapshot, stats_impl::ProxyStats,
+    Address, AddressPattern, Block, BodyRewrite, CookieRewrite, FaultInjection, HeaderPreset,
+    HeaderRewrite, HostHeader, MockResponse, NetworkProfile, PathTransformMode, PatternMatcher,
+    Protocol, ProxyStatsSnapshot, QueryRewrite, Redirect, RequestBodyRewrite, RuleAction, Via,
+    http_address::is_sed_expression, stats_impl::ProxyStats,
 };
 use derive_builder::Builder;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::num::NonZeroUsize;
+use std::ops::Deref;
 
 use http::Uri;
 use lru::LruCache;
@@ -11,16 +15,367 @@ use tokio::sync::Mutex;
 
 // 匹配模式类型
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PatternType {
     pub host: PatternMatcher,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub path: Option<PatternMatcher>,
 }
 
 // 代理规则：匹配模式 -> 目标地址
-#[derive(Debug, Clone)]
+#[derive(Builder, Debug, Clone)]
+#[builder(pattern = "owned")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProxyRule {
     pub pattern: AddressPattern,
     pub target: Address,
+    /// 规则所属分组（可选）；未分组的规则始终生效
+    #[builder(default, setter(into, strip_option))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub group: Option<String>,
+    /// 查询参数改写（在路径转换之后应用）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "QueryRewrite::is_empty")
+    )]
+    pub query: QueryRewrite,
+    /// 请求头改写（在路径转换之后、转发之前应用）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HeaderRewrite::is_empty")
+    )]
+    pub request_headers: HeaderRewrite,
+    /// 请求体改写（JSON 与表单，在请求头改写之后应用）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "RequestBodyRewrite::is_empty")
+    )]
+    pub request_body: RequestBodyRewrite,
+    /// 响应头预设（跨域、去除 CSP/HSTS、禁用缓存），在 `response_headers` 之前应用
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub response_presets: Vec<HeaderPreset>,
+    /// 响应头改写（包括预设响应、重定向等直接返回的响应）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HeaderRewrite::is_empty")
+    )]
+    pub response_headers: HeaderRewrite,
+    /// 响应体改写（字面量/正则替换与 HTML 片段注入）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BodyRewrite::is_empty")
+    )]
+    pub response_body: BodyRewrite,
+    /// `Set-Cookie` 改写（默认按原始请求自动改写 Domain、Path、Secure 与 SameSite）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "CookieRewrite::is_default")
+    )]
+    pub cookies: CookieRewrite,
+    /// 转发时 `Host` 请求头的取值方式（HTTP/HTTPS/WebSocket 一致）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HostHeader::is_target")
+    )]
+    pub host_header: HostHeader,
+    /// 连接目标时使用的上游代理（默认沿用全局配置）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Via::is_global")
+    )]
+    pub via: Via,
+    /// 本地目录目标（`file://`）中找不到且不含扩展名的路径回退到根目录的 `index.html`
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub spa_fallback: bool,
+    /// 命中后的处理方式（默认转发到 `target`；其他方式下不连接目标）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "RuleAction::is_forward")
+    )]
+    pub action: RuleAction,
+    /// 故障注入（延迟、错误、中断与断开连接）
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "FaultInjection::is_empty")
+    )]
+    pub fault: FaultInjection,
+    /// 模拟的网络状况（带宽与延迟）；未设置时沿用全局配置
+    #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub throttle: Option<NetworkProfile>,
+}
+
+impl ProxyRule {
+    pub fn new(pattern: AddressPattern, target: Address) -> Self {
+        Self {
+            pattern,
+            target,
+            group: None,
+            query: QueryRewrite::default(),
+            request_headers: HeaderRewrite::default(),
+            request_body: RequestBodyRewrite::default(),
+            response_presets: Vec::new(),
+            response_headers: HeaderRewrite::default(),
+            response_body: BodyRewrite::default(),
+            cookies: CookieRewrite::default(),
+            host_header: HostHeader::default(),
+            via: Via::default(),
+            spa_fallback: false,
+            action: RuleAction::default(),
+            fault: FaultInjection::default(),
+            throttle: None,
+        }
+    }
+
+    /// 规则的标识：匹配模式（省略默认端口），属于分组时附加 `#分组`，
+    /// 如 `https://api.example.com/v1/*`、`https://api.example.com/v1/*#staging`
+    ///
+    /// 同一管理器中的规则按标识区分，用于查找、更新、删除与单独启用/禁用
+    pub fn id(&self) -> String {
+        let id = pattern_id(&self.pattern);
+        match &self.group {
+            Some(group) => format!("{}#{}", id, group),
+            None => id,
+        }
+    }
+
+    /// 计算转发 URI：先按目标地址的路径转换模式改写路径，再应用查询参数改写
+    pub fn rewrite_uri(
+        &self,
+        original_uri: &Uri,
+        matched_prefix: Option<&str>,
+    ) -> Result<Uri, http::Error> {
+        let uri = self
+            .target
+            .to_uri_with_rewrite(original_uri, matched_prefix)?;
+        if self.query.is_empty() {
+            return Ok(uri);
+        }
+
+        let path_and_query = uri.path_and_query().map_or("/", |pq| pq.as_str());
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = Some(self.query.apply(path_and_query).parse()?);
+        Ok(Uri::from_parts(parts)?)
+    }
+
+    /// 未显式指定修饰符时的路径转换模式：目标带路径则前缀拼接，否则保留原始路径
+    fn implied_transform_mode(target: &Address) -> PathTransformMode {
+        if target.path.is_some() {
+            PathTransformMode::Prepend
+        } else {
+            PathTransformMode::Preserve
+        }
+    }
+}
+
+/// 单行规则 DSL：`<pattern> -> <target> [modifier, ...]`
+///
+/// ```text
+/// https://*.example.com/api/* -> http://localhost:8080/local
+/// https://api.example.com/v1/* -> http://127.0.0.1:9090/v2 [replace]
+/// https://pay.example.com -> http://127.0.0.1:7001 [group=mock-payments]
+/// ```
+///
+/// 修饰符可以是路径转换模式（preserve|prepend|replace|strip_prefix|`s#regex#replacement#`）、
+/// `group=<name>`、`host=preserve|target|<value>`、`via=direct|<proxy-url>`
+/// `spa`（本地目录目标的 SPA 回退）、`mock=<status>`（返回空响应体的预设响应）、
+/// `redirect[=<status>]`（重定向到改写后的目标地址）、`block[=<status>|reset]`（拦截）、
+/// `throttle=<profile>`（模拟网络状况）或响应头预设 `cors[=<origin>]`、`strip-csp`、`no-cache`，
+/// 多个修饰符用逗号分隔，也可以写成多个方括号
+impl std::str::FromStr for ProxyRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, rest) = s
+            .split_once("->")
+            .ok_or_else(|| format!("missing '->' in rule: {}", s.trim()))?;
+        let pattern: AddressPattern = pattern.trim().parse()?;
+
+        // 目标地址与修饰符之间以空白分隔（目标中可能含有 IPv6 的方括号）
+        let (target, modifiers) = rest
+            .trim()
+            .split_once(char::is_whitespace)
+            .map_or((rest.trim(), ""), |(t, m)| (t, m.trim()));
+        let mut target: Address = target.parse()?;
+
+        let mut mode = None;
+        let mut group = None;
+        let mut host_header = HostHeader::default();
+        let mut via = Via::default();
+        let mut spa_fallback = false;
+        let mut action = RuleAction::default();
+        let mut throttle = None;
+        let mut response_presets = Vec::new();
+        for item in split_modifiers(modifiers)? {
+            match item.split_once('=') {
+                Some(("group", name)) if !is_sed_expression(item) => {
+                    group = Some(name.trim().to_string());
+                }
+                Some(("host", value)) if !is_sed_expression(item) => {
+                    host_header = value.parse()?;
+                }
+                Some(("via", value)) if !is_sed_expression(item) => {
+                    via = value.parse()?;
+                }
+                Some(("mock", status)) if !is_sed_expression(item) => {
+                    let mock = MockResponse::new(parse_status(status)?);
+                    mock.validate()?;
+                    action = RuleAction::Mock(mock);
+                }
+                Some(("redirect", status)) if !is_sed_expression(item) => {
+                    action = RuleAction::Redirect(Redirect::new(parse_status(status)?)?);
+                }
+                Some(("throttle", profile)) if !is_sed_expression(item) => {
+                    throttle = Some(profile.parse()?);
+                }
+                Some(("cors", origin)) if !is_sed_expression(item) => {
+                    response_presets.push(format!("cors={}", origin).parse()?);
+                }
+                Some(("block", "reset")) => action = RuleAction::Block(Block::reset()),
+                Some(("block", status)) if !is_sed_expression(item) => {
+                    action = RuleAction::Block(Block::new(parse_status(status)?)?);
+                }
+                None if item == "redirect" => action = RuleAction::Redirect(Redirect::default()),
+                None if item == "block" => action = RuleAction::Block(Block::default()),
+                None if item == "spa" => spa_fallback = true,
+                None if matches!(item, "cors" | "strip-csp" | "no-cache") => {
+                    response_presets.push(item.parse()?);
+                }
+                Some((key, _)) if !is_sed_expression(item) => {
+                    return Err(format!("unknown modifier: {}", key));
+                }
+                _ => mode = Some(item.parse::<PathTransformMode>()?),
+            }
+        }
+
+        target.path_transform_mode =
+            mode.unwrap_or_else(|| ProxyRule::implied_transform_mode(&target));
+
+        Ok(Self {
+            group,
+            host_header,
+            via,
+            spa_fallback,
+            action,
+            throttle,
+            response_presets,
+            ..Self::new(pattern, target)
+        })
+    }
+}
+
+fn parse_status(status: &str) -> Result<u16, String> {
+    status
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid status code: {}", status))
+}
+
+/// 拆分方括号中的修饰符：`[a, b] [c]` -> `["a", "b", "c"]`
+///
+/// sed 风格表达式按分隔符整体读取，其中可以包含 `,` 与 `]`
+fn split_modifiers(s: &str) -> Result<Vec<&str>, String> {
+    let invalid = || format!("invalid modifiers: {}", s);
+
+    let mut items = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        rest = rest.strip_prefix('[').ok_or_else(invalid)?;
+        loop {
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+            if let Some(after) = rest.strip_prefix(']') {
+                rest = after.trim_start();
+                break;
+            }
+
+            let len = if is_sed_expression(rest) {
+                // s + 分隔符 + 正则 + 分隔符 + 模板 + 分隔符
+                let delimiter = rest[1..].chars().next().ok_or_else(invalid)?;
+                let body_start = 1 + delimiter.len_utf8();
+                let body = &rest[body_start..];
+                let mut ends = body.match_indices(delimiter).map(|(idx, _)| idx);
+                let end = ends.nth(1).ok_or_else(invalid)?;
+                body_start + end + delimiter.len_utf8()
+            } else {
+                rest.find([',', ']']).ok_or_else(invalid)?
+            };
+
+            let item = rest[..len].trim();
+            if !item.is_empty() {
+                items.push(item);
+            }
+            rest = &rest[len..];
+        }
+    }
+    Ok(items)
+}
+
+/// 以单行 DSL 形式输出规则，可被 `FromStr` 重新解析
+impl std::fmt::Display for ProxyRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {}", self.pattern, self.target)?;
+
+        let mut modifiers = Vec::new();
+        if self.target.path_transform_mode != Self::implied_transform_mode(&self.target) {
+            modifiers.push(self.target.path_transform_mode.to_string());
+        }
+        if let Some(group) = &self.group {
+            modifiers.push(format!("group={}", group));
+        }
+        if !self.host_header.is_target() {
+            modifiers.push(format!("host={}", self.host_header));
+        }
+        if !self.via.is_global() {
+            modifiers.push(format!("via={}", self.via));
+        }
+        if self.spa_fallback {
+            modifiers.push("spa".to_string());
+        }
+        match &self.action {
+            RuleAction::Forward => {}
+            RuleAction::Mock(mock) => modifiers.push(format!("mock={}", mock.status)),
+            RuleAction::Redirect(redirect) => {
+                modifiers.push(format!("redirect={}", redirect.status));
+            }
+            RuleAction::Block(block) if block.reset => modifiers.push("block=reset".to_string()),
+            RuleAction::Block(block) => modifiers.push(format!("block={}", block.status)),
+        }
+        if let Some(throttle) = &self.throttle {
+            modifiers.push(format!("throttle={}", throttle));
+        }
+        modifiers.extend(self.response_presets.iter().map(ToString::to_string));
+        if !modifiers.is_empty() {
+            write!(f, " [{}]", modifiers.join(", "))?;
+        }
+        Ok(())
+    }
 }
 
 // 匹配结果：包含目标地址和匹配的路径前缀
@@ -30,6 +385,16 @@ pub struct MatchResult {
     /// 匹配到的路径前缀（用于路径替换）
     /// 例如：pattern 是 "/console/api/*"，则 matched_path_prefix 是 "/console/api"
     pub matched_path_prefix: Option<String>,
+    /// 命中的完整规则（包含分组、查询改写等附加配置）
+    pub rule: ProxyRule,
+}
+
+impl MatchResult {
+    /// 计算转发 URI，见 [`ProxyRule::rewrite_uri`]
+    pub fn rewrite_uri(&self, original_uri: &Uri) -> Result<Uri, http::Error> {
+        self.rule
+            .rewrite_uri(original_uri, self.matched_path_prefix.as_deref())
+    }
 }
 
 // 精确匹配的索引键
@@ -41,6 +406,38 @@ pub struct ExactKey {
     path: Option<String>,
 }
 
+/// 管理器中保存的规则，附带预先计算的标识
+#[derive(Debug, Clone)]
+struct StoredRule {
+    id: String,
+    rule: ProxyRule,
+}
+
+impl StoredRule {
+    fn new(rule: ProxyRule) -> Self {
+        Self {
+            id: rule.id(),
+            rule,
+        }
+    }
+}
+
+impl Deref for StoredRule {
+    type Target = ProxyRule;
+
+    fn deref(&self) -> &ProxyRule {
+        &self.rule
+    }
+}
+
+fn pattern_id(pattern: &AddressPattern) -> String {
+    AddressPattern {
+        port: ExactKey::normalize_port(pattern.protocol, pattern.port),
+        ..pattern.clone()
+    }
+    .to_string()
+}
+
 impl ExactKey {
     fn normalize_port(protocol: Protocol, port: Option<u16>) -> Option<u16> {
         match (protocol, port) {
@@ -83,15 +480,27 @@ impl std::fmt::Display for ExactKey {
 #[derive(Debug)]
 // 代理管理器（优化版：混合索引 + LRU 缓存）
 pub struct ProxyManager {
-    // 精确匹配的快速索引 (O(1) 查找)
-    exact_rules: HashMap<ExactKey, Address>,
+    // 精确匹配的快速索引 (O(1) 查找)；同一模式可以在不同分组中各有一条规则
+    exact_rules: HashMap<ExactKey, Vec<StoredRule>>,
 
     // 通配符和正则规则（需要遍历，但数量通常较少）
-    pattern_rules: Vec<ProxyRule>,
+    pattern_rules: Vec<StoredRule>,
 
     // LRU 缓存（缓存最近查询结果）- 使用 Mutex 实现内部可变性
     cache: Mutex<LruCache<String, Option<Address>>>,
 
+    // 已禁用的规则分组
+    disabled_groups: HashSet<String>,
+
+    // 按 profile 启动时允许的分组；其余分组（包括之后新增的）默认禁用
+    profile: Option<HashSet<String>>,
+
+    // 单独禁用的规则（规则标识）
+    disabled_rules: HashSet<String>,
+
+    // 是否有通过管理接口做的运行时修改（热重载时会被配置覆盖）
+    runtime_modified: bool,
+
     // 性能统计（原子）
     stats: ProxyStats,
 }
@@ -106,7 +515,7 @@ pub struct ProxyManagerConfig {
 
     /// 初始精确规则（可选）
     #[builder(default = "std::collections::HashMap::new()")]
-    pub exact_rules: std::collections::HashMap<ExactKey, Address>,
+    pub exact_rules: std::collections::HashMap<ExactKey, ProxyRule>,
 
     /// 初始模式规则（可选）
     #[builder(default = "Vec::new()")]
@@ -119,9 +528,17 @@ impl ProxyManager {
         let cache_size = NonZeroUsize::new(cfg.cache_size).ok_or("cache_size must be non-zero")?;
 
         Ok(Self {
-            exact_rules: cfg.exact_rules,
-            pattern_rules: cfg.pattern_rules,
+            exact_rules: cfg
+                .exact_rules
+                .into_iter()
+                .map(|(key, rule)| (key, vec![StoredRule::new(rule)]))
+                .collect(),
+            pattern_rules: cfg.pattern_rules.into_iter().map(StoredRule::new).collect(),
             cache: Mutex::new(LruCache::new(cache_size)),
+            disabled_groups: HashSet::new(),
+            profile: None,
+            disabled_rules: HashSet::new(),
+            runtime_modified: false,
             stats: ProxyStats::default(),
         })
     }
@@ -155,7 +572,13 @@ impl std::fmt::Display for ProxyManager {
         let mut exact_rules: Vec<(String, String)> = self
             .exact_rules
             .iter()
-            .map(|(key, target)| (key.to_string(), target.to_string()))
+            .flat_map(|(key, rules)| rules.iter().map(move |rule| (key, rule)))
+            .map(|(key, rule)| {
+                (
+                    key.to_string(),
+                    format!("{}{}", rule.target, self.group_suffix(rule)),
+                )
+            })
             .collect();
         exact_rules.sort_unstable_by(|a, b| a.0.cmp(&b.0));
 
@@ -187,7 +610,14 @@ impl std::fmt::Display for ProxyManager {
                 .take(MAX_SHOW_PER_SECTION)
                 .enumerate()
             {
-                writeln!(f, "  {:>2}. {} -> {}", idx + 1, rule.pattern, rule.target)?;
+                writeln!(
+                    f,
+                    "  {:>2}. {} -> {}{}",
+                    idx + 1,
+                    rule.pattern,
+                    rule.target,
+                    self.group_suffix(rule)
+                )?;
             }
             if self.pattern_rules.len() > MAX_SHOW_PER_SECTION {
                 writeln!(
@@ -207,15 +637,18 @@ impl ProxyManager {
     ///
     /// 规则会自动分类到精确索引或模式列表中以优化查找性能
     pub async fn add_rule(&mut self, pattern: AddressPattern, target: Address) {
-        // 检查是否为精确匹配（可以使用快速索引）
-        let is_exact = matches!(&pattern.pattern_type.host, PatternMatcher::Exact(_))
-            && pattern
-                .pattern_type
-                .path
-                .as_ref()
-                .is_none_or(|p| matches!(p, PatternMatcher::Exact(_)));
+        self.add_proxy_rule(ProxyRule::new(pattern, target)).await;
+    }
 
-        if is_exact {
+    /// 添加完整的代理规则（可携带分组等附加信息）
+    ///
+    /// 已有标识相同（同一分组内的同一模式）的规则时替换该规则
+    pub async fn add_proxy_rule(&mut self, rule: ProxyRule) {
+        let rule = StoredRule::new(rule);
+        let pattern = &rule.pattern;
+
+        // 检查是否为精确匹配（可以使用快速索引）
+        if Self::is_exact_pattern(pattern) {
             // 提取精确匹配的键
             if let PatternMatcher::Exact(host) = &pattern.pattern_type.host {
                 let path = pattern.pattern_type.path.as_ref().and_then(|p| {
@@ -228,18 +661,117 @@ impl ProxyManager {
 
                 let key = ExactKey::new(pattern.protocol, host.clone(), pattern.port, path);
 
-                self.exact_rules.insert(key, target);
+                // 同一分组内的同一模式替换旧规则，不同分组的规则并存
+                let rules = self.exact_rules.entry(key).or_default();
+                match rules.iter_mut().find(|r| r.id == rule.id) {
+                    Some(existing) => *existing = rule,
+                    None => rules.push(rule),
+                }
+                self.cache.lock().await.clear();
                 return;
             }
         }
 
-        // 非精确匹配，添加到模式列表
-        self.pattern_rules.push(ProxyRule { pattern, target });
+        // 非精确匹配，添加到模式列表（替换时保持原有位置）
+        match self.pattern_rules.iter_mut().find(|r| r.id == rule.id) {
+            Some(existing) => *existing = rule,
+            None => self.pattern_rules.push(rule),
+        }
 
         // 清空缓存（规则变化）
         self.cache.lock().await.clear();
     }
 
+    /// 按标识查找规则
+    pub fn get_rule(&self, id: &str) -> Option<ProxyRule> {
+        let id = Self::normalize_rule_id(id);
+        self.stored_rules()
+            .find(|rule| rule.id == id)
+            .map(|rule| rule.rule.clone())
+    }
+
+    /// 按标识删除规则，返回被删除的规则
+    pub async fn remove_rule(&mut self, id: &str) -> Option<ProxyRule> {
+        let id = Self::normalize_rule_id(id);
+        let removed = if let Some((key, pos)) = self.exact_rules.iter().find_map(|(key, rules)| {
+            let pos = rules.iter().position(|rule| rule.id == id)?;
+            Some((key.clone(), pos))
+        }) {
+            let rules = self.exact_rules.get_mut(&key)?;
+            let removed = rules.remove(pos);
+            if rules.is_empty() {
+                self.exact_rules.remove(&key);
+            }
+            Some(removed.rule)
+        } else {
+            let pos = self.pattern_rules.iter().position(|rule| rule.id == id)?;
+            Some(self.pattern_rules.remove(pos).rule)
+        };
+
+        self.disabled_rules.remove(&id);
+        self.cache.lock().await.clear();
+        removed
+    }
+
+    /// 按标识替换规则，返回被替换的规则；规则不存在时返回 None 且不添加
+    ///
+    /// 模式规则原位替换以保持匹配顺序；单独禁用的状态随规则保留。
+    /// 新规则的标识与另一条规则相同时，那条规则会被删除
+    pub async fn replace_rule(&mut self, id: &str, rule: ProxyRule) -> Option<ProxyRule> {
+        let id = Self::normalize_rule_id(id);
+        if !self.stored_rules().any(|r| r.id == id) {
+            return None;
+        }
+        let disabled = self.disabled_rules.contains(&id);
+        let new_id = rule.id();
+        // 先删除标识冲突的规则，避免同一标识出现两次
+        if new_id != id {
+            self.remove_rule(&new_id).await;
+        }
+
+        let pos = self.pattern_rules.iter().position(|r| r.id == id);
+        let old = match pos {
+            Some(pos) if !Self::is_exact_pattern(&rule.pattern) => {
+                let old = std::mem::replace(&mut self.pattern_rules[pos], StoredRule::new(rule));
+                self.disabled_rules.remove(&id);
+                self.cache.lock().await.clear();
+                old.rule
+            }
+            _ => {
+                let old = self.remove_rule(&id).await?;
+                self.add_proxy_rule(rule).await;
+                old
+            }
+        };
+
+        if disabled {
+            self.disabled_rules.insert(new_id);
+        }
+        Some(old)
+    }
+
+    /// 规则是否被单独禁用
+    pub fn is_rule_disabled(&self, id: &str) -> bool {
+        self.disabled_rules.contains(&Self::normalize_rule_id(id))
+    }
+
+    /// 单独启用或禁用规则，返回状态是否发生变化
+    ///
+    /// 规则所在分组被禁用时，单独启用的规则同样不生效
+    pub async fn set_rule_enabled(&mut self, id: &str, enabled: bool) -> bool {
+        let id = Self::normalize_rule_id(id);
+        let changed = if enabled {
+            self.disabled_rules.remove(&id)
+        } else {
+            self.disabled_rules.insert(id)
+        };
+
+        if changed {
+            self.cache.lock().await.clear();
+        }
+        changed
+    }
+
     /// 从 Uri 查找匹配的目标地址（带缓存）
     pub async fn find_target(&self, uri: &Uri) -> Option<Address> {
         // 记录总查询（原子，低开销）
@@ -280,17 +812,18 @@ impl ProxyManager {
 
         // 1. 先查精确索引 (O(1))
         let key = ExactKey::from_address(&address);
-        if let Some(target) = self.exact_rules.get(&key) {
+        if let Some(rule) = self.find_exact(&key) {
             self.stats.inc_exact();
             return Some(MatchResult {
-                target: target.clone(),
+                target: rule.target.clone(),
                 matched_path_prefix: key.path.clone(),
+                rule: rule.rule.clone(),
             });
         }
 
         // 2. 遍历模式规则 (O(n)，但 n 通常很小)
         for rule in &self.pattern_rules {
-            if rule.pattern.matches(&address) {
+            if self.is_rule_enabled(rule) && rule.pattern.matches(&address) {
                 self.stats.inc_pattern();
 
                 // 提取匹配的路径前缀
@@ -314,6 +847,7 @@ impl ProxyManager {
                 return Some(MatchResult {
                     target: rule.target.clone(),
                     matched_path_prefix,
+                    rule: rule.rule.clone(),
                 });
             }
         }
@@ -326,14 +860,14 @@ impl ProxyManager {
     async fn find_target_for_address_uncached(&self, address: &Address) -> Option<Address> {
         // 1. 先查精确索引 (O(1))
         let key = ExactKey::from_address(address);
-        if let Some(target) = self.exact_rules.get(&key) {
+        if let Some(rule) = self.find_exact(&key) {
             self.stats.inc_exact();
-            return Some(target.clone());
+            return Some(rule.target.clone());
         }
 
         // 2. 遍历模式规则 (O(n)，但 n 通常很小)
         for rule in &self.pattern_rules {
-            if rule.pattern.matches(address) {
+            if self.is_rule_enabled(rule) && rule.pattern.matches(address) {
                 self.stats.inc_pattern();
                 return Some(rule.target.clone());
             }
@@ -347,7 +881,11 @@ impl ProxyManager {
         let mut rules = Vec::new();
 
         // 添加精确规则
-        for (key, target) in &self.exact_rules {
+        for (key, rule) in self
+            .exact_rules
+            .iter()
+            .flat_map(|(key, rules)| rules.iter().map(move |rule| (key, rule)))
+        {
             let pattern = AddressPattern {
                 protocol: key.protocol,
                 port: key.port,
@@ -358,24 +896,24 @@ impl ProxyManager {
             };
             rules.push(ProxyRule {
                 pattern,
-                target: target.clone(),
+                ..rule.rule.clone()
             });
         }
 
         // 添加模式规则
-        rules.extend(self.pattern_rules.clone());
+        rules.extend(self.pattern_rules.iter().map(|rule| rule.rule.clone()));
 
         rules
     }
 
-    /// 获取模式规则（仅通配符和正则）
-    pub fn pattern_rules(&self) -> &[ProxyRule] {
-        &self.pattern_rules
+    /// 获取模式规则（仅通配符和正则），按匹配顺序
+    pub fn pattern_rules(&self) -> impl ExactSizeIterator<Item = &ProxyRule> {
+        self.pattern_rules.iter().map(|rule| &rule.rule)
     }
 
     /// 获取精确规则数量
     pub fn exact_rule_count(&self) -> usize {
-        self.exact_rules.len()
+        self.exact_rules.values().map(Vec::len).sum()
     }
 
     /// 获取模式规则数量
@@ -398,12 +936,182 @@ impl ProxyManager {
     pub async fn clear(&mut self) {
         self.exact_rules.clear();
         self.pattern_rules.clear();
+        self.disabled_rules.clear();
         self.cache.lock().await.clear();
         self.stats.reset();
     }
 
+    /// 用 `other` 的规则、分组与启用状态及缓存整体替换当前内容（保留统计）
+    ///
+    /// 持有写锁时调用即可原子地切换规则集，用于配置热重载
+    pub fn replace_rules_with(&mut self, other: ProxyManager) {
+        self.exact_rules = other.exact_rules;
+        self.pattern_rules = other.pattern_rules;
+        self.disabled_groups = other.disabled_groups;
+        self.profile = other.profile;
+        self.disabled_rules = other.disabled_rules;
+        self.runtime_modified = other.runtime_modified;
+        self.cache = other.cache;
+    }
+
+    /// 标记规则集存在运行时修改（由管理接口调用）
+    pub fn mark_runtime_modified(&mut self) {
+        self.runtime_modified = true;
+    }
+
+    /// 自上次加载配置以来是否有运行时修改
+    pub fn is_runtime_modified(&self) -> bool {
+        self.runtime_modified
+    }
+
     /// 清空缓存（保留规则）
     pub async fn clear_cache(&self) {
         self.cache.lock().await.clear();
     }
+
+    /// 查找 CONNECT 隧道 `host:port` 适用的网络状况
+    ///
+    /// 隧道中看不到请求路径，按规则的主机与端口匹配（忽略 path），只考虑设置了 throttle 的规则
+    pub fn throttle_for_tunnel(&self, host: &str, port: u16) -> Option<NetworkProfile> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        self.stored_rules()
+            .filter(|rule| rule.throttle.is_some() && self.is_rule_enabled(rule))
+            .find(|rule| {
+                let pattern = &rule.pattern;
+                let default_port = if pattern.protocol == Protocol::Https {
+                    443
+                } else {
+                    80
+                };
+                pattern.port.unwrap_or(default_port) == port
+                    && pattern.pattern_type.host.matches(host)
+            })
+            .and_then(|rule| rule.throttle)
+    }
+
+    /// 获取所有规则中出现过的分组名（按名称排序）
+    pub fn groups(&self) -> Vec<String> {
+        self.stored_rules()
+            .filter_map(|rule| rule.group.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// 分组是否启用（未被显式禁用且在 profile 允许范围内的分组视为启用）
+    pub fn is_group_enabled(&self, group: &str) -> bool {
+        !self.disabled_groups.contains(group)
+            && self
+                .profile
+                .as_ref()
+                .is_none_or(|profile| profile.contains(group))
+    }
+
+    /// 启用或禁用整个分组，返回状态是否发生变化
+    ///
+    /// 分组不必已存在：提前禁用的分组在之后添加规则时同样生效。
+    /// 启用 profile 之外的分组会把它加入 profile
+    pub async fn set_group_enabled(&mut self, group: &str, enabled: bool) -> bool {
+        let was_enabled = self.is_group_enabled(group);
+        if enabled {
+            self.disabled_groups.remove(group);
+            if let Some(profile) = &mut self.profile {
+                profile.insert(group.to_string());
+            }
+        } else {
+            self.disabled_groups.insert(group.to_string());
+        }
+        let changed = was_enabled != enabled;
+
+        // 分组状态变化会影响匹配结果，需要清空缓存
+        if changed {
+            self.cache.lock().await.clear();
+        }
+        changed
+    }
+
+    /// 启用分组
+    pub async fn enable_group(&mut self, group: &str) -> bool {
+        self.set_group_enabled(group, true).await
+    }
+
+    /// 禁用分组
+    pub async fn disable_group(&mut self, group: &str) -> bool {
+        self.set_group_enabled(group, false).await
+    }
+
+    /// 仅启用给定的分组，其余分组全部禁用（用于按 profile 启动）
+    ///
+    /// 之后新增的分组同样默认禁用，需要显式启用
+    pub async fn enable_only_groups<I, S>(&mut self, groups: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.profile = Some(groups.into_iter().map(|g| g.as_ref().to_string()).collect());
+        self.disabled_groups.clear();
+        self.cache.lock().await.clear();
+    }
+
+    /// 当前生效的规则（分组与规则本身均已启用）
+    pub(crate) fn active_rules(&self) -> impl Iterator<Item = &ProxyRule> {
+        self.stored_rules()
+            .filter(|rule| self.is_rule_enabled(rule))
+            .map(|rule| &rule.rule)
+    }
+
+    fn is_rule_enabled(&self, rule: &StoredRule) -> bool {
+        rule.group
+            .as_deref()
+            .is_none_or(|group| self.is_group_enabled(group))
+            && !self.disabled_rules.contains(&rule.id)
+    }
+
+    /// 精确规则与模式规则
+    fn stored_rules(&self) -> impl Iterator<Item = &StoredRule> {
+        self.exact_rules
+            .values()
+            .flatten()
+            .chain(&self.pattern_rules)
+    }
+
+    /// 精确索引中第一条已启用的规则
+    fn find_exact(&self, key: &ExactKey) -> Option<&StoredRule> {
+        self.exact_rules
+            .get(key)?
+            .iter()
+            .find(|rule| self.is_rule_enabled(rule))
+    }
+
+    /// 主机与路径都为精确匹配的模式使用精确索引
+    fn is_exact_pattern(pattern: &AddressPattern) -> bool {
+        matches!(&pattern.pattern_type.host, PatternMatcher::Exact(_))
+            && pattern
+                .pattern_type
+                .path
+                .as_ref()
+                .is_none_or(|p| matches!(p, PatternMatcher::Exact(_)))
+    }
+
+    /// 把传入的标识规范化（省略默认端口）；无法解析时原样使用
+    fn normalize_rule_id(id: &str) -> String {
+        if let Ok(pattern) = id.parse::<AddressPattern>() {
+            return pattern_id(&pattern);
+        }
+        match id.rsplit_once('#') {
+            Some((pattern, group)) => match pattern.parse::<AddressPattern>() {
+                Ok(pattern) => format!("{}#{}", pattern_id(&pattern), group),
+                Err(_) => id.to_string(),
+            },
+            None => id.to_string(),
+        }
+    }
+
+    fn group_suffix(&self, rule: &ProxyRule) -> String {
+        match rule.group.as_deref() {
+            Some(group) if self.is_group_enabled(group) => format!(" [group={}]", group),
+            Some(group) => format!(" [group={}, disabled]", group),
+            None => String::new(),
+        }
+    }
 }
@@ -0,0 +1,130 @@
+use crate::PatternMatcher;
+use crate::url_encoding::{encode_query_component, percent_decode};
+
+/// 查询参数改写：按 删除 -> 重命名 -> 设置 的顺序应用于转发请求
+///
+/// ```toml
+/// query = { remove = ["utm_*"], rename = { q = "query" }, set = { debug = "1" } }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct QueryRewrite {
+    /// 删除的参数名，支持精确、通配符（如 `utm_*`）与 `re:` 正则
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub remove: Vec<PatternMatcher>,
+    /// 重命名参数：旧名 -> 新名
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub rename: Vec<(String, String)>,
+    /// 设置参数：存在则覆盖（同名多值合并为一个），不存在则追加
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub set: Vec<(String, String)>,
+}
+
+impl QueryRewrite {
+    pub fn is_empty(&self) -> bool {
+        self.remove.is_empty() && self.rename.is_empty() && self.set.is_empty()
+    }
+
+    /// 添加删除规则
+    pub fn remove(mut self, name: &str) -> Result<Self, regex::Error> {
+        self.remove.push(PatternMatcher::from_str(name)?);
+        Ok(self)
+    }
+
+    /// 添加重命名规则
+    pub fn rename(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.rename.push((from.into(), to.into()));
+        self
+    }
+
+    /// 添加设置规则
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set.push((name.into(), value.into()));
+        self
+    }
+
+    /// 对 `path?query` 形式的字符串应用改写，返回新的 `path?query`
+    pub fn apply(&self, path_and_query: &str) -> String {
+        if self.is_empty() {
+            return path_and_query.to_string();
+        }
+
+        let (path, query) = path_and_query
+            .split_once('?')
+            .unwrap_or((path_and_query, ""));
+
+        // 删除、重命名与设置都用解码后的参数名与配置比较，写入的名称与值统一编码
+        let mut params: Vec<(String, Option<String>)> = query
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| match p.split_once('=') {
+                Some((k, v)) => (k.to_string(), Some(v.to_string())),
+                None => (p.to_string(), None),
+            })
+            .collect();
+
+        params.retain(|(name, _)| {
+            let name = percent_decode(name);
+            !self.remove.iter().any(|m| m.matches(&name))
+        });
+
+        for (from, to) in &self.rename {
+            let to = encode_query_component(to);
+            for (name, _) in params
+                .iter_mut()
+                .filter(|(name, _)| percent_decode(name) == *from)
+            {
+                name.clone_from(&to);
+            }
+        }
+
+        for (name, value) in &self.set {
+            let encoded = encode_query_component(name);
+            let value = Some(encode_query_component(value));
+
+            // 保留第一个同名参数并覆盖其值，其余同名参数删除
+            let mut found = false;
+            params.retain_mut(|(n, v)| {
+                if percent_decode(n) != *name {
+                    return true;
+                }
+                if found {
+                    return false;
+                }
+                found = true;
+                v.clone_from(&value);
+                true
+            });
+            if !found {
+                params.push((encoded, value));
+            }
+        }
+
+        if params.is_empty() {
+            return path.to_string();
+        }
+
+        let query = params
+            .iter()
+            .map(|(k, v)| match v {
+                Some(v) => format!("{}={}", k, v),
+                None => k.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}?{}", path, query)
+    }
+}
@@ -0,0 +1,365 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use fs_err as fs;
+use http::header::{
+    CONNECTION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, HOST, HeaderName, HeaderValue,
+    TRANSFER_ENCODING,
+};
+use http::{HeaderMap, Method, Request, Response, StatusCode, Uri};
+use hudsucker::Body;
+use hudsucker::hyper::body::Body as _;
+use hudsucker::openssl::sha::sha256;
+use serde_json::{Value, json};
+use tracing::{debug, error};
+
+use crate::PatternMatcher;
+use crate::body_rewrite::read_body;
+use crate::har::{Capture, headers, tee_body};
+
+/// 录制与回放时消息体大小上限的默认值；请求体或响应体超过上限的请求不录制
+pub const DEFAULT_RECORD_MAX_BODY_SIZE: usize = 64 * 1024 * 1024;
+
+/// 录制与回放的请求匹配方式
+///
+/// 请求按 方法 + URL + 请求体的 SHA-256 计算键：查询参数不区分顺序，默认端口与主机大小写被规范化，
+/// 请求头默认不参与匹配
+#[derive(Debug, Clone, Default)]
+pub struct RecordMatch {
+    /// 不参与匹配的查询参数名，支持精确、通配符（如 `utm_*`）与 `re:` 正则
+    pub ignore_query: Vec<PatternMatcher>,
+    /// 参与匹配的请求头名称（不区分大小写）
+    pub headers: Vec<String>,
+    /// 不按请求体匹配
+    pub ignore_body: bool,
+}
+
+impl RecordMatch {
+    /// 添加不参与匹配的查询参数
+    pub fn ignore_query(mut self, name: &str) -> Result<Self, regex::Error> {
+        self.ignore_query.push(PatternMatcher::from_str(name)?);
+        Ok(self)
+    }
+
+    /// 添加参与匹配的请求头
+    pub fn header(mut self, name: impl Into<String>) -> Self {
+        self.headers.push(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// 设置是否忽略请求体
+    pub fn ignore_body(mut self, ignore_body: bool) -> Self {
+        self.ignore_body = ignore_body;
+        self
+    }
+
+    /// 请求的键：规范化后的请求描述的 SHA-256（十六进制）
+    pub fn key(&self, method: &Method, uri: &Uri, headers: &HeaderMap, body: &[u8]) -> String {
+        let mut canonical = format!("{}\n{}\n", method, self.url(uri, headers));
+        for name in &self.headers {
+            let values: Vec<_> = headers
+                .get_all(name.as_str())
+                .iter()
+                .map(|v| String::from_utf8_lossy(v.as_bytes()))
+                .collect();
+            canonical.push_str(&format!(
+                "{}: {}\n",
+                name.to_ascii_lowercase(),
+                values.join(", ")
+            ));
+        }
+        if !self.ignore_body {
+            canonical.push_str(&hex(&sha256(body)));
+        }
+        hex(&sha256(canonical.as_bytes()))
+    }
+
+    /// 参与匹配的 URL：去掉默认端口与忽略的查询参数，其余参数按字典序排列
+    ///
+    /// 请求行没有主机时（如直接访问代理）取 `Host` 请求头
+    pub fn url(&self, uri: &Uri, headers: &HeaderMap) -> String {
+        let scheme = uri.scheme_str().unwrap_or("http").to_ascii_lowercase();
+        let authority = uri.authority().cloned().or_else(|| {
+            headers
+                .get(HOST)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+        });
+        let mut url = format!("{}://", scheme);
+        if let Some(authority) = authority {
+            url.push_str(&authority.host().to_ascii_lowercase());
+            let default_port = if scheme == "https" { 443 } else { 80 };
+            if let Some(port) = authority.port_u16().filter(|port| *port != default_port) {
+                url.push_str(&format!(":{}", port));
+            }
+        }
+        url.push_str(uri.path());
+
+        let mut params: Vec<&str> = uri
+            .query()
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .filter(|param| {
+                let name = param.split_once('=').map_or(*param, |(name, _)| name);
+                !param.is_empty() && !self.ignore_query.iter().any(|m| m.matches(name))
+            })
+            .collect();
+        params.sort_unstable();
+        if !params.is_empty() {
+            url.push('?');
+            url.push_str(&params.join("&"));
+        }
+        url
+    }
+}
+
+/// 录制还是回放
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordMode {
+    /// 照常转发，并把上游响应保存下来
+    Record,
+    /// 直接返回保存的响应；未录制的请求在 `strict` 时返回 504，否则照常转发
+    Replay { strict: bool },
+}
+
+/// 录制与回放上游响应：每个请求的响应保存为目录中以键命名的 JSON 文件
+///
+/// 键按转发给上游的请求计算（规则改写之后），回放的响应同样经过规则的响应改写
+///
+/// ```ignore
+/// let recording = RecordReplay::new("recordings", RecordMode::Replay { strict: true })
+///     .matching(RecordMatch::default().ignore_query("_")?);
+/// let handler = ProxyHandlerBuilder::default()
+///     .proxy_manager(manager)
+///     .recording(Some(recording))
+///     .build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecordReplay {
+    dir: PathBuf,
+    mode: RecordMode,
+    matching: RecordMatch,
+    max_body_size: usize,
+}
+
+impl RecordReplay {
+    pub fn new(dir: impl Into<PathBuf>, mode: RecordMode) -> Self {
+        Self {
+            dir: dir.into(),
+            mode,
+            matching: RecordMatch::default(),
+            max_body_size: DEFAULT_RECORD_MAX_BODY_SIZE,
+        }
+    }
+
+    /// 设置请求的匹配方式
+    pub fn matching(mut self, matching: RecordMatch) -> Self {
+        self.matching = matching;
+        self
+    }
+
+    /// 设置消息体大小上限（字节）
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn mode(&self) -> RecordMode {
+        self.mode
+    }
+
+    /// 键对应的录制文件
+    pub fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// 读取请求体并计算键；请求体超过上限时返回 None，请求体保持不变
+    pub(crate) async fn key(&self, req: Request<Body>) -> (Request<Body>, Option<String>) {
+        let (parts, body) = req.into_parts();
+        match read_body(body, self.max_body_size).await {
+            Ok(data) => {
+                let key = self
+                    .matching
+                    .key(&parts.method, &parts.uri, &parts.headers, &data);
+                (Request::from_parts(parts, Body::from(data)), Some(key))
+            }
+            Err(body) => {
+                debug!(
+                    "Request body of {} exceeds {} bytes, not recording",
+                    parts.uri, self.max_body_size
+                );
+                (Request::from_parts(parts, body), None)
+            }
+        }
+    }
+
+    /// 读取录制的响应；文件不存在或无法解析时返回 None
+    pub(crate) async fn load(&self, key: &str) -> Option<Response<Body>> {
+        let path = self.path_for(key);
+        let data = fs::tokio::read(&path).await.ok()?;
+        let response = serde_json::from_slice::<Value>(&data)
+            .map_err(|e| e.to_string())
+            .and_then(|record| response_from_json(&record["response"]));
+        match response {
+            Ok(res) => Some(res),
+            Err(e) => {
+                error!("Invalid recording {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// 开始录制一个请求的响应
+    pub(crate) fn entry(&self, key: &str, req: &Request<Body>) -> RecordEntry {
+        let mut matched_headers = HeaderMap::new();
+        for name in &self.matching.headers {
+            if let Ok(name) = name.parse::<HeaderName>() {
+                for value in req.headers().get_all(&name) {
+                    matched_headers.append(name.clone(), value.clone());
+                }
+            }
+        }
+        RecordEntry {
+            path: self.path_for(key),
+            max_body_size: self.max_body_size,
+            request: json!({
+                "method": req.method().as_str(),
+                "url": req.uri().to_string(),
+                "headers": headers(&matched_headers),
+            }),
+        }
+    }
+}
+
+/// 进行中的一次录制
+#[derive(Debug, Clone)]
+pub(crate) struct RecordEntry {
+    path: PathBuf,
+    max_body_size: usize,
+    request: Value,
+}
+
+impl RecordEntry {
+    /// 复制转发中的上游响应，响应体完整读完后保存；被截断或中途断开的响应不保存
+    pub(crate) fn finish(self, res: Response<Body>) -> Response<Body> {
+        let (parts, body) = res.into_parts();
+        let status = parts.status;
+        let response_headers = parts.headers.clone();
+        let max_body_size = self.max_body_size;
+        let content_length = response_headers
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok());
+        let save = move |body: Capture| {
+            if body.is_whole(content_length) {
+                self.save(status, &response_headers, &body.data);
+            } else {
+                debug!(
+                    "Response body for {} is incomplete, not recording",
+                    self.request["url"]
+                );
+            }
+        };
+
+        if body.is_end_stream() {
+            save(Capture {
+                complete: true,
+                ..Capture::default()
+            });
+            return Response::from_parts(parts, body);
+        }
+        let capture = Arc::new(Mutex::new(Capture::default()));
+        let body = tee_body(body, max_body_size, capture, Some(Box::new(save)));
+        Response::from_parts(parts, body)
+    }
+
+    fn save(self, status: StatusCode, response_headers: &HeaderMap, body: &[u8]) {
+        // 逐跳的头只对原连接有效，回放时由服务端重新生成
+        let mut stored_headers = response_headers.clone();
+        for name in [CONNECTION, TRANSFER_ENCODING] {
+            stored_headers.remove(name);
+        }
+        stored_headers.remove("keep-alive");
+
+        let mut response = json!({
+            "status": status.as_u16(),
+            "headers": headers(&stored_headers),
+        });
+        match std::str::from_utf8(body) {
+            Ok(text) if !response_headers.contains_key(CONTENT_ENCODING) => {
+                response["text"] = json!(text);
+            }
+            _ => {
+                response["text"] = json!(BASE64.encode(body));
+                response["encoding"] = json!("base64");
+            }
+        }
+
+        let record = json!({ "request": self.request, "response": response });
+        let result = serde_json::to_vec_pretty(&record)
+            .map_err(std::io::Error::other)
+            .and_then(|data| {
+                if let Some(dir) = self.path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(&self.path, data)
+            });
+        match result {
+            Ok(()) => debug!(
+                "Recorded {} {} to {}",
+                self.request["method"],
+                self.request["url"],
+                self.path.display()
+            ),
+            Err(e) => error!("Failed to write recording: {}", e),
+        }
+    }
+}
+
+/// 未录制的请求在严格回放模式下的响应
+pub(crate) fn not_recorded(req: &Request<Body>) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::GATEWAY_TIMEOUT)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(format!(
+            "proxy-fork: no recording for {} {}\n",
+            req.method(),
+            req.uri()
+        )))
+        .expect("Failed to build response")
+}
+
+fn response_from_json(response: &Value) -> Result<Response<Body>, String> {
+    let status = response["status"]
+        .as_u64()
+        .and_then(|status| u16::try_from(status).ok())
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .ok_or("missing or invalid status")?;
+    let mut builder = Response::builder().status(status);
+    for header in response["headers"].as_array().into_iter().flatten() {
+        let (Some(name), Some(value)) = (header["name"].as_str(), header["value"].as_str()) else {
+            return Err(format!("invalid header: {}", header));
+        };
+        let name = name.parse::<HeaderName>().map_err(|e| e.to_string())?;
+        let value = HeaderValue::from_str(value).map_err(|e| e.to_string())?;
+        builder = builder.header(name, value);
+    }
+
+    let text = response["text"].as_str().unwrap_or_default();
+    let body = if response["encoding"].as_str() == Some("base64") {
+        BASE64.decode(text).map_err(|e| e.to_string())?
+    } else {
+        text.as_bytes().to_vec()
+    };
+    builder.body(Body::from(body)).map_err(|e| e.to_string())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,205 @@
+use http::Request;
+use http::header::{CONTENT_LENGTH, HeaderValue, TRANSFER_ENCODING};
+use hudsucker::{Body, decode_request};
+use json_patch::Patch;
+use serde_json::Value;
+use tracing::{debug, error};
+
+use crate::body_rewrite::{media_type, read_body, supported_encoding};
+use crate::{DEFAULT_BODY_REWRITE_MAX_SIZE, JsonPath, QueryRewrite};
+
+/// 请求体改写：JSON 按 删除 -> 替换 -> 设置 -> JSON Patch 的顺序应用，表单按字段改写
+///
+/// ```toml
+/// request_body = { set = { "$.user.role" = "admin" }, remove = ["$.debug"], patch = [{ op = "add", path = "/flags/-", value = "beta" }], form = { set = { debug = "1" } } }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct RequestBodyRewrite {
+    /// 删除 JSONPath 匹配到的值
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub remove: Vec<JsonPath>,
+    /// 替换 JSONPath 匹配到的值：JSONPath -> JSON 值（不存在时跳过）
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub replace: Vec<(JsonPath, Value)>,
+    /// 设置 JSONPath 的值：JSONPath -> JSON 值（不含通配符的路径不存在时创建）
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub set: Vec<(JsonPath, Value)>,
+    /// RFC 6902 JSON Patch，整体应用，任一操作失败时不改写请求体
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub patch: Option<Patch>,
+    /// `application/x-www-form-urlencoded` 表单字段改写（格式同查询参数改写）
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "QueryRewrite::is_empty")
+    )]
+    pub form: QueryRewrite,
+    /// 改写的请求体大小上限（字节，解压后），超过时不改写
+    pub max_size: usize,
+}
+
+impl Default for RequestBodyRewrite {
+    fn default() -> Self {
+        Self {
+            remove: Vec::new(),
+            replace: Vec::new(),
+            set: Vec::new(),
+            patch: None,
+            form: QueryRewrite::default(),
+            max_size: DEFAULT_BODY_REWRITE_MAX_SIZE,
+        }
+    }
+}
+
+impl RequestBodyRewrite {
+    pub fn is_empty(&self) -> bool {
+        !self.has_json_edits() && self.form.is_empty()
+    }
+
+    fn has_json_edits(&self) -> bool {
+        !self.remove.is_empty()
+            || !self.replace.is_empty()
+            || !self.set.is_empty()
+            || self.patch.as_ref().is_some_and(|patch| !patch.0.is_empty())
+    }
+
+    /// 添加删除规则
+    pub fn remove(mut self, path: &str) -> Result<Self, String> {
+        self.remove.push(path.parse()?);
+        Ok(self)
+    }
+
+    /// 添加替换规则
+    pub fn replace(mut self, path: &str, value: Value) -> Result<Self, String> {
+        self.replace.push((path.parse()?, value));
+        Ok(self)
+    }
+
+    /// 添加设置规则
+    pub fn set(mut self, path: &str, value: Value) -> Result<Self, String> {
+        self.set.push((path.parse()?, value));
+        Ok(self)
+    }
+
+    /// 设置 JSON Patch
+    pub fn patch(mut self, patch: Patch) -> Self {
+        self.patch = Some(patch);
+        self
+    }
+
+    /// 设置表单字段改写
+    pub fn form(mut self, form: QueryRewrite) -> Self {
+        self.form = form;
+        self
+    }
+
+    /// 改写 JSON 值；JSON Patch 失败时返回错误，此时 `value` 可能已被部分改写
+    pub fn rewrite_json(&self, value: &mut Value) -> Result<(), json_patch::PatchError> {
+        for path in &self.remove {
+            path.remove(value);
+        }
+        for (path, new_value) in &self.replace {
+            path.replace(value, new_value);
+        }
+        for (path, new_value) in &self.set {
+            path.set(value, new_value);
+        }
+        match &self.patch {
+            Some(patch) => json_patch::patch(value, patch),
+            None => Ok(()),
+        }
+    }
+
+    /// 改写 `a=1&b=2` 形式的表单
+    pub fn rewrite_form(&self, form: &str) -> String {
+        let rewritten = self.form.apply(&format!("?{}", form));
+        rewritten
+            .strip_prefix('?')
+            .unwrap_or(&rewritten)
+            .to_string()
+    }
+
+    /// 对请求应用改写
+    ///
+    /// 只处理 JSON（`application/json`、`*+json`）与表单请求体；压缩方式不支持、超过 `max_size`、
+    /// 无法解析或 JSON Patch 失败时不改写，解压失败时原样转发
+    pub async fn apply(&self, req: Request<Body>) -> Request<Body> {
+        let Some(media_type) = media_type(req.headers()).map(str::to_ascii_lowercase) else {
+            return req;
+        };
+        let is_json = media_type == "application/json" || media_type.ends_with("+json");
+        let is_form = media_type == "application/x-www-form-urlencoded";
+        if !(is_json && self.has_json_edits() || is_form && !self.form.is_empty()) {
+            return req;
+        }
+        if !supported_encoding(req.headers()) {
+            debug!("Unsupported content encoding, not rewriting request body");
+            return req;
+        }
+
+        // 先读取原始请求体，解压失败时仍可原样转发
+        let (parts, body) = req.into_parts();
+        let raw = match read_body(body, self.max_size).await {
+            Ok(raw) => raw,
+            Err(body) => return Request::from_parts(parts, body),
+        };
+        let decoded = decode_request(Request::from_parts(parts.clone(), Body::from(raw.clone())));
+        let (mut parts, body) = match decoded {
+            Ok(req) => req.into_parts(),
+            Err(e) => {
+                error!("Failed to decode request body for rewriting: {}", e);
+                return Request::from_parts(parts, Body::from(raw));
+            }
+        };
+        let data = match read_body(body, self.max_size).await {
+            Ok(data) => data,
+            Err(body) => {
+                parts.headers.remove(CONTENT_LENGTH);
+                return Request::from_parts(parts, body);
+            }
+        };
+
+        let rewritten = if is_json {
+            self.rewrite_json_body(&data)
+        } else {
+            std::str::from_utf8(&data)
+                .ok()
+                .map(|form| self.rewrite_form(form).into_bytes())
+        };
+        let data = rewritten.unwrap_or(data);
+
+        parts.headers.remove(TRANSFER_ENCODING);
+        parts
+            .headers
+            .insert(CONTENT_LENGTH, HeaderValue::from(data.len()));
+        Request::from_parts(parts, Body::from(data))
+    }
+
+    fn rewrite_json_body(&self, data: &[u8]) -> Option<Vec<u8>> {
+        let mut value: Value = match serde_json::from_slice(data) {
+            Ok(value) => value,
+            Err(e) => {
+                debug!("Request body is not valid JSON, not rewriting: {}", e);
+                return None;
+            }
+        };
+        if let Err(e) = self.rewrite_json(&mut value) {
+            error!("Failed to apply JSON patch to request body: {}", e);
+            return None;
+        }
+        serde_json::to_vec(&value).ok()
+    }
+}
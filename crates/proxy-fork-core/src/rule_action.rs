@@ -0,0 +1,443 @@
+use std::path::PathBuf;
+
+use fs_err::tokio as fs;
+use http::header::{CONTENT_TYPE, HeaderName, HeaderValue, LOCATION};
+use http::{HeaderMap, Request, Response, StatusCode, Uri};
+use hudsucker::{AbortConnection, Body};
+use tracing::error;
+
+use crate::local_files::content_type;
+use crate::url_encoding::percent_decode;
+use crate::{AddressPattern, PatternMatcher};
+
+/// 规则命中后的处理方式
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum RuleAction {
+    /// 转发到规则目标（默认）
+    #[default]
+    Forward,
+    /// 直接返回预设响应，不连接上游
+    Mock(MockResponse),
+    /// 返回重定向响应
+    Redirect(Redirect),
+    /// 拦截请求：返回错误状态码或直接断开连接
+    Block(Block),
+}
+
+impl RuleAction {
+    pub fn is_forward(&self) -> bool {
+        matches!(self, Self::Forward)
+    }
+}
+
+/// 预设响应：状态码、响应头与响应体（内联或从文件读取）
+///
+/// 响应头的值与响应体支持 `{{...}}` 模板，见 [`RequestTemplate`]
+///
+/// ```toml
+/// mock = { status = 200, headers = { content-type = "application/json" }, body = '{"id": "{{path.2}}"}' }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct MockResponse {
+    /// 状态码（默认 200）
+    pub status: u16,
+    /// 响应头
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde_helpers::pairs_as_map",
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub headers: Vec<(String, String)>,
+    /// 内联响应体
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub body: Option<String>,
+    /// 从文件读取响应体（优先于 `body`），每次请求重新读取；
+    /// 未设置 `Content-Type` 时按扩展名识别
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub body_file: Option<PathBuf>,
+}
+
+impl Default for MockResponse {
+    fn default() -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: None,
+            body_file: None,
+        }
+    }
+}
+
+impl MockResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            ..Self::default()
+        }
+    }
+
+    /// 添加响应头
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// 设置内联响应体
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// 设置响应体文件
+    pub fn body_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.body_file = Some(path.into());
+        self
+    }
+
+    /// 校验状态码与响应头是否合法（与 [`Redirect::new`] 一样在加载规则时调用）
+    pub fn validate(&self) -> Result<(), String> {
+        StatusCode::from_u16(self.status)
+            .map_err(|_| format!("invalid mock status code: {}", self.status))?;
+        for (name, value) in &self.headers {
+            HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("invalid mock header name: {}", name))?;
+            HeaderValue::from_str(value)
+                .map_err(|_| format!("invalid mock header value: {}: {}", name, value))?;
+        }
+        Ok(())
+    }
+
+    /// 按请求渲染响应；状态码、响应头无效或文件读取失败时返回 500
+    pub async fn respond(&self, template: &RequestTemplate) -> Response<Body> {
+        let Ok(status) = StatusCode::from_u16(self.status) else {
+            error!("Invalid mock status code: {}", self.status);
+            return internal_error();
+        };
+
+        let body = match &self.body_file {
+            Some(path) => match fs::read(path).await {
+                // 文本文件按模板渲染，二进制文件原样返回
+                Ok(bytes) => match String::from_utf8(bytes) {
+                    Ok(text) => Body::from(template.render(&text)),
+                    Err(e) => Body::from(e.into_bytes()),
+                },
+                Err(e) => {
+                    error!("Failed to read mock body file: {}", e);
+                    return internal_error();
+                }
+            },
+            None => Body::from(
+                self.body
+                    .as_deref()
+                    .map(|body| template.render(body))
+                    .unwrap_or_default(),
+            ),
+        };
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&template.render(value)),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    headers.append(name, value);
+                }
+                _ => {
+                    error!("Invalid mock header: {}: {}", name, value);
+                    return internal_error();
+                }
+            }
+        }
+        if let Some(path) = &self.body_file
+            && !headers.contains_key(CONTENT_TYPE)
+        {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type(path)));
+        }
+
+        let mut response = Response::new(body);
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        response
+    }
+}
+
+/// 重定向：返回 301/302/307/308 与 `Location`
+///
+/// ```toml
+/// { protocol = "https", host = "old.example.com", action = "redirect", status = 301, location = "https://new.example.com{{path}}" }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Redirect {
+    /// 状态码：301 | 302 | 307 | 308（默认 302）
+    pub status: u16,
+    /// 重定向地址，支持模板（见 [`RequestTemplate`]）；未设置时使用按规则目标改写后的地址
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub location: Option<String>,
+}
+
+impl Default for Redirect {
+    fn default() -> Self {
+        Self {
+            status: 302,
+            location: None,
+        }
+    }
+}
+
+impl Redirect {
+    pub fn new(status: u16) -> Result<Self, String> {
+        if !matches!(status, 301 | 302 | 307 | 308) {
+            return Err(format!(
+                "invalid redirect status: {} (expected 301, 302, 307 or 308)",
+                status
+            ));
+        }
+        Ok(Self {
+            status,
+            location: None,
+        })
+    }
+
+    /// 设置重定向地址模板
+    pub fn location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// 生成重定向响应；`target` 为按规则目标改写后的地址，未设置 `location` 时使用
+    pub fn respond(&self, template: &RequestTemplate, target: &Uri) -> Response<Body> {
+        let location = match &self.location {
+            Some(location) => template.render(location),
+            None => target.to_string(),
+        };
+        match (Self::new(self.status), HeaderValue::from_str(&location)) {
+            (Ok(_), Ok(location)) => Response::builder()
+                .status(self.status)
+                .header(LOCATION, location)
+                .body(Body::empty())
+                .expect("Failed to build response"),
+            (Err(e), _) => {
+                error!("{}", e);
+                internal_error()
+            }
+            (_, Err(_)) => {
+                error!("Invalid redirect location: {}", location);
+                internal_error()
+            }
+        }
+    }
+}
+
+/// 拦截请求：返回 403/404/451，或不返回任何响应直接断开连接
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Block {
+    /// 状态码：403 | 404 | 451（默认 403）
+    pub status: u16,
+    /// 为 true 时直接断开连接（HTTP/2 为重置流），忽略 `status`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "std::ops::Not::not"))]
+    pub reset: bool,
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Self {
+            status: 403,
+            reset: false,
+        }
+    }
+}
+
+impl Block {
+    pub fn new(status: u16) -> Result<Self, String> {
+        if !matches!(status, 403 | 404 | 451) {
+            return Err(format!(
+                "invalid block status: {} (expected 403, 404 or 451)",
+                status
+            ));
+        }
+        Ok(Self {
+            status,
+            reset: false,
+        })
+    }
+
+    /// 断开连接而不返回响应
+    pub fn reset() -> Self {
+        Self {
+            reset: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn respond(&self) -> Response<Body> {
+        if self.reset {
+            let mut response = Response::new(Body::empty());
+            response.extensions_mut().insert(AbortConnection);
+            return response;
+        }
+        match Self::new(self.status) {
+            Ok(_) => Response::builder()
+                .status(self.status)
+                .body(Body::empty())
+                .expect("Failed to build response"),
+            Err(e) => {
+                error!("{}", e);
+                internal_error()
+            }
+        }
+    }
+}
+
+fn internal_error() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .body(Body::empty())
+        .expect("Failed to build response")
+}
+
+/// 模板渲染时可引用的请求信息
+///
+/// - `{{method}}`、`{{host}}`、`{{path}}`、`{{query}}`：请求方法、主机、路径与原始查询串
+/// - `{{path.N}}`：第 N 个路径片段（从 0 开始，负数从末尾倒数），如 `/users/42` 中 `{{path.1}}` 为 `42`
+/// - `{{query.name}}`：查询参数（解码后的首个值）
+/// - `{{header.name}}`：请求头（不区分大小写）
+/// - `{{capture.N}}`、`{{capture.name}}`：规则 `re:` 正则的捕获组；路径为正则时取路径（含查询串），否则取主机
+///
+/// 取不到的值渲染为空字符串，无法识别的表达式原样保留
+#[derive(Debug, Clone, Default)]
+pub struct RequestTemplate {
+    method: String,
+    host: String,
+    path: String,
+    query: Option<String>,
+    headers: HeaderMap,
+    captures: Vec<(Option<String>, String)>,
+}
+
+impl RequestTemplate {
+    pub fn new(req: &Request<Body>, pattern: &AddressPattern) -> Self {
+        let uri = req.uri();
+        let host = uri
+            .host()
+            .or_else(|| {
+                req.headers()
+                    .get(http::header::HOST)
+                    .and_then(|v| v.to_str().ok())
+            })
+            .unwrap_or_default()
+            .to_string();
+        let path_and_query = uri.path_and_query().map_or("/", |pq| pq.as_str());
+
+        let captures = match &pattern.pattern_type.path {
+            Some(path @ PatternMatcher::Regex { .. }) => capture_groups(path, path_and_query),
+            _ => capture_groups(&pattern.pattern_type.host, &host),
+        };
+
+        Self {
+            method: req.method().to_string(),
+            host,
+            path: uri.path().to_string(),
+            query: uri.query().map(ToString::to_string),
+            headers: req.headers().clone(),
+            captures,
+        }
+    }
+
+    /// 替换模板中的 `{{...}}` 表达式
+    pub fn render(&self, template: &str) -> String {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                rest = &rest[start..];
+                break;
+            };
+            match self.lookup(after[..end].trim()) {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[start..start + 2 + end + 2]),
+            }
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn lookup(&self, expr: &str) -> Option<String> {
+        let (key, arg) = expr.split_once('.').unwrap_or((expr, ""));
+        let value = match (key, arg) {
+            ("method", "") => Some(self.method.clone()),
+            ("host", "") => Some(self.host.clone()),
+            ("path", "") => Some(self.path.clone()),
+            ("query", "") => self.query.clone(),
+            ("path", index) => {
+                let index: isize = index.parse().ok()?;
+                let segments: Vec<_> = self.path.split('/').filter(|s| !s.is_empty()).collect();
+                let index = if index < 0 {
+                    segments.len().checked_sub(index.unsigned_abs())
+                } else {
+                    Some(index.unsigned_abs())
+                };
+                index
+                    .and_then(|index| segments.get(index))
+                    .map(|s| percent_decode(s))
+            }
+            ("query", name) => self.query.as_deref().and_then(|query| {
+                query.split('&').find_map(|pair| {
+                    let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+                    (percent_decode(k) == name).then(|| percent_decode(v))
+                })
+            }),
+            ("header", name) => self
+                .headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(ToString::to_string),
+            ("capture", name) => match name.parse::<usize>() {
+                Ok(index) => self.captures.get(index).map(|(_, v)| v.clone()),
+                Err(_) => self
+                    .captures
+                    .iter()
+                    .find(|(n, _)| n.as_deref() == Some(name))
+                    .map(|(_, v)| v.clone()),
+            },
+            _ => return None,
+        };
+        Some(value.unwrap_or_default())
+    }
+}
+
+/// 正则匹配器的捕获组（按序号排列，附带组名）；非正则或不匹配时为空
+fn capture_groups(matcher: &PatternMatcher, value: &str) -> Vec<(Option<String>, String)> {
+    let PatternMatcher::Regex { compiled, .. } = matcher else {
+        return Vec::new();
+    };
+    let Some(captures) = compiled.captures(value) else {
+        return Vec::new();
+    };
+    compiled
+        .capture_names()
+        .zip(captures.iter())
+        .map(|(name, m)| {
+            (
+                name.map(ToString::to_string),
+                m.map_or_else(String::new, |m| m.as_str().to_string()),
+            )
+        })
+        .collect()
+}
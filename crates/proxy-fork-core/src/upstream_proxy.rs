@@ -0,0 +1,608 @@
+use crate::http_address::split_authority;
+use crate::url_encoding::percent_decode;
+use base64::Engine;
+use http::Uri;
+use hudsucker::hyper_util::rt::TokioIo;
+use std::{
+    future::Future,
+    io,
+    net::IpAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tower_service::Service;
+use tracing::debug;
+
+/// 上游代理类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpstreamProxyKind {
+    /// HTTP 代理，统一通过 `CONNECT` 建立隧道
+    Http,
+    /// SOCKS5 代理，目标域名交由代理解析
+    Socks5,
+}
+
+/// 上游代理认证信息
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ProxyAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl std::fmt::Debug for ProxyAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProxyAuth")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+/// 上游代理地址：`http://[user:pass@]host[:port]` 或 `socks5://[user:pass@]host[:port]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UpstreamProxy {
+    pub kind: UpstreamProxyKind,
+    pub host: String,
+    pub port: u16,
+    pub auth: Option<ProxyAuth>,
+}
+
+impl std::str::FromStr for UpstreamProxy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // 与 curl 一致：省略协议时视为 HTTP 代理
+        let (scheme, rest) = s.split_once("://").unwrap_or(("http", s));
+        let kind = match scheme.to_ascii_lowercase().as_str() {
+            "http" => UpstreamProxyKind::Http,
+            "socks5" | "socks5h" => UpstreamProxyKind::Socks5,
+            _ => {
+                return Err(format!(
+                    "Invalid upstream proxy scheme: {} (expected http, socks5 or socks5h)",
+                    scheme
+                ));
+            }
+        };
+
+        let rest = rest.trim_end_matches('/');
+        let (auth, authority) = match rest.rsplit_once('@') {
+            Some((auth, authority)) => (Some(auth), authority),
+            None => (None, rest),
+        };
+        let auth = auth.map(|auth| {
+            let (username, password) = auth.split_once(':').unwrap_or((auth, ""));
+            ProxyAuth {
+                username: percent_decode(username),
+                password: percent_decode(password),
+            }
+        });
+
+        let (host, port) = split_authority(authority)?;
+        if host.is_empty() || authority.contains('/') {
+            return Err(format!("Invalid upstream proxy address: {}", s));
+        }
+        let port = port.unwrap_or(match kind {
+            UpstreamProxyKind::Http => 80,
+            UpstreamProxyKind::Socks5 => 1080,
+        });
+
+        Ok(Self {
+            kind,
+            host: host.to_string(),
+            port,
+            auth,
+        })
+    }
+}
+
+impl std::fmt::Display for UpstreamProxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scheme = match self.kind {
+            UpstreamProxyKind::Http => "http",
+            UpstreamProxyKind::Socks5 => "socks5",
+        };
+        write!(f, "{}://", scheme)?;
+        if let Some(auth) = &self.auth {
+            write!(
+                f,
+                "{}:{}@",
+                percent_encode(&auth.username),
+                percent_encode(&auth.password)
+            )?;
+        }
+        write!(f, "{}", host_port(&self.host, self.port))
+    }
+}
+
+/// 单条规则的出站方式
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Via {
+    /// 使用全局上游代理配置（默认）
+    #[default]
+    Global,
+    /// 直连目标地址，忽略全局上游代理
+    Direct,
+    /// 使用指定的上游代理
+    Proxy(UpstreamProxy),
+}
+
+impl Via {
+    pub fn is_global(&self) -> bool {
+        matches!(self, Via::Global)
+    }
+}
+
+impl std::str::FromStr for Via {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "global" => Ok(Via::Global),
+            "direct" => Ok(Via::Direct),
+            "" => Err("via must not be empty".to_string()),
+            _ => Ok(Via::Proxy(s.parse()?)),
+        }
+    }
+}
+
+impl std::fmt::Display for Via {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Via::Global => write!(f, "global"),
+            Via::Direct => write!(f, "direct"),
+            Via::Proxy(proxy) => write!(f, "{}", proxy),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Via {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Via {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NoProxyEntry {
+    /// `*`：所有主机均直连
+    All,
+    /// 域名本身及其子域名
+    Domain(String),
+    /// IP 地址或网段
+    Cidr(IpAddr, u8),
+}
+
+/// `NO_PROXY` 列表：逗号或空白分隔
+///
+/// 支持 `*`、`example.com`（含子域名）、`.example.com` / `*.example.com`、
+/// IP 地址以及 `10.0.0.0/8` 形式的网段；条目中的端口会被忽略
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoProxy {
+    entries: Vec<NoProxyEntry>,
+}
+
+impl NoProxy {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 主机是否应绕过上游代理直连
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        let ip = host.parse::<IpAddr>().ok();
+
+        self.entries.iter().any(|entry| match entry {
+            NoProxyEntry::All => true,
+            NoProxyEntry::Domain(domain) => {
+                host == *domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            NoProxyEntry::Cidr(network, prefix) => {
+                ip.is_some_and(|ip| ip_in_network(ip, *network, *prefix))
+            }
+        })
+    }
+}
+
+impl std::str::FromStr for NoProxy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = Vec::new();
+        for item in s.split(|c: char| c == ',' || c.is_whitespace()) {
+            let item = item.trim().to_ascii_lowercase();
+            if item.is_empty() {
+                continue;
+            }
+            if item == "*" {
+                entries.push(NoProxyEntry::All);
+                continue;
+            }
+
+            if let Some((network, prefix)) = item.split_once('/') {
+                let network: IpAddr = network
+                    .parse()
+                    .map_err(|_| format!("Invalid NO_PROXY network: {}", item))?;
+                let max = if network.is_ipv4() { 32 } else { 128 };
+                let prefix = prefix
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|p| *p <= max)
+                    .ok_or_else(|| format!("Invalid NO_PROXY network: {}", item))?;
+                entries.push(NoProxyEntry::Cidr(network, prefix));
+                continue;
+            }
+
+            // 未加方括号的 IPv6 地址不能按 `host:port` 拆分
+            let host = if item.parse::<IpAddr>().is_ok() {
+                item.as_str()
+            } else {
+                split_authority(&item)?.0
+            };
+            match host.parse::<IpAddr>() {
+                Ok(ip) => {
+                    let prefix = if ip.is_ipv4() { 32 } else { 128 };
+                    entries.push(NoProxyEntry::Cidr(ip, prefix));
+                }
+                Err(_) => {
+                    let domain = host.trim_start_matches("*.").trim_start_matches('.');
+                    entries.push(NoProxyEntry::Domain(
+                        domain.trim_end_matches('.').to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(Self { entries })
+    }
+}
+
+fn ip_in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// 全局上游代理配置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpstreamProxyConfig {
+    /// 用于 `http://` / `ws://` 目标的代理
+    pub http: Option<UpstreamProxy>,
+    /// 用于 `https://` / `wss://` 目标（以及 TLS 或 443 端口的 CONNECT 透传隧道）的代理
+    pub https: Option<UpstreamProxy>,
+    /// 绕过上游代理的主机
+    pub no_proxy: NoProxy,
+}
+
+impl UpstreamProxyConfig {
+    /// 所有目标使用同一个上游代理
+    pub fn all(proxy: UpstreamProxy) -> Self {
+        Self {
+            http: Some(proxy.clone()),
+            https: Some(proxy),
+            no_proxy: NoProxy::default(),
+        }
+    }
+
+    /// 读取 `HTTP_PROXY`、`HTTPS_PROXY`、`ALL_PROXY` 与 `NO_PROXY`（大小写均可）
+    ///
+    /// 无法解析的变量会被忽略并输出警告
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// 与 [`from_env`](Self::from_env) 相同，但从给定的函数读取变量，便于测试
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| {
+            lookup(&name.to_ascii_lowercase())
+                .or_else(|| lookup(name))
+                .filter(|value| !value.trim().is_empty())
+        };
+        let proxy = |name: &str| {
+            var(name).and_then(|value| match value.parse::<UpstreamProxy>() {
+                Ok(proxy) => Some(proxy),
+                Err(e) => {
+                    tracing::warn!("Ignoring {}: {}", name, e);
+                    None
+                }
+            })
+        };
+
+        let all = proxy("ALL_PROXY");
+        let no_proxy = var("NO_PROXY")
+            .and_then(|value| match value.parse::<NoProxy>() {
+                Ok(no_proxy) => Some(no_proxy),
+                Err(e) => {
+                    tracing::warn!("Ignoring NO_PROXY: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            http: proxy("HTTP_PROXY").or_else(|| all.clone()),
+            https: proxy("HTTPS_PROXY").or(all),
+            no_proxy,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.http.is_none() && self.https.is_none()
+    }
+
+    /// 选择连接目标时使用的上游代理，`None` 表示直连
+    pub fn proxy_for(&self, is_https: bool, host: &str) -> Option<&UpstreamProxy> {
+        let proxy = if is_https { &self.https } else { &self.http };
+        proxy.as_ref().filter(|_| !self.no_proxy.matches(host))
+    }
+}
+
+/// 出站连接器：按全局配置与规则的 `via` 选择直连、HTTP CONNECT 或 SOCKS5
+///
+/// 实现了 `Service<Uri>`，可交给 `ProxyBuilder::with_rustls_connector_over`，
+/// 普通请求、WebSocket 与 CONNECT 透传隧道都会经过它建立 TCP 连接。
+/// 规则指定的 `via` 由 `ProxyHandler` 通过 hudsucker 的 `Dialer` 扩展随请求传递。
+/// HTTP 上游代理一律通过 `CONNECT` 建立隧道，明文请求同样如此
+#[derive(Debug, Clone, Default)]
+pub struct UpstreamConnector {
+    config: Arc<UpstreamProxyConfig>,
+    via: Via,
+}
+
+impl UpstreamConnector {
+    pub fn new(config: UpstreamProxyConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            via: Via::Global,
+        }
+    }
+
+    /// 使用规则的出站方式，[`Via::Global`] 时按全局配置选择
+    pub fn with_via(mut self, via: Via) -> Self {
+        self.via = via;
+        self
+    }
+
+    /// 选择连接 `uri` 时使用的上游代理，`None` 表示直连
+    pub fn route(&self, uri: &Uri) -> Option<UpstreamProxy> {
+        match &self.via {
+            Via::Global => {
+                let (is_https, host, _) = target_of(uri).ok()?;
+                self.config.proxy_for(is_https, host).cloned()
+            }
+            Via::Direct => None,
+            Via::Proxy(proxy) => Some(proxy.clone()),
+        }
+    }
+
+    /// 建立到 `uri` 所指主机的 TCP 连接（TLS 由调用方负责）
+    pub async fn connect(&self, uri: Uri) -> io::Result<TcpStream> {
+        let (_, host, port) = target_of(&uri)?;
+        match self.route(&uri) {
+            None => TcpStream::connect((host, port)).await,
+            Some(proxy) => {
+                debug!("Connecting to {} via upstream proxy {}", uri, proxy.host);
+                let mut stream = TcpStream::connect((proxy.host.as_str(), proxy.port)).await?;
+                match proxy.kind {
+                    UpstreamProxyKind::Http => {
+                        http_connect(&mut stream, host, port, proxy.auth.as_ref()).await?;
+                    }
+                    UpstreamProxyKind::Socks5 => {
+                        socks5_connect(&mut stream, host, port, proxy.auth.as_ref()).await?;
+                    }
+                }
+                Ok(stream)
+            }
+        }
+    }
+}
+
+impl Service<Uri> for UpstreamConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connector = self.clone();
+        Box::pin(async move { connector.connect(uri).await.map(TokioIo::new) })
+    }
+}
+
+/// 解析连接目标：(是否 TLS, 主机, 端口)
+fn target_of(uri: &Uri) -> io::Result<(bool, &str, u16)> {
+    let is_https = matches!(uri.scheme_str(), Some("https" | "wss"));
+    let host = uri
+        .host()
+        .map(|h| h.trim_start_matches('[').trim_end_matches(']'))
+        .ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("missing host: {uri}"))
+        })?;
+    let port = uri.port_u16().unwrap_or(if is_https { 443 } else { 80 });
+    Ok((is_https, host, port))
+}
+
+fn host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+fn proxy_error(message: String) -> io::Error {
+    io::Error::other(message)
+}
+
+/// 通过 HTTP 代理的 `CONNECT` 方法建立隧道
+async fn http_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    auth: Option<&ProxyAuth>,
+) -> io::Result<()> {
+    let authority = host_port(host, port);
+    let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+    if let Some(auth) = auth {
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", auth.username, auth.password));
+        request.push_str(&format!("Proxy-Authorization: Basic {credentials}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // 逐字节读取响应头，避免吞掉隧道建立后目标服务器发送的数据
+    let mut head = Vec::with_capacity(128);
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= 8192 {
+            return Err(proxy_error(
+                "upstream proxy response header too large".into(),
+            ));
+        }
+        head.push(stream.read_u8().await?);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+    if status.starts_with('2') && status.len() == 3 {
+        Ok(())
+    } else {
+        Err(proxy_error(format!(
+            "upstream proxy refused CONNECT {authority}: {status_line}"
+        )))
+    }
+}
+
+/// SOCKS5 握手（RFC 1928 / RFC 1929），目标域名交由代理解析
+async fn socks5_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    auth: Option<&ProxyAuth>,
+) -> io::Result<()> {
+    const VERSION: u8 = 0x05;
+    const NO_AUTH: u8 = 0x00;
+    const USER_PASS: u8 = 0x02;
+
+    if auth.is_some() {
+        stream.write_all(&[VERSION, 2, NO_AUTH, USER_PASS]).await?;
+    } else {
+        stream.write_all(&[VERSION, 1, NO_AUTH]).await?;
+    }
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != VERSION {
+        return Err(proxy_error("invalid SOCKS5 server reply".into()));
+    }
+
+    match (reply[1], auth) {
+        (NO_AUTH, _) => {}
+        (USER_PASS, Some(auth)) => {
+            let username = auth.username.as_bytes();
+            let password = auth.password.as_bytes();
+            let (Ok(ulen), Ok(plen)) = (u8::try_from(username.len()), u8::try_from(password.len()))
+            else {
+                return Err(proxy_error("SOCKS5 credentials too long".into()));
+            };
+            let mut request = vec![0x01, ulen];
+            request.extend_from_slice(username);
+            request.push(plen);
+            request.extend_from_slice(password);
+            stream.write_all(&request).await?;
+
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0x00 {
+                return Err(proxy_error("SOCKS5 authentication failed".into()));
+            }
+        }
+        _ => {
+            return Err(proxy_error(
+                "SOCKS5 server requires an unsupported authentication method".into(),
+            ));
+        }
+    }
+
+    let mut request = vec![VERSION, 0x01, 0x00];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            let len = u8::try_from(host.len())
+                .map_err(|_| proxy_error(format!("host name too long: {host}")))?;
+            request.push(0x03);
+            request.push(len);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await?;
+    if head[1] != 0x00 {
+        return Err(proxy_error(format!(
+            "SOCKS5 connect to {} failed with code {}",
+            host_port(host, port),
+            head[1]
+        )));
+    }
+    // 跳过绑定地址与端口
+    let addr_len = match head[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => usize::from(stream.read_u8().await?),
+        _ => return Err(proxy_error("invalid SOCKS5 address type".into())),
+    };
+    let mut bound = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(char::from(b));
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
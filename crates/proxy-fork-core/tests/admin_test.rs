@@ -0,0 +1,264 @@
+#[cfg(test)]
+mod admin_test {
+    use std::sync::Arc;
+
+    use http::{Method, Request, StatusCode};
+    use http_body_util::BodyExt;
+    use hudsucker::Body;
+    use proxy_fork_core::{AdminServer, AdminServerBuilder, ProxyManager};
+    use serde_json::{Value, json};
+    use tokio::sync::RwLock;
+
+    fn admin() -> (AdminServer, Arc<RwLock<ProxyManager>>) {
+        let manager = Arc::new(RwLock::new(
+            ProxyManager::from_config(ProxyManager::builder().build().unwrap()).unwrap(),
+        ));
+        let admin = AdminServerBuilder::default()
+            .proxy_manager(manager.clone())
+            .token("secret")
+            .build()
+            .unwrap();
+        (admin, manager)
+    }
+
+    async fn call(
+        admin: &AdminServer,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .header("authorization", "Bearer secret")
+            .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+            .unwrap();
+        let res = admin.handle(req).await;
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_admin_requires_token() {
+        let (admin, _) = admin();
+
+        // 健康检查不需要令牌
+        let req = Request::get("/health").body(Body::empty()).unwrap();
+        assert_eq!(admin.handle(req).await.status(), StatusCode::OK);
+
+        let req = Request::get("/rules").body(Body::empty()).unwrap();
+        assert_eq!(admin.handle(req).await.status(), StatusCode::UNAUTHORIZED);
+        let req = Request::get("/rules")
+            .header("authorization", "Bearer wrong!")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(admin.handle(req).await.status(), StatusCode::UNAUTHORIZED);
+
+        let token = AdminServer::random_token();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, AdminServer::random_token());
+    }
+
+    #[tokio::test]
+    async fn test_admin_rule_lifecycle() {
+        let (admin, manager) = admin();
+        assert!(!manager.read().await.is_runtime_modified());
+
+        // 添加：DSL 字符串或规则对象
+        let (status, entry) = call(
+            &admin,
+            Method::POST,
+            "/rules",
+            Some(json!(
+                "https://api.example.com/v1/* -> http://localhost:3000 [group=local]"
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(entry["id"], "https://api.example.com/v1/*#local");
+        assert_eq!(entry["group"], "local");
+        assert_eq!(entry["enabled"], true);
+        // 运行时修改会被标记，热重载时据此提示
+        assert!(manager.read().await.is_runtime_modified());
+
+        // 同一分组内的同一模式冲突，其他分组可以共用
+        let (status, _) = call(
+            &admin,
+            Method::POST,
+            "/rules",
+            Some(json!(
+                "https://api.example.com/v1/* -> http://localhost:4000 [group=local]"
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, entry) = call(
+            &admin,
+            Method::POST,
+            "/rules",
+            Some(json!(
+                "https://api.example.com/v1/* -> https://staging.example.com [group=staging]"
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(entry["id"], "https://api.example.com/v1/*#staging");
+
+        let (status, body) = call(&admin, Method::POST, "/rules", Some(json!("nonsense"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("invalid rule"));
+
+        let (status, _) = call(
+            &admin,
+            Method::POST,
+            "/rules",
+            Some(json!("http://static.example.com/ -> http://localhost:8080")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (_, list) = call(&admin, Method::GET, "/rules", None).await;
+        assert_eq!(list["rules"].as_array().unwrap().len(), 3);
+
+        // 禁用与启用：标识可按 URL 编码传入，也可原样传入（`#` 需编码）
+        let encoded = "/rules/https%3A%2F%2Fapi.example.com%2Fv1%2F*%23local";
+        let (status, entry) =
+            call(&admin, Method::POST, &format!("{}/disable", encoded), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(entry["enabled"], false);
+        assert!(
+            manager
+                .read()
+                .await
+                .is_rule_disabled("https://api.example.com/v1/*#local")
+        );
+        // 另一分组的同名规则不受影响
+        assert!(
+            !manager
+                .read()
+                .await
+                .is_rule_disabled("https://api.example.com/v1/*#staging")
+        );
+        let (_, entry) = call(
+            &admin,
+            Method::POST,
+            "/rules/https://api.example.com/v1/*%23local/enable",
+            None,
+        )
+        .await;
+        assert_eq!(entry["enabled"], true);
+
+        // 分组被禁用时规则不生效
+        manager.write().await.disable_group("local").await;
+        let (_, entry) = call(&admin, Method::GET, encoded, None).await;
+        assert_eq!(entry["enabled"], true);
+        assert_eq!(entry["active"], false);
+
+        // 替换为另一条已存在规则的标识时冲突
+        let (status, _) = call(
+            &admin,
+            Method::PUT,
+            encoded,
+            Some(json!(
+                "https://api.example.com/v1/* -> http://localhost:3001 [group=staging]"
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(manager.read().await.all_rules().len(), 3);
+
+        // 替换
+        let (status, entry) = call(
+            &admin,
+            Method::PUT,
+            encoded,
+            Some(json!(
+                "https://api.example.com/v2/* -> http://localhost:3001"
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(entry["id"], "https://api.example.com/v2/*");
+        let (status, _) = call(&admin, Method::GET, encoded, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // 删除
+        let (status, body) = call(
+            &admin,
+            Method::DELETE,
+            "/rules/https://api.example.com/v2/*",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], "https://api.example.com/v2/*");
+        assert_eq!(manager.read().await.all_rules().len(), 2);
+        let (status, _) = call(
+            &admin,
+            Method::DELETE,
+            "/rules/https://api.example.com/v2/*",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_admin_add_rule_object() {
+        let (admin, manager) = admin();
+        let rule: proxy_fork_core::ProxyRule =
+            "http://static.example.com/ -> http://localhost:8080"
+                .parse()
+                .unwrap();
+        let (status, entry) = call(
+            &admin,
+            Method::POST,
+            "/rules",
+            Some(serde_json::to_value(&rule).unwrap()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(entry["id"], "http://static.example.com/");
+        assert_eq!(manager.read().await.exact_rule_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_admin_stats_and_cache() {
+        let (admin, manager) = admin();
+        manager
+            .write()
+            .await
+            .add_proxy_rule(
+                "https://api.example.com/* -> http://localhost:3000"
+                    .parse()
+                    .unwrap(),
+            )
+            .await;
+        let uri = "https://api.example.com/x".parse().unwrap();
+        manager.read().await.find_target(&uri).await;
+
+        let (status, stats) = call(&admin, Method::GET, "/stats", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["pattern_rules"], 1);
+        // 未启用 proxy_manage_stats 时统计恒为 0
+        if cfg!(feature = "proxy_manage_stats") {
+            assert_eq!(stats["total_lookups"], 1);
+            assert_eq!(stats["pattern_hits"], 1);
+        }
+
+        let (status, _) = call(&admin, Method::POST, "/stats/reset", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, stats) = call(&admin, Method::GET, "/stats", None).await;
+        assert_eq!(stats["total_lookups"], 0);
+
+        let (status, _) = call(&admin, Method::POST, "/cache/clear", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = call(&admin, Method::GET, "/nope", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(&admin, Method::PATCH, "/rules/x", None).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod body_rewrite_test {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use http::Response;
+    use http_body_util::BodyExt;
+    use hudsucker::Body;
+    use proxy_fork_core::BodyRewrite;
+
+    fn response(content_type: &str, body: impl Into<Body>) -> Response<Body> {
+        Response::builder()
+            .header("content-type", content_type)
+            .body(body.into())
+            .unwrap()
+    }
+
+    async fn text(res: Response<Body>) -> String {
+        let bytes = res.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn gzip(data: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_rewrite_text() {
+        let rewrite = BodyRewrite::default()
+            .replace("https://cdn.example.com", "http://localhost:3000")
+            .regex(r"s#/assets/app\.[0-9a-f]+\.js#/assets/app.js#")
+            .unwrap()
+            .inject_head("<script src=\"/dev.js\"></script>")
+            .inject_body("<div id=\"dev\"></div>");
+
+        let html = "<HTML><head><script src=\"https://cdn.example.com/assets/app.3f2a.js\"></script></HEAD>\
+            <body><img src=\"https://cdn.example.com/logo.png\"><script src=\"/assets/app.9e1b.js\"></script></Body></html>";
+        assert_eq!(
+            rewrite.rewrite_text(html, true),
+            "<HTML><head><script src=\"http://localhost:3000/assets/app.js\"></script>\
+            <script src=\"/dev.js\"></script></HEAD><body><img src=\"http://localhost:3000/logo.png\">\
+            <script src=\"/assets/app.js\"></script><div id=\"dev\"></div></Body></html>"
+        );
+
+        // 非 HTML 不注入；找不到标签时跳过
+        let css = "a { background: url(https://cdn.example.com/bg.png) } </head>";
+        assert_eq!(
+            rewrite.rewrite_text(css, false),
+            "a { background: url(http://localhost:3000/bg.png) } </head>"
+        );
+        assert_eq!(
+            rewrite.rewrite_text("<p>fragment</p>", true),
+            "<p>fragment</p>"
+        );
+        assert!(BodyRewrite::default().is_empty());
+        assert!(BodyRewrite::default().regex("not-sed").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_fixes_headers() {
+        let rewrite = BodyRewrite::default().replace("production", "local");
+
+        let res = response("text/html; charset=utf-8", "<p>production</p>");
+        let res = rewrite.apply(res).await;
+        assert_eq!(res.headers()["content-length"], "12");
+        assert_eq!(text(res).await, "<p>local</p>");
+
+        // 压缩的响应体先解压，改写后以未压缩形式返回
+        let compressed = gzip("{\"env\":\"production\"}");
+        let res = Response::builder()
+            .header("content-type", "application/json")
+            .header("content-encoding", "gzip")
+            .header("content-length", compressed.len())
+            .body(Body::from(compressed))
+            .unwrap();
+        let res = rewrite.apply(res).await;
+        assert!(res.headers().get("content-encoding").is_none());
+        assert_eq!(res.headers()["content-length"], "15");
+        assert_eq!(text(res).await, "{\"env\":\"local\"}");
+    }
+
+    #[tokio::test]
+    async fn test_apply_skips() {
+        let rewrite = BodyRewrite::default().replace("production", "local");
+
+        // Content-Type 不匹配
+        let res = rewrite.apply(response("image/png", "production")).await;
+        assert_eq!(text(res).await, "production");
+
+        // 自定义 Content-Type
+        let only_js = rewrite.clone().content_types(&["*javascript"]).unwrap();
+        let res = only_js.apply(response("text/html", "production")).await;
+        assert_eq!(text(res).await, "production");
+        let res = only_js
+            .apply(response("application/javascript", "production"))
+            .await;
+        assert_eq!(text(res).await, "local");
+
+        // 不支持的压缩方式
+        let res = Response::builder()
+            .header("content-type", "text/plain")
+            .header("content-encoding", "compress")
+            .body(Body::from("production"))
+            .unwrap();
+        let res = rewrite.apply(res).await;
+        assert_eq!(res.headers()["content-encoding"], "compress");
+        assert_eq!(text(res).await, "production");
+
+        // 超过大小上限（无 Content-Length 时读取到上限为止，之后原样转发）
+        let mut small = rewrite.clone();
+        small.max_size = 8;
+        let chunks = ["produ", "ction", " production"].map(Ok::<_, hudsucker::Error>);
+        let res = response(
+            "text/plain",
+            Body::from_stream(hudsucker::futures::stream::iter(chunks)),
+        );
+        let res = small.apply(res).await;
+        assert!(res.headers().get("content-length").is_none());
+        assert_eq!(text(res).await, "production production");
+    }
+}
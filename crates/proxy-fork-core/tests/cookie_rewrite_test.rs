@@ -0,0 +1,179 @@
+#[cfg(test)]
+mod cookie_rewrite_test {
+    use http::{HeaderMap, Uri};
+    use proxy_fork_core::{CookieOrigin, CookieRewrite, SameSite};
+
+    fn origin(original: &str, forwarded: &str) -> CookieOrigin {
+        CookieOrigin::new(
+            &original.parse::<Uri>().unwrap(),
+            &forwarded.parse::<Uri>().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_cookie_origin_path_prefix() {
+        // 去掉前缀转发：/api/users -> /users
+        let o = origin(
+            "https://app.example.com/api/users",
+            "http://localhost:3000/users",
+        );
+        assert_eq!(o.host, "app.example.com");
+        assert!(o.secure);
+        assert_eq!(o.path_prefix, Some((String::new(), "/api".to_string())));
+
+        // 添加前缀转发：/users -> /local/users
+        let o = origin(
+            "http://app.example.com/users",
+            "http://localhost:3000/local/users",
+        );
+        assert!(!o.secure);
+        assert_eq!(o.path_prefix, Some(("/local".to_string(), String::new())));
+
+        // 共同后缀不拆分路径段
+        let o = origin("http://a.com/v1/users", "http://b.com/v2/users");
+        assert_eq!(o.path_prefix, Some(("/v2".to_string(), "/v1".to_string())));
+
+        // 路径不变
+        let o = origin("http://a.com/users", "http://b.com/users");
+        assert_eq!(o.path_prefix, None);
+    }
+
+    #[test]
+    fn test_cookie_rewrite_auto_domain() {
+        let rewrite = CookieRewrite::default();
+        let o = origin("https://app.example.com/", "http://localhost:3000/");
+
+        // 不包含原始主机的 Domain 被删除
+        assert_eq!(
+            rewrite.rewrite("sid=1; Domain=localhost; Path=/; HttpOnly", &o),
+            "sid=1; Path=/; HttpOnly"
+        );
+        // 原始主机或其父域保留
+        assert_eq!(
+            rewrite.rewrite("sid=1; Domain=.example.com", &o),
+            "sid=1; Domain=.example.com"
+        );
+        assert_eq!(
+            rewrite.rewrite("sid=1; domain=APP.example.com", &o),
+            "sid=1; domain=APP.example.com"
+        );
+        assert_eq!(rewrite.rewrite("sid=1; Domain=ample.com", &o), "sid=1");
+    }
+
+    #[test]
+    fn test_cookie_rewrite_auto_path() {
+        let rewrite = CookieRewrite::default();
+
+        let o = origin(
+            "https://app.example.com/api/login",
+            "http://localhost:3000/login",
+        );
+        assert_eq!(rewrite.rewrite("sid=1; Path=/", &o), "sid=1; Path=/api/");
+        assert_eq!(
+            rewrite.rewrite("sid=1; Path=/auth", &o),
+            "sid=1; Path=/api/auth"
+        );
+
+        let o = origin(
+            "https://app.example.com/login",
+            "http://localhost:3000/local/login",
+        );
+        assert_eq!(
+            rewrite.rewrite("sid=1; Path=/local/auth", &o),
+            "sid=1; Path=/auth"
+        );
+        assert_eq!(rewrite.rewrite("sid=1; Path=/local", &o), "sid=1; Path=/");
+        // 不在转发前缀下的路径保持原样
+        assert_eq!(
+            rewrite.rewrite("sid=1; Path=/other", &o),
+            "sid=1; Path=/other"
+        );
+        assert_eq!(
+            rewrite.rewrite("sid=1; Path=/localx", &o),
+            "sid=1; Path=/localx"
+        );
+    }
+
+    #[test]
+    fn test_cookie_rewrite_auto_secure() {
+        let rewrite = CookieRewrite::default();
+
+        // 原始请求为 HTTP：删除 Secure，SameSite=None 改为 Lax
+        let o = origin("http://app.example.com/", "https://prod.example.com/");
+        assert_eq!(
+            rewrite.rewrite("sid=1; Secure; SameSite=None; HttpOnly", &o),
+            "sid=1; SameSite=Lax; HttpOnly"
+        );
+
+        // 原始请求为 HTTPS：SameSite=None 补上 Secure
+        let o = origin("https://app.example.com/", "http://localhost:3000/");
+        assert_eq!(
+            rewrite.rewrite("sid=1; SameSite=none", &o),
+            "sid=1; SameSite=none; Secure"
+        );
+        assert_eq!(
+            rewrite.rewrite("sid=1; Secure; SameSite=Strict", &o),
+            "sid=1; Secure; SameSite=Strict"
+        );
+    }
+
+    #[test]
+    fn test_cookie_rewrite_explicit() {
+        let o = origin("https://app.example.com/", "http://localhost:3000/");
+
+        let rewrite = CookieRewrite {
+            domain: Some("example.com".into()),
+            path: Some("/".into()),
+            secure: Some(true),
+            same_site: Some(SameSite::Strict),
+            ..CookieRewrite::default()
+        };
+        assert_eq!(
+            rewrite.rewrite("sid=1; Domain=localhost; Path=/app; SameSite=Lax", &o),
+            "sid=1; Path=/; SameSite=Strict; Domain=example.com; Secure"
+        );
+
+        // 空字符串删除 Domain；secure = false 删除 Secure
+        let rewrite = CookieRewrite {
+            domain: Some(String::new()),
+            secure: Some(false),
+            ..CookieRewrite::disabled()
+        };
+        assert_eq!(
+            rewrite.rewrite("sid=1; Domain=.example.com; Secure", &o),
+            "sid=1"
+        );
+
+        // 关闭自动改写时保持原样
+        assert_eq!(
+            CookieRewrite::disabled().rewrite("sid=1; Domain=localhost; Secure", &o),
+            "sid=1; Domain=localhost; Secure"
+        );
+    }
+
+    #[test]
+    fn test_cookie_rewrite_apply_all_set_cookie() {
+        let o = origin("https://app.example.com/", "http://localhost:3000/");
+        let mut headers = HeaderMap::new();
+        headers.append("set-cookie", "a=1; Domain=localhost".parse().unwrap());
+        headers.append("set-cookie", "b=2; Path=/".parse().unwrap());
+        headers.append("content-type", "text/plain".parse().unwrap());
+
+        CookieRewrite::default().apply(&mut headers, &o);
+        let cookies: Vec<_> = headers
+            .get_all("set-cookie")
+            .iter()
+            .map(|v| v.to_str().unwrap())
+            .collect();
+        assert_eq!(cookies, vec!["a=1", "b=2; Path=/"]);
+        assert_eq!(headers["content-type"], "text/plain");
+    }
+
+    #[test]
+    fn test_same_site_parse() {
+        assert_eq!("lax".parse::<SameSite>().unwrap(), SameSite::Lax);
+        assert_eq!("STRICT".parse::<SameSite>().unwrap(), SameSite::Strict);
+        assert_eq!(SameSite::None.to_string(), "None");
+        assert!("sometimes".parse::<SameSite>().is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::ErrorKind;
+use std::io::{ErrorKind, Write};
 use std::sync::Arc;
 
 use hudsucker::Proxy;
@@ -11,12 +11,16 @@ use hudsucker::tokio_tungstenite::{
     },
 };
 use proxy_fork_core::{
-    Address, AddressPattern, NoCa, PatternMatcher, PatternType, Protocol, ProxyHandlerBuilder,
-    ProxyManager, rustls,
+    Address, AddressPattern, BodyRewrite, CookieRewrite, FaultInjection, FaultRng, HarRecorder,
+    HeaderPreset, HeaderRewrite, MockResponse, NetworkProfile, NoCa, Pac, PatternMatcher,
+    PatternType, Protocol, ProxyHandlerBuilder, ProxyManager, ProxyRule, QueryRewrite, RecordMatch,
+    RecordMode, RecordReplay, RequestBodyRewrite, RuleAction, UpstreamConnector, UpstreamProxy,
+    UpstreamProxyConfig, rustls,
 };
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::RwLock;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::oneshot;
 use tokio::time::{Duration, timeout};
 
@@ -206,6 +210,7 @@ async fn test_end_to_end_websocket_proxy() {
             let (stream, _) = backend_listener.accept().await.unwrap();
             let host_tx = Arc::clone(&host_tx);
             tokio::spawn(async move {
+                #[allow(clippy::result_large_err)]
                 let callback = |req: &WsRequest, mut response: WsResponse| {
                     if let Some(tx) = host_tx.lock().unwrap().take() {
                         let host = req
@@ -395,3 +400,1952 @@ async fn test_end_to_end_websocket_proxy() {
     proxy_handle.abort();
     backend_handle.abort();
 }
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_end_to_end_unix_socket_proxy() {
+    use tokio::net::UnixListener;
+
+    let dir = tempfile::tempdir().unwrap();
+    let http_socket = dir.path().join("http.sock");
+    let ws_socket = dir.path().join("ws.sock");
+
+    // HTTP backend on a Unix socket: echo back request line and Host header
+    let http_listener = UnixListener::bind(&http_socket).unwrap();
+    let http_backend = tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = http_listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0; 1024];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let request_line = request.lines().next().unwrap_or_default();
+                let host = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("host: "))
+                    .unwrap_or_default();
+                let body = format!("{request_line}|{host}");
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    // WebSocket echo backend on a Unix socket
+    let ws_listener = UnixListener::bind(&ws_socket).unwrap();
+    let ws_backend = tokio::spawn(async move {
+        loop {
+            let (stream, _) = ws_listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let Ok(mut websocket) = hudsucker::tokio_tungstenite::accept_async(stream).await
+                else {
+                    return;
+                };
+                while let Some(Ok(message)) = websocket.next().await {
+                    if let Message::Text(text) = message
+                        && websocket
+                            .send(Message::Text(format!("echo:{text}").into()))
+                            .await
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    let config = ProxyManager::builder().cache_size(1000).build().unwrap();
+    let mut proxy_manager = ProxyManager::from_config(config).unwrap();
+    for rule in [
+        format!(
+            "http://unix.example.com/api/* -> unix://{}",
+            http_socket.display()
+        ),
+        format!(
+            "http://unix.example.com/ws -> unix://{}",
+            ws_socket.display()
+        ),
+    ] {
+        proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+    }
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .with_ca(true)
+        .build()
+        .unwrap();
+
+    let Some(proxy_listener) =
+        bind_or_skip("127.0.0.1:0", "test_end_to_end_unix_socket_proxy").await
+    else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler.clone())
+        .with_websocket_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // HTTP: original path and Host are kept
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://unix.example.com/api/users?id=1").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.text().await.unwrap(),
+        "GET /api/users?id=1 HTTP/1.1|unix.example.com"
+    );
+
+    // WebSocket: handshake and echo through a CONNECT tunnel
+    let mut tunnel = TcpStream::connect(proxy_addr).await.unwrap();
+    tunnel
+        .write_all(b"CONNECT unix.example.com:80 HTTP/1.1\r\nHost: unix.example.com:80\r\n\r\n")
+        .await
+        .unwrap();
+    let mut buf = [0u8; 1024];
+    let n = timeout(Duration::from_secs(5), tunnel.read(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&buf[..n]).starts_with("HTTP/1.1 200"));
+
+    let (mut websocket, _) = timeout(
+        Duration::from_secs(5),
+        client_async("ws://unix.example.com/ws", tunnel),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    websocket
+        .send(Message::Text("hello-unix".into()))
+        .await
+        .unwrap();
+    let message = timeout(Duration::from_secs(5), websocket.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(message, Message::Text("echo:hello-unix".into()));
+
+    proxy_handle.abort();
+    http_backend.abort();
+    ws_backend.abort();
+}
+
+/// 启动一个只返回固定文本的 HTTP 后端
+async fn spawn_text_backend(listener: TcpListener, body: &'static str) {
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0; 1024];
+                let _ = socket.read(&mut buf).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+}
+
+/// 模拟 HTTP CONNECT 上游代理，返回记录隧道目标的通道
+fn spawn_connect_upstream(listener: TcpListener) -> UnboundedReceiver<String> {
+    let (tunnel_tx, tunnel_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let tunnel_tx = tunnel_tx.clone();
+            tokio::spawn(async move {
+                let mut head = Vec::new();
+                while !head.ends_with(b"\r\n\r\n") {
+                    head.push(socket.read_u8().await.unwrap());
+                }
+                let head = String::from_utf8(head).unwrap();
+                let target = head.split_whitespace().nth(1).unwrap().to_string();
+                tunnel_tx.send(target.clone()).unwrap();
+
+                let mut upstream = TcpStream::connect(target).await.unwrap();
+                socket
+                    .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                    .await
+                    .unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut socket, &mut upstream).await;
+            });
+        }
+    });
+    tunnel_rx
+}
+
+#[tokio::test]
+async fn test_end_to_end_upstream_proxy() {
+    let test_name = "test_end_to_end_upstream_proxy";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_port = backend_listener.local_addr().unwrap().port();
+    spawn_text_backend(backend_listener, "Hello from backend").await;
+
+    let Some(upstream_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let upstream_port = upstream_listener.local_addr().unwrap().port();
+    let mut tunnel_rx = spawn_connect_upstream(upstream_listener);
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    for rule in [
+        format!("http://example.com/* -> http://127.0.0.1:{backend_port}"),
+        format!("http://direct.example.com/* -> http://localhost:{backend_port} [via=direct]"),
+        format!("http://shared.example.com/* -> http://localhost:{backend_port}"),
+    ] {
+        proxy_manager
+            .add_proxy_rule(rule.parse::<ProxyRule>().unwrap())
+            .await;
+    }
+    let proxy_manager = Arc::new(RwLock::new(proxy_manager));
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(proxy_manager)
+        .build()
+        .unwrap();
+    let upstream: UpstreamProxy = format!("http://127.0.0.1:{upstream_port}").parse().unwrap();
+    let connector = UpstreamConnector::new(UpstreamProxyConfig::all(upstream));
+
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector_over(rustls::crypto::aws_lc_rs::default_provider(), connector)
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+
+    // 经全局上游代理转发
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "Hello from backend");
+    assert_eq!(
+        tunnel_rx.recv().await.unwrap(),
+        format!("127.0.0.1:{backend_port}")
+    );
+
+    // via=direct 的规则绕过上游代理
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://direct.example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "Hello from backend");
+    assert!(tunnel_rx.try_recv().is_err());
+
+    // 目标相同但未指定 via 的规则不受影响，仍走上游代理
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://shared.example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        tunnel_rx.recv().await.unwrap(),
+        format!("localhost:{backend_port}")
+    );
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_tunnel_uses_https_proxy() {
+    let test_name = "test_end_to_end_tunnel_uses_https_proxy";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_port = backend_listener.local_addr().unwrap().port();
+    spawn_text_backend(backend_listener, "Hello from backend").await;
+
+    let Some(upstream_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let upstream_port = upstream_listener.local_addr().unwrap().port();
+    let mut tunnel_rx = spawn_connect_upstream(upstream_listener);
+
+    // 只设置 HTTPS_PROXY
+    let upstream = UpstreamProxyConfig::from_lookup(|name| {
+        (name == "HTTPS_PROXY").then(|| format!("http://127.0.0.1:{upstream_port}"))
+    });
+    let proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    // 未启用 CA 时 CONNECT 隧道原样透传
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector_over(
+            rustls::crypto::aws_lc_rs::default_provider(),
+            UpstreamConnector::new(upstream),
+        )
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let open_tunnel = || async move {
+        let mut stream = TcpStream::connect(proxy_addr).await.unwrap();
+        let target = format!("127.0.0.1:{backend_port}");
+        stream
+            .write_all(format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await.unwrap());
+        }
+        assert!(head.starts_with(b"HTTP/1.1 200"));
+        stream
+    };
+
+    // 以 TLS 握手开头的隧道经 HTTPS_PROXY 建立
+    let mut stream = open_tunnel().await;
+    stream.write_all(b"\x16\x03\x01\x00\x00").await.unwrap();
+    let target = timeout(Duration::from_secs(5), tunnel_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(target, format!("127.0.0.1:{backend_port}"));
+
+    // 明文隧道只会使用 HTTP_PROXY，未设置时直连
+    let mut stream = open_tunnel().await;
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = vec![0; 1024];
+    let len = timeout(Duration::from_secs(5), stream.read(&mut response))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&response[..len]).ends_with("Hello from backend"));
+    assert!(tunnel_rx.try_recv().is_err());
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_local_directory() {
+    let test_name = "test_end_to_end_local_directory";
+    let dir = tempfile::tempdir().unwrap();
+    fs_err::write(dir.path().join("index.html"), "<h1>local build</h1>").unwrap();
+    fs_err::create_dir(dir.path().join("assets")).unwrap();
+    fs_err::write(dir.path().join("assets/app.css"), "body { color: red }").unwrap();
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let rule: ProxyRule = format!(
+        "http://app.example.com/static/* -> file://{} [strip_prefix, spa]",
+        dir.path().display()
+    )
+    .parse()
+    .unwrap();
+    proxy_manager.add_proxy_rule(rule).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .get("http://app.example.com/static/assets/app.css")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/css; charset=utf-8"
+    );
+    assert_eq!(response.text().await.unwrap(), "body { color: red }");
+
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .get("http://app.example.com/static/assets/app.css")
+            .header("range", "bytes=0-3")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 206);
+    assert_eq!(response.text().await.unwrap(), "body");
+
+    // SPA 回退
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://app.example.com/static/settings").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "<h1>local build</h1>");
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_mock_response() {
+    let test_name = "test_end_to_end_mock_response";
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let rule = ProxyRule {
+        action: RuleAction::Mock(
+            MockResponse::new(200)
+                .header("content-type", "application/json")
+                .body(r#"{"user": "{{capture.id}}", "page": "{{query.page}}"}"#),
+        ),
+        ..ProxyRule::new(
+            AddressPattern::new(
+                Protocol::Http,
+                "api.example.com",
+                None,
+                Some(r"re:^/users/(?P<id>\d+)"),
+            )
+            .unwrap(),
+            "http://localhost".parse().unwrap(),
+        )
+    };
+    proxy_manager.add_proxy_rule(rule).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://api.example.com/users/42?page=3").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    assert_eq!(
+        response.text().await.unwrap(),
+        r#"{"user": "42", "page": "3"}"#
+    );
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_redirect_and_block() {
+    let test_name = "test_end_to_end_redirect_and_block";
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    for rule in [
+        "http://old.example.com/* -> https://new.example.com [redirect=301]",
+        "http://ads.example.com/* -> http://localhost [block=451]",
+        "http://tracker.example.com/* -> http://localhost [block=reset]",
+    ] {
+        proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+    }
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://old.example.com/docs?page=2").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 301);
+    assert_eq!(
+        response.headers()["location"],
+        "https://new.example.com/docs?page=2"
+    );
+
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://ads.example.com/pixel.gif").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 451);
+
+    let result = timeout(
+        Duration::from_secs(5),
+        client.get("http://tracker.example.com/collect").send(),
+    )
+    .await
+    .unwrap();
+    assert!(result.is_err());
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_fault_injection() {
+    let test_name = "test_end_to_end_fault_injection";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    spawn_text_backend(backend_listener, "hello from a flaky backend").await;
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let faults = [
+        (
+            "slow",
+            FaultInjection {
+                latency_ms: Some((200, 300)),
+                ..Default::default()
+            },
+        ),
+        (
+            "errors",
+            FaultInjection {
+                error_rate: 1.0,
+                ..Default::default()
+            },
+        ),
+        (
+            "aborts",
+            FaultInjection {
+                abort_rate: 1.0,
+                ..Default::default()
+            },
+        ),
+        (
+            "resets",
+            FaultInjection {
+                reset_rate: 1.0,
+                ..Default::default()
+            },
+        ),
+    ];
+    for (host, fault) in faults {
+        let mut rule: ProxyRule =
+            format!("http://{}.example.com/* -> http://{}", host, backend_addr)
+                .parse()
+                .unwrap();
+        rule.fault = fault;
+        proxy_manager.add_proxy_rule(rule).await;
+    }
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .fault_rng(FaultRng::seeded(7))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+
+    let started = tokio::time::Instant::now();
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://slow.example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert_eq!(response.text().await.unwrap(), "hello from a flaky backend");
+
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://errors.example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 503);
+
+    // 响应头正常返回，响应体读到一半中断
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://aborts.example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(
+        timeout(Duration::from_secs(5), response.text())
+            .await
+            .unwrap()
+            .is_err()
+    );
+
+    let result = timeout(
+        Duration::from_secs(5),
+        client.get("http://resets.example.com/").send(),
+    )
+    .await
+    .unwrap();
+    assert!(result.is_err());
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_throttle() {
+    let test_name = "test_end_to_end_throttle";
+    let body: &'static str = "x".repeat(30_000).leak();
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    spawn_text_backend(backend_listener, body).await;
+
+    // 隧道后端：收到数据后返回 30000 字节
+    let Some(tunnel_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let tunnel_addr = tunnel_listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = tunnel_listener.accept().await.unwrap();
+        let mut buf = [0; 4];
+        socket.read_exact(&mut buf).await.unwrap();
+        socket.write_all(body.as_bytes()).await.unwrap();
+    });
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    // 800 kbps = 100000 字节每秒
+    let rule = format!(
+        "http://slow.example.com/* -> http://{} [throttle=800/800/100]",
+        backend_addr
+    );
+    proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+    let rule = format!("http://fast.example.com/* -> http://{}", backend_addr);
+    proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .throttle(Some(NetworkProfile::new(800, 800, 200)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+
+    // 规则中的 throttle：100ms 延迟 + 30000 字节 / 100000 字节每秒
+    let started = tokio::time::Instant::now();
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://slow.example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.text().await.unwrap(), body);
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(350), "{:?}", elapsed);
+
+    // CONNECT 隧道使用全局配置：200ms 延迟 + 30000 字节 / 100000 字节每秒
+    let mut stream = TcpStream::connect(proxy_addr).await.unwrap();
+    stream
+        .write_all(format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", tunnel_addr).as_bytes())
+        .await
+        .unwrap();
+    let mut head = [0; 1024];
+    let n = stream.read(&mut head).await.unwrap();
+    assert!(String::from_utf8_lossy(&head[..n]).starts_with("HTTP/1.1 200"));
+
+    let started = tokio::time::Instant::now();
+    stream.write_all(b"ping").await.unwrap();
+    let mut received = Vec::new();
+    timeout(Duration::from_secs(5), stream.read_to_end(&mut received))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.len(), body.len());
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(350), "{:?}", elapsed);
+
+    proxy_handle.abort();
+}
+
+/// 启动一个把收到的请求（请求行、请求头与按 Content-Length 读取的请求体）原样作为响应体返回的 HTTP 后端
+async fn spawn_echo_backend(listener: TcpListener) {
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                let head_len = loop {
+                    if let Some(idx) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break idx + 4;
+                    }
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                };
+                let content_length = String::from_utf8_lossy(&request[..head_len])
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                while request.len() < head_len + content_length {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                    request.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.write_all(&request).await.unwrap();
+            });
+        }
+    });
+}
+
+#[tokio::test]
+async fn test_end_to_end_request_headers() {
+    let test_name = "test_end_to_end_request_headers";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    spawn_echo_backend(backend_listener).await;
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let mut rule: ProxyRule = format!("http://api.example.com/* -> http://{}", backend_addr)
+        .parse()
+        .unwrap();
+    rule.request_headers = HeaderRewrite::default()
+        .remove("If-None-Match")
+        .unwrap()
+        .replace("Cookie", "s/session=[^;]+/session=dev/")
+        .unwrap()
+        .set("Authorization", "Bearer dev")
+        .append("X-Forwarded-User", "alice");
+    proxy_manager.add_proxy_rule(rule).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .get("http://api.example.com/users")
+            .header("If-None-Match", "\"v1\"")
+            .header("Cookie", "theme=dark; session=prod-token")
+            .header("Authorization", "Bearer prod")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let head = response.text().await.unwrap().to_ascii_lowercase();
+
+    assert!(head.starts_with("get /users http/1.1\r\n"), "{}", head);
+    assert!(!head.contains("if-none-match"), "{}", head);
+    assert!(
+        head.contains("cookie: theme=dark; session=dev\r\n"),
+        "{}",
+        head
+    );
+    assert!(head.contains("authorization: bearer dev\r\n"), "{}", head);
+    assert!(!head.contains("bearer prod"), "{}", head);
+    assert!(head.contains("x-forwarded-user: alice\r\n"), "{}", head);
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_response_headers() {
+    let test_name = "test_end_to_end_response_headers";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = backend_listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0; 4096];
+                let _ = socket.read(&mut buf).await.unwrap();
+                socket
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\n\
+                        Content-Security-Policy: default-src 'self'\r\n\
+                        Strict-Transport-Security: max-age=31536000\r\n\
+                        ETag: \"v1\"\r\n\
+                        Cache-Control: public, max-age=3600\r\n\
+                        Server: backend\r\n\
+                        Content-Length: 2\r\n\r\nok",
+                    )
+                    .await
+                    .unwrap();
+            });
+        }
+    });
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let mut rule: ProxyRule = format!(
+        "http://api.example.com/* -> http://{} [cors, strip-csp, no-cache]",
+        backend_addr
+    )
+    .parse()
+    .unwrap();
+    rule.response_headers = HeaderRewrite::default()
+        .remove("Server")
+        .unwrap()
+        .set("X-Proxied-By", "proxy-fork");
+    proxy_manager.add_proxy_rule(rule).await;
+    let mut rule: ProxyRule = "http://mock.example.com/* -> http://localhost [mock=200]"
+        .parse()
+        .unwrap();
+    rule.response_presets = vec![HeaderPreset::Cors(Some("http://localhost:3000".into()))];
+    proxy_manager.add_proxy_rule(rule).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+
+    // 转发的响应：回显 Origin，去掉 CSP/HSTS 与缓存头
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .get("http://api.example.com/users")
+            .header("Origin", "http://localhost:5173")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let headers = response.headers().clone();
+    assert_eq!(response.text().await.unwrap(), "ok");
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "http://localhost:5173"
+    );
+    assert_eq!(headers["access-control-allow-credentials"], "true");
+    assert!(headers.get("content-security-policy").is_none());
+    assert!(headers.get("strict-transport-security").is_none());
+    assert!(headers.get("etag").is_none());
+    assert_eq!(
+        headers["cache-control"],
+        "no-store, no-cache, must-revalidate, max-age=0"
+    );
+    assert!(headers.get("server").is_none());
+    assert_eq!(headers["x-proxied-by"], "proxy-fork");
+
+    // 预检请求由代理直接应答
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .request(reqwest::Method::OPTIONS, "http://api.example.com/users")
+            .header("Origin", "http://localhost:5173")
+            .header("Access-Control-Request-Method", "DELETE")
+            .header("Access-Control-Request-Headers", "authorization")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+    let headers = response.headers();
+    assert_eq!(headers["access-control-allow-methods"], "DELETE");
+    assert_eq!(headers["access-control-allow-headers"], "authorization");
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "http://localhost:5173"
+    );
+    assert_eq!(headers["x-proxied-by"], "proxy-fork");
+
+    // 预设响应同样应用响应头预设
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://mock.example.com/data").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "http://localhost:3000"
+    );
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_set_cookie_rewrite() {
+    let test_name = "test_end_to_end_set_cookie_rewrite";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = backend_listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0; 4096];
+                let _ = socket.read(&mut buf).await.unwrap();
+                socket
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\n\
+                        Set-Cookie: sid=abc; Domain=localhost; Path=/local; Secure; SameSite=None; HttpOnly\r\n\
+                        Set-Cookie: theme=dark; Domain=.example.com; Path=/local/settings\r\n\
+                        Content-Length: 2\r\n\r\nok",
+                    )
+                    .await
+                    .unwrap();
+            });
+        }
+    });
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let rule = format!("http://app.example.com/* -> http://{}/local", backend_addr);
+    proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+    let mut rule: ProxyRule = format!("http://raw.example.com/* -> http://{}/local", backend_addr)
+        .parse()
+        .unwrap();
+    rule.cookies = CookieRewrite::disabled();
+    proxy_manager.add_proxy_rule(rule).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let set_cookies = |response: &reqwest::Response| -> Vec<String> {
+        response
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .map(|v| v.to_str().unwrap().to_string())
+            .collect()
+    };
+
+    // 自动改写：Domain 不匹配时删除，Path 映射回原始路径，HTTP 站点去掉 Secure
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://app.example.com/users").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        set_cookies(&response),
+        vec![
+            "sid=abc; Path=/; SameSite=Lax; HttpOnly",
+            "theme=dark; Domain=.example.com; Path=/settings",
+        ]
+    );
+
+    // 关闭自动改写时原样返回
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://raw.example.com/users").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        set_cookies(&response)[0],
+        "sid=abc; Domain=localhost; Path=/local; Secure; SameSite=None; HttpOnly"
+    );
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_location_restore() {
+    let test_name = "test_end_to_end_location_restore";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = backend_listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0; 4096];
+                let _ = socket.read(&mut buf).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 302 Found\r\n\
+                    Location: http://{0}/local/login?next=%2Fhome\r\n\
+                    Refresh: 0; url=http://{0}/local/\r\n\
+                    Content-Length: 0\r\n\r\n",
+                    backend_addr
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let rule = format!("http://app.example.com/* -> http://{}/local", backend_addr);
+    proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    // 指向目标地址的 URL 还原为原始站点，并去掉目标路径前缀
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://app.example.com/account").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 302);
+    assert_eq!(
+        response.headers()["location"],
+        "http://app.example.com/login?next=%2Fhome"
+    );
+    assert_eq!(
+        response.headers()["refresh"],
+        "0; url=http://app.example.com/"
+    );
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_response_body_rewrite() {
+    let test_name = "test_end_to_end_response_body_rewrite";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    // 生产环境的 HTML：gzip 压缩，引用 CDN 上的资源
+    let html = "<html><head><link rel=\"stylesheet\" href=\"https://cdn.example.com/app.css\"></head>\
+        <body><script src=\"https://cdn.example.com/app.js\"></script></body></html>";
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(html.as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = backend_listener.accept().await.unwrap();
+            let compressed = compressed.clone();
+            tokio::spawn(async move {
+                let mut buf = [0; 4096];
+                let _ = socket.read(&mut buf).await.unwrap();
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+                    Content-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+                    compressed.len()
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(&compressed).await.unwrap();
+            });
+        }
+    });
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let mut rule: ProxyRule = format!("http://www.example.com/* -> http://{}", backend_addr)
+        .parse()
+        .unwrap();
+    rule.response_body = BodyRewrite::default()
+        .replace("https://cdn.example.com", "http://localhost:3000")
+        .inject_head("<script src=\"http://localhost:3000/@vite/client\"></script>");
+    proxy_manager.add_proxy_rule(rule).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://www.example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(response.headers().get("content-encoding").is_none());
+    let content_length = response.headers()["content-length"].clone();
+    let body = response.text().await.unwrap();
+    assert_eq!(content_length, body.len().to_string().as_str());
+    assert_eq!(
+        body,
+        "<html><head><link rel=\"stylesheet\" href=\"http://localhost:3000/app.css\">\
+        <script src=\"http://localhost:3000/@vite/client\"></script></head>\
+        <body><script src=\"http://localhost:3000/app.js\"></script></body></html>"
+    );
+
+    // HEAD 请求不改写，响应头保持上游的取值
+    let response = timeout(
+        Duration::from_secs(5),
+        client.head("http://www.example.com/").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.headers()["content-encoding"], "gzip");
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_request_body() {
+    let test_name = "test_end_to_end_request_body";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    spawn_echo_backend(backend_listener).await;
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let mut rule: ProxyRule = format!("http://api.example.com/* -> http://{}", backend_addr)
+        .parse()
+        .unwrap();
+    rule.request_body = RequestBodyRewrite::default()
+        .remove("$.debug")
+        .unwrap()
+        .set("$.user.role", serde_json::json!("admin"))
+        .unwrap()
+        .form(QueryRewrite::default().set("debug", "1"));
+    proxy_manager.add_proxy_rule(rule).await;
+
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+
+    // JSON 请求体：删除 debug，设置 user.role，Content-Length 与新请求体一致
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .post("http://api.example.com/users")
+            .json(&serde_json::json!({ "user": { "name": "alice" }, "debug": true }))
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let echoed = response.text().await.unwrap();
+    let body = r#"{"user":{"name":"alice","role":"admin"}}"#;
+    assert!(echoed.ends_with(&format!("\r\n\r\n{}", body)), "{}", echoed);
+    assert!(
+        echoed
+            .to_ascii_lowercase()
+            .contains(&format!("content-length: {}\r\n", body.len())),
+        "{}",
+        echoed
+    );
+
+    // 表单请求体
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .post("http://api.example.com/login")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body("user=alice&debug=0")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let echoed = response.text().await.unwrap();
+    assert!(echoed.ends_with("\r\n\r\nuser=alice&debug=1"), "{}", echoed);
+
+    // 其他类型的请求体原样转发
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .post("http://api.example.com/upload")
+            .header("content-type", "text/plain")
+            .body("{\"debug\":true}")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let echoed = response.text().await.unwrap();
+    assert!(echoed.ends_with("\r\n\r\n{\"debug\":true}"), "{}", echoed);
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_har_capture() {
+    let test_name = "test_end_to_end_har_capture";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    spawn_echo_backend(backend_listener).await;
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let rule = format!("http://api.example.com/* -> http://{}", backend_addr);
+    proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+    let rule = "http://mock.example.com/* -> http://localhost [mock=204]";
+    proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+
+    let har = HarRecorder::new().max_body_size(8);
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .har(Some(har.clone()))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .post("http://api.example.com/echo?x=1")
+            .header("content-type", "text/plain")
+            .body("hello world")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(response.text().await.unwrap().ends_with("hello world"));
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://mock.example.com/ping").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 204);
+
+    // 响应体传输结束后才写入记录
+    timeout(Duration::from_secs(5), async {
+        while har.len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let log = har.to_har();
+    assert_eq!(log["log"]["version"], "1.2");
+    let entries = log["log"]["entries"].as_array().unwrap();
+
+    let forwarded = &entries[0];
+    assert_eq!(forwarded["request"]["method"], "POST");
+    assert_eq!(
+        forwarded["request"]["url"],
+        "http://api.example.com/echo?x=1"
+    );
+    assert_eq!(
+        forwarded["request"]["queryString"],
+        serde_json::json!([{ "name": "x", "value": "1" }])
+    );
+    assert_eq!(forwarded["request"]["bodySize"], 11);
+    assert_eq!(forwarded["request"]["postData"]["mimeType"], "text/plain");
+    // 超过上限的消息体只记录前 8 个字节
+    assert_eq!(forwarded["request"]["postData"]["text"], "hello wo");
+    assert_eq!(forwarded["request"]["postData"]["_truncated"], true);
+    assert_eq!(
+        forwarded["_rewrittenUrl"],
+        format!("http://{}/echo?x=1", backend_addr)
+    );
+    assert_eq!(forwarded["_rule"], "http://api.example.com/*");
+    assert_eq!(forwarded["response"]["status"], 200);
+    assert!(forwarded["response"]["content"]["size"].as_u64().unwrap() > 11);
+    assert_eq!(forwarded["response"]["content"]["text"], "POST /ec");
+    assert!(forwarded["timings"]["wait"].as_f64().unwrap() >= 0.0);
+    assert!(
+        forwarded["startedDateTime"]
+            .as_str()
+            .unwrap()
+            .ends_with('Z')
+    );
+
+    let mocked = &entries[1];
+    assert_eq!(mocked["request"]["method"], "GET");
+    assert_eq!(mocked["response"]["status"], 204);
+    assert_eq!(mocked["response"]["content"]["size"], 0);
+    assert!(mocked["request"].get("postData").is_none());
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_har_records_unreachable_via() {
+    let test_name = "test_end_to_end_har_records_unreachable_via";
+    // 绑定后立即释放，得到一个无人监听的端口
+    let Some(closed_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let closed_addr = closed_listener.local_addr().unwrap();
+    drop(closed_listener);
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let rule = format!(
+        "http://down.example.com/* -> http://127.0.0.1:9 [via=http://user:secret@{}]",
+        closed_addr
+    );
+    proxy_manager.add_proxy_rule(rule.parse().unwrap()).await;
+
+    let har = HarRecorder::new();
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .har(Some(har.clone()))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let response = timeout(
+        Duration::from_secs(5),
+        client.get("http://down.example.com/ping").send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 502);
+
+    // 转发失败的请求同样写入记录
+    timeout(Duration::from_secs(5), async {
+        while har.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let log = har.to_har();
+    let entry = &log["log"]["entries"][0];
+    assert_eq!(entry["request"]["url"], "http://down.example.com/ping");
+    assert_eq!(entry["response"]["status"], 502);
+    // 记录中不含上游代理的凭据
+    assert_eq!(entry["_rule"], "http://down.example.com/*");
+    assert!(!log.to_string().contains("secret"));
+
+    proxy_handle.abort();
+}
+
+async fn start_recording_proxy(
+    recording: RecordReplay,
+    test_name: &str,
+) -> Option<(std::net::SocketAddr, tokio::task::JoinHandle<()>)> {
+    let proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .recording(Some(recording))
+        .build()
+        .unwrap();
+    let proxy_listener = bind_or_skip("127.0.0.1:0", test_name).await?;
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    Some((proxy_addr, proxy_handle))
+}
+
+#[tokio::test]
+async fn test_end_to_end_record_replay() {
+    let test_name = "test_end_to_end_record_replay";
+    let Some(backend_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_addr = backend_listener.local_addr().unwrap();
+    spawn_echo_backend(backend_listener).await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let matching = RecordMatch::default().ignore_query("_").unwrap();
+
+    // 录制：照常转发并保存上游响应
+    let recording = RecordReplay::new(dir.path(), RecordMode::Record).matching(matching.clone());
+    let Some((proxy_addr, proxy_handle)) = start_recording_proxy(recording, test_name).await else {
+        return;
+    };
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .post(format!("http://{}/echo?b=2&a=1&_=123", backend_addr))
+            .body("hello")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let recorded = response.text().await.unwrap();
+    assert!(recorded.starts_with("POST /echo?b=2&a=1&_=123"));
+    proxy_handle.abort();
+
+    // 响应体传输结束后才写入文件
+    let files = timeout(Duration::from_secs(5), async {
+        loop {
+            let files: Vec<_> = fs_err::read_dir(dir.path())
+                .map(|entries| entries.filter_map(Result::ok).collect())
+                .unwrap_or_default();
+            if !files.is_empty() {
+                break files;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(files.len(), 1);
+    let record: serde_json::Value =
+        serde_json::from_slice(&fs_err::read(files[0].path()).unwrap()).unwrap();
+    assert_eq!(record["request"]["method"], "POST");
+    assert_eq!(record["response"]["status"], 200);
+    assert_eq!(record["response"]["text"], recorded);
+
+    // 严格回放：命中的请求返回录制的响应（查询参数顺序与忽略的参数不影响匹配），未录制的返回 504
+    let replay =
+        RecordReplay::new(dir.path(), RecordMode::Replay { strict: true }).matching(matching);
+    let Some((proxy_addr, proxy_handle)) = start_recording_proxy(replay, test_name).await else {
+        return;
+    };
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://{}", proxy_addr)).unwrap())
+        .build()
+        .unwrap();
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .post(format!("http://{}/echo?a=1&b=2&_=456", backend_addr))
+            .body("hello")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), recorded);
+
+    let response = timeout(
+        Duration::from_secs(5),
+        client
+            .post(format!("http://{}/echo?a=1&b=2", backend_addr))
+            .body("other")
+            .send(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.status(), 504);
+    assert!(
+        response
+            .text()
+            .await
+            .unwrap()
+            .contains("no recording for POST")
+    );
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_pac() {
+    let test_name = "test_end_to_end_pac";
+    let proxy_manager = Arc::new(RwLock::new(
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap(),
+    ));
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(proxy_manager.clone())
+        .pac(Some(Pac::new("127.0.0.1:7898")))
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let proxy_addr = proxy_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // 直接访问代理端口取脚本，PROXY 指向请求使用的地址
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+    let fetch = || async {
+        let response = timeout(
+            Duration::from_secs(5),
+            client
+                .get(format!("http://{}/proxy.pac", proxy_addr))
+                .send(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/x-ns-proxy-autoconfig"
+        );
+        response.text().await.unwrap()
+    };
+    let script = fetch().await;
+    assert!(!script.contains("PROXY"));
+
+    // 规则变化后脚本随之更新
+    proxy_manager
+        .write()
+        .await
+        .add_proxy_rule(
+            "https://api.example.com -> http://localhost:8080"
+                .parse()
+                .unwrap(),
+        )
+        .await;
+    let script = fetch().await;
+    assert!(script.contains(r#"host == "api.example.com""#));
+    assert!(script.contains(&format!("PROXY {}", proxy_addr)));
+
+    proxy_handle.abort();
+}
+
+#[tokio::test]
+async fn test_end_to_end_socks5() {
+    let test_name = "test_end_to_end_socks5";
+    let Some(backend) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let backend_port = backend.local_addr().unwrap().port();
+    spawn_text_backend(backend, "from backend").await;
+
+    let mut proxy_manager =
+        ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+            .unwrap();
+    let rule: ProxyRule = format!("http://api.example.com -> http://127.0.0.1:{backend_port}")
+        .parse()
+        .unwrap();
+    proxy_manager.add_proxy_rule(rule).await;
+
+    // with_ca(true) 时 SOCKS5 连接与 CONNECT 隧道一样被拦截，按规则改写
+    let handler = ProxyHandlerBuilder::default()
+        .proxy_manager(Arc::new(RwLock::new(proxy_manager)))
+        .with_ca(true)
+        .build()
+        .unwrap();
+    let Some(proxy_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let Some(socks5_listener) = bind_or_skip("127.0.0.1:0", test_name).await else {
+        return;
+    };
+    let socks5_addr = socks5_listener.local_addr().unwrap();
+    let proxy = Proxy::builder()
+        .with_listener(proxy_listener)
+        .with_ca(NoCa)
+        .with_rustls_connector(rustls::crypto::aws_lc_rs::default_provider())
+        .with_http_handler(handler)
+        .with_socks5_listener(socks5_listener)
+        .build()
+        .unwrap();
+    let proxy_handle = tokio::spawn(async move {
+        proxy.start().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // 域名交由代理解析（ATYP = 0x03）
+    let socks5_request = |command: u8| {
+        let host = b"api.example.com";
+        let mut request = vec![0x05, command, 0x00, 0x03, host.len() as u8];
+        request.extend_from_slice(host);
+        request.extend_from_slice(&80u16.to_be_bytes());
+        request
+    };
+    let handshake = |command: u8| async move {
+        let mut stream = TcpStream::connect(socks5_addr).await.unwrap();
+        stream.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+        let mut method = [0u8; 2];
+        stream.read_exact(&mut method).await.unwrap();
+        assert_eq!(method, [0x05, 0x00]);
+        stream.write_all(&socks5_request(command)).await.unwrap();
+        let mut reply = [0u8; 10];
+        timeout(Duration::from_secs(5), stream.read_exact(&mut reply))
+            .await
+            .unwrap()
+            .unwrap();
+        (stream, reply[1])
+    };
+
+    let (mut stream, reply) = handshake(0x01).await;
+    assert_eq!(reply, 0x00);
+    stream
+        .write_all(b"GET /users HTTP/1.1\r\nHost: api.example.com\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = Vec::new();
+    timeout(Duration::from_secs(5), stream.read_to_end(&mut response))
+        .await
+        .unwrap()
+        .unwrap();
+    let response = String::from_utf8_lossy(&response);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.ends_with("from backend"), "{response}");
+
+    // 只支持 CONNECT，BIND 返回 command not supported
+    let (_, reply) = handshake(0x02).await;
+    assert_eq!(reply, 0x07);
+
+    proxy_handle.abort();
+}
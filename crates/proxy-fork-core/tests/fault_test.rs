@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod fault_test {
+    use std::time::Duration;
+
+    use http::StatusCode;
+    use proxy_fork_core::{Fault, FaultInjection, FaultRng};
+
+    fn faults(fault: &FaultInjection, rng: &FaultRng, n: usize) -> Vec<Option<Fault>> {
+        (0..n).map(|_| fault.sample(rng).1).collect()
+    }
+
+    #[test]
+    fn test_empty_by_default() {
+        let fault = FaultInjection::default();
+        assert!(fault.is_empty());
+        assert!(fault.validate().is_ok());
+        assert_eq!(fault.sample(&FaultRng::seeded(1)), (None, None));
+    }
+
+    #[test]
+    fn test_seeded_sampling_is_reproducible() {
+        let fault = FaultInjection {
+            latency_ms: Some((500, 2000)),
+            error_rate: 0.3,
+            abort_rate: 0.3,
+            reset_rate: 0.1,
+            ..Default::default()
+        };
+        let sample = |seed| {
+            let rng = FaultRng::seeded(seed);
+            (0..50).map(|_| fault.sample(&rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(sample(42), sample(42));
+        assert_ne!(sample(42), sample(43));
+
+        for (latency, _) in sample(7) {
+            let latency = latency.unwrap();
+            assert!(latency >= Duration::from_millis(500));
+            assert!(latency <= Duration::from_millis(2000));
+        }
+    }
+
+    #[test]
+    fn test_rates() {
+        let rng = FaultRng::seeded(0);
+        let always_error = FaultInjection {
+            error_rate: 1.0,
+            error_status: 502,
+            ..Default::default()
+        };
+        assert!(
+            faults(&always_error, &rng, 20)
+                .iter()
+                .all(|f| *f == Some(Fault::Error(StatusCode::BAD_GATEWAY)))
+        );
+
+        // 同时配置多种故障时断开连接优先
+        let reset_first = FaultInjection {
+            error_rate: 1.0,
+            abort_rate: 1.0,
+            reset_rate: 1.0,
+            ..Default::default()
+        };
+        assert!(
+            faults(&reset_first, &rng, 20)
+                .iter()
+                .all(|f| *f == Some(Fault::Reset))
+        );
+
+        let latency_only = FaultInjection {
+            latency_ms: Some((10, 10)),
+            ..Default::default()
+        };
+        assert!(!latency_only.is_empty());
+        assert_eq!(
+            latency_only.sample(&rng),
+            (Some(Duration::from_millis(10)), None)
+        );
+
+        // 10% 的错误率在大量样本上大致成立
+        let tenth = FaultInjection {
+            error_rate: 0.1,
+            ..Default::default()
+        };
+        let errors = faults(&tenth, &rng, 10_000)
+            .iter()
+            .filter(|f| f.is_some())
+            .count();
+        assert!((800..1200).contains(&errors), "errors: {}", errors);
+    }
+
+    #[test]
+    fn test_validate() {
+        let invalid = [
+            FaultInjection {
+                latency_ms: Some((2000, 500)),
+                ..Default::default()
+            },
+            FaultInjection {
+                error_rate: 1.5,
+                ..Default::default()
+            },
+            FaultInjection {
+                reset_rate: -0.1,
+                ..Default::default()
+            },
+            FaultInjection {
+                error_rate: 0.5,
+                error_status: 1000,
+                ..Default::default()
+            },
+        ];
+        for fault in invalid {
+            assert!(fault.validate().is_err(), "{:?}", fault);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+#[cfg(test)]
+mod har_test {
+    use proxy_fork_core::HarRecorder;
+
+    #[test]
+    fn test_har_recorder_write_to() {
+        let har = HarRecorder::new();
+        assert!(har.is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.har");
+        har.write_to(&path).unwrap();
+
+        let log: serde_json::Value = serde_json::from_slice(&fs_err::read(&path).unwrap()).unwrap();
+        assert_eq!(log["log"]["version"], "1.2");
+        assert_eq!(log["log"]["creator"]["name"], "proxy-fork");
+        assert_eq!(log["log"]["entries"], serde_json::json!([]));
+
+        // 克隆的记录器共享记录
+        let cloned = har.clone();
+        cloned.clear();
+        assert_eq!(har.len(), 0);
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod header_rewrite_test {
+    use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+    use proxy_fork_core::{HeaderPreset, HeaderRewrite};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn values<'a>(headers: &'a HeaderMap, name: &str) -> Vec<&'a str> {
+        headers
+            .get_all(name)
+            .iter()
+            .map(|v| v.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_header_rewrite_apply() {
+        let rewrite = HeaderRewrite::default()
+            .remove("If-None-Match")
+            .unwrap()
+            .remove("X-Debug-*")
+            .unwrap()
+            .replace("Cookie", "s/session=[^;]+/session=dev/")
+            .unwrap()
+            .set("Authorization", "Bearer dev")
+            .append("X-Feature", "beta");
+
+        let mut h = headers(&[
+            ("if-none-match", "\"abc\""),
+            ("x-debug-trace", "1"),
+            ("x-debug-user", "2"),
+            ("cookie", "theme=dark; session=abc123"),
+            ("cookie", "other=1"),
+            ("authorization", "Bearer prod"),
+            ("authorization", "Basic x"),
+            ("x-feature", "alpha"),
+        ]);
+        rewrite.apply(&mut h);
+
+        assert!(h.get("if-none-match").is_none());
+        assert!(h.get("x-debug-trace").is_none() && h.get("x-debug-user").is_none());
+        // 不匹配的值保持原样
+        assert_eq!(values(&h, "cookie"), ["theme=dark; session=dev", "other=1"]);
+        assert_eq!(values(&h, "authorization"), ["Bearer dev"]);
+        assert_eq!(values(&h, "x-feature"), ["alpha", "beta"]);
+    }
+
+    #[test]
+    fn test_header_rewrite_order_and_validation() {
+        // 先删除再设置：删除不影响之后设置的头
+        let rewrite = HeaderRewrite::default()
+            .remove("re:^x-")
+            .unwrap()
+            .set("X-Forwarded-User", "dev");
+        let mut h = headers(&[("x-forwarded-user", "prod"), ("x-other", "1")]);
+        rewrite.apply(&mut h);
+        assert_eq!(values(&h, "x-forwarded-user"), ["dev"]);
+        assert!(h.get("x-other").is_none());
+
+        assert!(HeaderRewrite::default().is_empty());
+        assert!(rewrite.validate().is_ok());
+
+        let invalid = HeaderRewrite::default().set("Bad Name", "1");
+        assert!(invalid.validate().is_err());
+        let invalid = HeaderRewrite::default().append("X-Ok", "line\nbreak");
+        assert!(invalid.validate().is_err());
+        // 不合法的操作在应用时跳过
+        let mut h = headers(&[("x-ok", "1")]);
+        invalid.apply(&mut h);
+        assert_eq!(values(&h, "x-ok"), ["1"]);
+
+        assert!(
+            HeaderRewrite::default()
+                .replace("Cookie", "not-sed")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_header_preset_parse() {
+        assert_eq!(
+            "cors".parse::<HeaderPreset>().unwrap(),
+            HeaderPreset::Cors(None)
+        );
+        assert_eq!(
+            "CORS = http://localhost:3000"
+                .parse::<HeaderPreset>()
+                .unwrap(),
+            HeaderPreset::Cors(Some("http://localhost:3000".into()))
+        );
+        assert_eq!(
+            " strip-csp ".parse::<HeaderPreset>().unwrap(),
+            HeaderPreset::StripCsp
+        );
+        assert_eq!(
+            "no-cache".parse::<HeaderPreset>().unwrap(),
+            HeaderPreset::NoCache
+        );
+        assert!("cors=".parse::<HeaderPreset>().is_err());
+        assert!("cache=off".parse::<HeaderPreset>().is_err());
+
+        for text in [
+            "cors",
+            "cors=https://app.example.com",
+            "strip-csp",
+            "no-cache",
+        ] {
+            assert_eq!(text.parse::<HeaderPreset>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_header_preset_cors() {
+        let origin = HeaderValue::from_static("http://localhost:3000");
+
+        // 回显请求的 Origin，并允许携带凭据
+        let mut h = headers(&[
+            ("access-control-allow-origin", "https://app.example.com"),
+            ("x-request-id", "1"),
+        ]);
+        HeaderPreset::Cors(None).apply(&mut h, Some(&origin));
+        assert_eq!(
+            values(&h, "access-control-allow-origin"),
+            ["http://localhost:3000"]
+        );
+        assert_eq!(values(&h, "access-control-allow-credentials"), ["true"]);
+        assert_eq!(
+            values(&h, "access-control-expose-headers"),
+            ["x-request-id"]
+        );
+        assert_eq!(values(&h, "vary"), ["Origin"]);
+
+        // 指定来源时不随请求变化
+        let mut h = HeaderMap::new();
+        HeaderPreset::Cors(Some("https://app.example.com".into())).apply(&mut h, Some(&origin));
+        assert_eq!(
+            values(&h, "access-control-allow-origin"),
+            ["https://app.example.com"]
+        );
+        assert!(h.get("vary").is_none());
+
+        // 请求不带 Origin 时允许任意来源
+        let mut h = headers(&[("access-control-allow-credentials", "true")]);
+        HeaderPreset::Cors(None).apply(&mut h, None);
+        assert_eq!(values(&h, "access-control-allow-origin"), ["*"]);
+        assert!(h.get("access-control-allow-credentials").is_none());
+    }
+
+    #[test]
+    fn test_header_preset_strip_csp_and_no_cache() {
+        let mut h = headers(&[
+            ("content-security-policy", "default-src 'self'"),
+            ("content-security-policy-report-only", "default-src 'self'"),
+            ("strict-transport-security", "max-age=31536000"),
+            ("etag", "\"v1\""),
+            ("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            ("cache-control", "public, max-age=3600"),
+            ("content-type", "text/html"),
+        ]);
+        HeaderPreset::StripCsp.apply(&mut h, None);
+        HeaderPreset::NoCache.apply(&mut h, None);
+
+        assert!(h.get("content-security-policy").is_none());
+        assert!(h.get("content-security-policy-report-only").is_none());
+        assert!(h.get("strict-transport-security").is_none());
+        assert!(h.get("etag").is_none() && h.get("last-modified").is_none());
+        assert_eq!(
+            values(&h, "cache-control"),
+            ["no-store, no-cache, must-revalidate, max-age=0"]
+        );
+        assert_eq!(values(&h, "pragma"), ["no-cache"]);
+        assert_eq!(values(&h, "content-type"), ["text/html"]);
+    }
+
+    #[test]
+    fn test_cors_preflight() {
+        let req = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("http://api.example.com/users")
+            .header("origin", "http://localhost:3000")
+            .header("access-control-request-method", "PUT")
+            .header(
+                "access-control-request-headers",
+                "authorization, content-type",
+            )
+            .header("access-control-request-private-network", "true")
+            .body(())
+            .unwrap();
+        assert!(HeaderPreset::is_preflight(&req));
+
+        let res = HeaderPreset::preflight_response(&req);
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let h = res.headers();
+        assert_eq!(values(h, "access-control-allow-methods"), ["PUT"]);
+        assert_eq!(
+            values(h, "access-control-allow-headers"),
+            ["authorization, content-type"]
+        );
+        assert_eq!(values(h, "access-control-allow-private-network"), ["true"]);
+
+        let plain = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("http://api.example.com/users")
+            .body(())
+            .unwrap();
+        assert!(!HeaderPreset::is_preflight(&plain));
+    }
+}
@@ -311,4 +311,281 @@ mod address_pattern_test {
             "https://api.example.com/console/api/v2/open/logo"
         );
     }
+
+    #[test]
+    fn test_address_from_str() {
+        let address: Address = "http://localhost:8080/local".parse().unwrap();
+        assert_eq!(
+            address,
+            create_address(Protocol::Http, "localhost", Some(8080), Some("/local"))
+        );
+
+        // 根路径视为未指定路径
+        let address: Address = "https://backend.internal/".parse().unwrap();
+        assert_eq!(
+            address,
+            create_address(Protocol::Https, "backend.internal", None, None)
+        );
+
+        let address: Address = "http://[::1]:9000".parse().unwrap();
+        assert_eq!(address.host, "::1");
+        assert_eq!(address.port, Some(9000));
+        assert_eq!(address.to_string(), "http://[::1]:9000/");
+        let uri = address
+            .to_uri_with_rewrite(&"https://example.com/a".parse().unwrap(), None)
+            .unwrap();
+        assert_eq!(uri.to_string(), "http://[::1]:9000/a");
+
+        // 不带方括号的 IPv6 地址没有端口
+        let address: Address = "http://::1".parse().unwrap();
+        assert_eq!(address.host, "::1");
+        assert_eq!(address.port, None);
+        assert_eq!(address.to_string(), "http://[::1]/");
+
+        assert!("localhost:8080".parse::<Address>().is_err());
+        assert!("ws://localhost:8080".parse::<Address>().is_err());
+        assert!("http://*.example.com".parse::<Address>().is_err());
+    }
+
+    #[test]
+    fn test_address_pattern_from_str() {
+        let pattern: AddressPattern = "https://*.example.com:8443/api/*".parse().unwrap();
+        assert_eq!(pattern.protocol, Protocol::Https);
+        assert_eq!(pattern.port, Some(8443));
+        assert_eq!(pattern.to_string(), "https://*.example.com:8443/api/*");
+
+        let ipv6: AddressPattern = "http://[::1]:8080/api".parse().unwrap();
+        assert_eq!(ipv6.port, Some(8080));
+        assert_eq!(ipv6.to_string(), "http://[::1]:8080/api");
+
+        let addr = create_address(
+            Protocol::Https,
+            "app.example.com",
+            Some(8443),
+            Some("/api/users"),
+        );
+        assert!(pattern.matches(&addr));
+
+        // 省略路径表示不约束路径
+        let pattern: AddressPattern = "http://re:^dev\\d+\\.example\\.com$".parse().unwrap();
+        assert!(pattern.pattern_type.path.is_none());
+        let addr = create_address(Protocol::Http, "dev12.example.com", None, Some("/any"));
+        assert!(pattern.matches(&addr));
+
+        assert!("*.example.com/api/*".parse::<AddressPattern>().is_err());
+        assert!("https:///api".parse::<AddressPattern>().is_err());
+        assert!("https://re:(/api".parse::<AddressPattern>().is_err());
+    }
+
+    #[test]
+    fn test_strip_prefix_transform() {
+        let target = Address {
+            protocol: Protocol::Http,
+            host: "localhost".to_string(),
+            port: Some(8080),
+            path: None,
+            path_transform_mode: PathTransformMode::StripPrefix,
+        };
+
+        let uri: Uri = "https://example.com/api/users?page=2".parse().unwrap();
+        let new_uri = target.to_uri_with_rewrite(&uri, Some("/api/")).unwrap();
+        assert_eq!(new_uri.to_string(), "http://localhost:8080/users?page=2");
+
+        // 去除后为空时回到根路径
+        let uri: Uri = "https://example.com/api?x=1".parse().unwrap();
+        let new_uri = target.to_uri_with_rewrite(&uri, Some("/api")).unwrap();
+        assert_eq!(new_uri.path_and_query().unwrap().as_str(), "/?x=1");
+
+        // 没有匹配前缀时保留原始路径
+        let uri: Uri = "https://example.com/other".parse().unwrap();
+        let new_uri = target.to_uri_with_rewrite(&uri, None).unwrap();
+        assert_eq!(new_uri.path(), "/other");
+    }
+
+    #[test]
+    fn test_regex_transform() {
+        let target = Address {
+            protocol: Protocol::Http,
+            host: "localhost".to_string(),
+            port: Some(8080),
+            path: None,
+            path_transform_mode: "s#^/v1/(.*)#/api/v2/$1#".parse().unwrap(),
+        };
+
+        let uri: Uri = "https://example.com/v1/users?page=2".parse().unwrap();
+        let new_uri = target.to_uri_with_rewrite(&uri, None).unwrap();
+        assert_eq!(
+            new_uri.to_string(),
+            "http://localhost:8080/api/v2/users?page=2"
+        );
+
+        // 不匹配时保留原始路径
+        let uri: Uri = "https://example.com/v2/users".parse().unwrap();
+        let new_uri = target.to_uri_with_rewrite(&uri, None).unwrap();
+        assert_eq!(new_uri.path(), "/v2/users");
+
+        // 命名捕获组与查询参数改写
+        let target = Address {
+            path_transform_mode: "s|^/items/(?P<id>\\d+)\\?.*$|/item?id=${id}|"
+                .parse()
+                .unwrap(),
+            ..target
+        };
+        let uri: Uri = "https://example.com/items/42?ref=home".parse().unwrap();
+        let new_uri = target.to_uri_with_rewrite(&uri, None).unwrap();
+        assert_eq!(new_uri.path_and_query().unwrap().as_str(), "/item?id=42");
+    }
+
+    #[test]
+    fn test_path_transform_mode_from_str() {
+        assert_eq!(
+            "strip_prefix".parse::<PathTransformMode>().unwrap(),
+            PathTransformMode::StripPrefix
+        );
+        assert_eq!(
+            "strip-prefix".parse::<PathTransformMode>().unwrap(),
+            PathTransformMode::StripPrefix
+        );
+
+        let mode: PathTransformMode = "s#^/v1/(.*)#/api/v2/$1#".parse().unwrap();
+        let PathTransformMode::Regex(regex) = &mode else {
+            panic!("expected regex mode");
+        };
+        assert_eq!(regex.pattern(), "^/v1/(.*)");
+        assert_eq!(regex.replacement(), "/api/v2/$1");
+        assert_eq!(mode.to_string(), "s#^/v1/(.*)#/api/v2/$1#");
+
+        // 分隔符出现在正则中时选择其他分隔符输出
+        let mode: PathTransformMode = "s|^/a#b|/c|".parse().unwrap();
+        assert_eq!(mode.to_string(), "s|^/a#b|/c|");
+        assert_eq!(mode.to_string().parse::<PathTransformMode>().unwrap(), mode);
+
+        assert!(
+            "s#^/v1/(.*)#/api/v2/$1"
+                .parse::<PathTransformMode>()
+                .is_err()
+        );
+        assert!("s##/x#".parse::<PathTransformMode>().is_err());
+        assert!("s#(#/x#".parse::<PathTransformMode>().is_err());
+        // 空白或字母数字不能作分隔符，与规则 DSL 的判断保持一致
+        assert!("s x y ".parse::<PathTransformMode>().is_err());
+        assert!("sxaxbx".parse::<PathTransformMode>().is_err());
+    }
+
+    #[test]
+    fn test_unix_socket_address() {
+        let address: Address = "unix:///run/app.sock".parse().unwrap();
+        assert_eq!(address.protocol, Protocol::Unix);
+        assert_eq!(
+            address.unix_socket_path(),
+            Some(std::path::Path::new("/run/app.sock"))
+        );
+        assert_eq!(address.path, None);
+        assert_eq!(address.to_string(), "unix:///run/app.sock");
+
+        // 与 nginx 一样用 `:` 分隔套接字路径与路径前缀
+        let address: Address = "unix:///run/app.sock:/api".parse().unwrap();
+        assert_eq!(address.host, "/run/app.sock");
+        assert_eq!(address.path.as_deref(), Some("/api"));
+        assert_eq!(address.to_string(), "unix:///run/app.sock:/api");
+
+        assert!("unix://".parse::<Address>().is_err());
+        assert!("unix:///run/app.sock".parse::<AddressPattern>().is_err());
+        assert!("file:///srv/dist".parse::<AddressPattern>().is_err());
+        assert_eq!(
+            create_address(Protocol::Http, "localhost", None, None).unix_socket_path(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unix_socket_rewrite_keeps_original_authority() {
+        let original: Uri = "https://app.example.com/api/users?id=1".parse().unwrap();
+
+        let address: Address = "unix:///run/app.sock".parse().unwrap();
+        let uri = address.to_uri_with_rewrite(&original, None).unwrap();
+        assert_eq!(uri.to_string(), "http://app.example.com/api/users?id=1");
+
+        let mut address: Address = "unix:///run/app.sock:/local".parse().unwrap();
+        address.path_transform_mode = PathTransformMode::Prepend;
+        let uri = address.to_uri_with_rewrite(&original, None).unwrap();
+        assert_eq!(
+            uri.to_string(),
+            "http://app.example.com/local/api/users?id=1"
+        );
+    }
+
+    #[test]
+    fn test_restore_uri_inverts_rewrite() {
+        let original: Uri = "https://app.example.com/api/v1/users".parse().unwrap();
+        let restore = |address: &Address, uri: &str, prefix: Option<&str>| {
+            address
+                .restore_uri(&uri.parse().unwrap(), &original, prefix)
+                .map(|uri| uri.to_string())
+        };
+
+        // 保留路径：只还原协议与主机
+        let address: Address = "http://localhost:8080".parse().unwrap();
+        assert_eq!(
+            restore(&address, "http://localhost:8080/login?next=1", None).as_deref(),
+            Some("https://app.example.com/login?next=1")
+        );
+        // 协议、主机或端口与目标不一致时不还原
+        assert_eq!(restore(&address, "http://localhost:9090/login", None), None);
+        assert_eq!(
+            restore(&address, "https://localhost:8080/login", None),
+            None
+        );
+        assert_eq!(restore(&address, "http://example.org/login", None), None);
+
+        // 前缀拼接：去掉目标前缀，不在前缀下时不还原
+        let mut address: Address = "http://localhost:8080/local".parse().unwrap();
+        address.path_transform_mode = PathTransformMode::Prepend;
+        let forwarded = address.to_uri_with_rewrite(&original, None).unwrap();
+        assert_eq!(
+            restore(&address, &forwarded.to_string(), None).as_deref(),
+            Some(original.to_string().as_str())
+        );
+        assert_eq!(
+            restore(&address, "http://localhost:8080/local", None).as_deref(),
+            Some("https://app.example.com/")
+        );
+        assert_eq!(
+            restore(&address, "http://localhost:8080/localx", None),
+            None
+        );
+
+        // 前缀替换：新前缀换回匹配的前缀
+        let mut address: Address = "http://localhost:8080/api/v2".parse().unwrap();
+        address.path_transform_mode = PathTransformMode::Replace;
+        let forwarded = address
+            .to_uri_with_rewrite(&original, Some("/api/v1/*"))
+            .unwrap();
+        assert_eq!(forwarded.path(), "/api/v2/users");
+        assert_eq!(
+            restore(
+                &address,
+                "http://localhost:8080/api/v2/login",
+                Some("/api/v1/*")
+            )
+            .as_deref(),
+            Some("https://app.example.com/api/v1/login")
+        );
+        assert_eq!(
+            restore(&address, "http://localhost:8080/other", Some("/api/v1/*")),
+            None
+        );
+
+        // 去除前缀：补回匹配的前缀
+        let mut address: Address = "http://localhost:8080".parse().unwrap();
+        address.path_transform_mode = PathTransformMode::StripPrefix;
+        assert_eq!(
+            restore(&address, "http://localhost:8080/login", Some("/api/*")).as_deref(),
+            Some("https://app.example.com/api/login")
+        );
+
+        // 本地目标没有可还原的地址
+        let address: Address = "unix:///run/app.sock".parse().unwrap();
+        assert_eq!(restore(&address, "http://localhost/login", None), None);
+    }
 }
@@ -0,0 +1,205 @@
+#[cfg(test)]
+mod local_files_test {
+    use fs_err as fs;
+    use http::{Method, Request, StatusCode, header};
+    use hudsucker::Body;
+    use proxy_fork_core::{Address, LocalFiles, Protocol, ProxyRule};
+
+    fn site() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("index.html"), "<h1>home</h1>").unwrap();
+        fs::write(dir.path().join("app.js"), "console.log(1)").unwrap();
+        fs::write(dir.path().join("data.bin"), b"0123456789").unwrap();
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("docs/index.html"), "<h1>docs</h1>").unwrap();
+        dir
+    }
+
+    fn get(path: &str) -> http::request::Builder {
+        Request::builder().method(Method::GET).uri(path)
+    }
+
+    async fn serve(files: &LocalFiles, req: http::request::Builder) -> http::Response<Body> {
+        let req = req.body(Body::empty()).unwrap();
+        let path = req.uri().path_and_query().unwrap().as_str().to_string();
+        files.serve(&req, &path).await
+    }
+
+    fn header(res: &http::Response<Body>, name: header::HeaderName) -> &str {
+        res.headers().get(name).unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    fn test_parse_file_target() {
+        let target: Address = "file:///home/me/build/dist".parse().unwrap();
+        assert_eq!(target.protocol, Protocol::File);
+        assert_eq!(
+            target.local_root(),
+            Some(std::path::Path::new("/home/me/build/dist"))
+        );
+        assert_eq!(target.to_string(), "file:///home/me/build/dist");
+        assert!("file://".parse::<Address>().is_err());
+
+        let target: Address = "file:///C:/dist".parse().unwrap();
+        assert_eq!(target.host, "C:/dist");
+
+        let rule: ProxyRule = "https://app.example.com -> file:///srv/dist [spa]"
+            .parse()
+            .unwrap();
+        assert!(rule.spa_fallback);
+        assert_eq!(
+            rule.to_string(),
+            "https://app.example.com -> file:///srv/dist [spa]"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serve_files_and_directories() {
+        let dir = site();
+        let files = LocalFiles::new(dir.path());
+
+        let res = serve(&files, get("/app.js")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            header(&res, header::CONTENT_TYPE),
+            "text/javascript; charset=utf-8"
+        );
+        assert_eq!(header(&res, header::CONTENT_LENGTH), "14");
+        assert_eq!(header(&res, header::ACCEPT_RANGES), "bytes");
+        assert!(res.headers().contains_key(header::ETAG));
+        assert!(res.headers().contains_key(header::LAST_MODIFIED));
+
+        let res = serve(&files, get("/data.bin")).await;
+        assert_eq!(
+            header(&res, header::CONTENT_TYPE),
+            "application/octet-stream"
+        );
+
+        // 目录：补全结尾的 `/`，再返回 index.html
+        let res = serve(&files, get("/docs?v=1")).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(header(&res, header::LOCATION), "/docs/?v=1");
+        let res = serve(&files, get("/docs/")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            header(&res, header::CONTENT_TYPE),
+            "text/html; charset=utf-8"
+        );
+
+        // 路径经过转换时，按原始请求路径重定向
+        let req = get("/static/docs?v=1").body(Body::empty()).unwrap();
+        let res = files.serve(&req, "/docs?v=1").await;
+        assert_eq!(header(&res, header::LOCATION), "/static/docs/?v=1");
+        let req = get("/static/docs/").body(Body::empty()).unwrap();
+        assert_eq!(files.serve(&req, "/docs").await.status(), StatusCode::OK);
+
+        assert_eq!(
+            serve(&files, get("/missing")).await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            serve(&files, get("/../etc/passwd")).await.status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            serve(&files, get("/%2e%2e/etc/passwd")).await.status(),
+            StatusCode::FORBIDDEN
+        );
+
+        let res = serve(&files, get("/app.js").method(Method::POST)).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(header(&res, header::ALLOW), "GET, HEAD");
+    }
+
+    #[tokio::test]
+    async fn test_spa_fallback() {
+        let dir = site();
+        let files = LocalFiles::new(dir.path()).with_spa_fallback(true);
+
+        let res = serve(&files, get("/dashboard/settings")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(header(&res, header::CONTENT_LENGTH), "13");
+
+        // 带扩展名的资源缺失时仍返回 404
+        assert_eq!(
+            serve(&files, get("/missing.js")).await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            serve(&LocalFiles::new(dir.path()), get("/dashboard"))
+                .await
+                .status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conditional_requests() {
+        let dir = site();
+        let files = LocalFiles::new(dir.path());
+
+        let res = serve(&files, get("/app.js")).await;
+        let etag = header(&res, header::ETAG).to_string();
+        let last_modified = header(&res, header::LAST_MODIFIED).to_string();
+
+        let res = serve(&files, get("/app.js").header(header::IF_NONE_MATCH, &etag)).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        let res = serve(
+            &files,
+            get("/app.js").header(header::IF_NONE_MATCH, "\"other\""),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = serve(
+            &files,
+            get("/app.js").header(header::IF_MODIFIED_SINCE, &last_modified),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        let res = serve(
+            &files,
+            get("/app.js").header(header::IF_MODIFIED_SINCE, "Thu, 01 Jan 1970 00:00:00 GMT"),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_range_requests() {
+        let dir = site();
+        let files = LocalFiles::new(dir.path());
+
+        for (range, content_range, len) in [
+            ("bytes=2-5", "bytes 2-5/10", "4"),
+            ("bytes=7-", "bytes 7-9/10", "3"),
+            ("bytes=-3", "bytes 7-9/10", "3"),
+            ("bytes=8-100", "bytes 8-9/10", "2"),
+        ] {
+            let res = serve(&files, get("/data.bin").header(header::RANGE, range)).await;
+            assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT, "{range}");
+            assert_eq!(header(&res, header::CONTENT_RANGE), content_range);
+            assert_eq!(header(&res, header::CONTENT_LENGTH), len);
+        }
+
+        let res = serve(&files, get("/data.bin").header(header::RANGE, "bytes=10-")).await;
+        assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(header(&res, header::CONTENT_RANGE), "bytes */10");
+
+        // 多段或无法识别的 Range 按完整响应处理
+        for range in ["bytes=0-1,4-5", "items=0-1", "bytes=5-2"] {
+            let res = serve(&files, get("/data.bin").header(header::RANGE, range)).await;
+            assert_eq!(res.status(), StatusCode::OK, "{range}");
+        }
+
+        // If-Range 不匹配时忽略 Range
+        let res = serve(
+            &files,
+            get("/data.bin")
+                .header(header::RANGE, "bytes=0-1")
+                .header(header::IF_RANGE, "\"stale\""),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
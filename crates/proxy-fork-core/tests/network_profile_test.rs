@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod network_profile_test {
+    use std::time::Duration;
+
+    use proxy_fork_core::{NETWORK_PRESETS, NetworkProfile};
+
+    #[test]
+    fn test_presets() {
+        let profile: NetworkProfile = "3G".parse().unwrap();
+        assert_eq!(profile, NetworkProfile::new(1600, 750, 563));
+        assert_eq!(profile.to_string(), "3g");
+
+        for (name, profile) in NETWORK_PRESETS {
+            assert_eq!(NetworkProfile::preset(name), Some(profile));
+            assert_eq!(profile.to_string(), name);
+        }
+        assert_eq!(NetworkProfile::preset("5g"), None);
+    }
+
+    #[test]
+    fn test_custom_profile() {
+        let profile: NetworkProfile = "1000 / 500 / 150".parse().unwrap();
+        assert_eq!(profile, NetworkProfile::new(1000, 500, 150));
+        assert_eq!(profile.to_string(), "1000/500/150");
+        assert_eq!(profile.preset_name(), None);
+
+        let profile: NetworkProfile = "256/0".parse().unwrap();
+        assert_eq!(profile, NetworkProfile::new(256, 0, 0));
+
+        for invalid in ["", "5g", "1000", "1000/500/150/1", "1000/fast", "-1/0"] {
+            assert!(invalid.parse::<NetworkProfile>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_throttle() {
+        let throttle = NetworkProfile::new(800, 0, 150).throttle();
+        assert_eq!(throttle.download, Some(100_000));
+        assert_eq!(throttle.upload, None);
+        assert_eq!(throttle.latency, Duration::from_millis(150));
+
+        assert_eq!(
+            NetworkProfile::default().throttle(),
+            hudsucker::Throttle::default()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod pac_test {
+    use proxy_fork_core::{Pac, ProxyManager, ProxyRule};
+
+    async fn manager(rules: &[&str]) -> ProxyManager {
+        let mut manager =
+            ProxyManager::from_config(ProxyManager::builder().cache_size(100).build().unwrap())
+                .unwrap();
+        for rule in rules {
+            manager
+                .add_proxy_rule(rule.parse::<ProxyRule>().unwrap())
+                .await;
+        }
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_pac_script_from_rules() {
+        let mut manager = manager(&[
+            "https://API.example.com/users -> http://localhost:8080",
+            "https://*.example.org/* -> http://localhost:8081",
+            "http://static.* -> http://localhost:8082",
+            r"https://re:^cdn\d+\.example\.net$ -> http://localhost:8083",
+            "https://pay.example.com -> http://localhost:8084 [group=mock]",
+            // 同一主机的多条规则只生成一个条件
+            "https://api.example.com/orders -> http://localhost:8085",
+        ])
+        .await;
+        let script = Pac::new("127.0.0.1:7898").script(&manager, "127.0.0.1:7898");
+
+        assert!(script.starts_with("function FindProxyForURL(url, host) {"));
+        assert_eq!(script.matches(r#"host == "api.example.com""#).count(), 1);
+        assert!(script.contains(r#"dnsDomainIs(host, ".example.org")"#));
+        assert!(script.contains(r#"host.indexOf("static.") == 0"#));
+        assert!(script.contains(r#"new RegExp("^cdn\\d+\\.example\\.net$").test(host)"#));
+        assert!(script.contains(r#"host == "pay.example.com""#));
+        assert!(script.contains(r#"return "PROXY 127.0.0.1:7898";"#));
+        assert!(script.contains(r#"return "DIRECT";"#));
+
+        // 禁用的分组与规则不走代理
+        manager.disable_group("mock").await;
+        manager
+            .set_rule_enabled("https://*.example.org/*", false)
+            .await;
+        let script = Pac::new("127.0.0.1:7898").script(&manager, "127.0.0.1:7898");
+        assert!(!script.contains("pay.example.com"));
+        assert!(!script.contains("example.org"));
+        assert!(script.contains("api.example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_pac_script_with_hosts() {
+        let rules = manager(&["https://api.example.com -> http://localhost:8080"]).await;
+
+        // 指定主机时代替规则中的主机
+        let pac = Pac::new("127.0.0.1:7898")
+            .hosts(["*.corp.example", "intranet"])
+            .unwrap();
+        let script = pac.script(&rules, "192.168.1.10:7898");
+        assert!(!script.contains("api.example.com"));
+        assert!(script.contains(r#"dnsDomainIs(host, ".corp.example")"#));
+        assert!(script.contains(r#"host == "intranet""#));
+        assert!(script.contains(r#"return "PROXY 192.168.1.10:7898";"#));
+
+        assert!(Pac::new("127.0.0.1:7898").hosts(["re:("]).is_err());
+
+        // JavaScript 不支持的正则语法退回为全部走代理
+        let script = Pac::new("127.0.0.1:7898")
+            .hosts([r"re:(?i)^api\.", r"re:^\p{Greek}+$", r"re:^[[:alpha:]]+$"])
+            .unwrap()
+            .script(&rules, "127.0.0.1:7898");
+        assert!(!script.contains("RegExp"));
+        assert!(script.contains("    true\n"));
+        let script = Pac::new("127.0.0.1:7898")
+            .hosts([r"re:^(?:api|www)\.[a-z-]+\.com$"])
+            .unwrap()
+            .script(&rules, "127.0.0.1:7898");
+        assert!(script.contains(r#"new RegExp("^(?:api|www)\\.[a-z-]+\\.com$").test(host)"#));
+
+        // 没有规则时全部直连
+        let script = Pac::new("127.0.0.1:7898").script(&manager(&[]).await, "127.0.0.1:7898");
+        assert!(!script.contains("PROXY"));
+        assert!(script.contains(r#"return "DIRECT";"#));
+    }
+}
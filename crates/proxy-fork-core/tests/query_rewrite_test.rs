@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod query_rewrite_test {
+    use http::Uri;
+    use proxy_fork_core::{ProxyManager, ProxyRule, QueryRewrite};
+
+    #[test]
+    fn test_query_rewrite_apply() {
+        let rewrite = QueryRewrite::default()
+            .remove("utm_*")
+            .unwrap()
+            .rename("q", "query")
+            .set("debug", "1");
+
+        assert_eq!(
+            rewrite.apply("/search?q=rust&utm_source=mail&utm_medium=x&page=2"),
+            "/search?query=rust&page=2&debug=1"
+        );
+        // 已存在的参数被覆盖，多余的同名参数被删除
+        assert_eq!(
+            rewrite.apply("/search?debug=0&a=1&debug=2"),
+            "/search?debug=1&a=1"
+        );
+        // 无查询串时追加
+        assert_eq!(rewrite.apply("/search"), "/search?debug=1");
+    }
+
+    #[test]
+    fn test_query_rewrite_edge_cases() {
+        let rewrite = QueryRewrite::default().remove("re:^_").unwrap();
+        // 全部删除后去掉 '?'
+        assert_eq!(rewrite.apply("/p?_t=1&_x"), "/p");
+        // 无值参数保持原样
+        assert_eq!(rewrite.apply("/p?flag&_t=1"), "/p?flag");
+
+        let rewrite = QueryRewrite::default().set("msg", "a b&c");
+        assert_eq!(rewrite.apply("/p"), "/p?msg=a%20b%26c");
+
+        // 需要编码的参数名按解码后的形式比较，写入时编码
+        let rewrite = QueryRewrite::default()
+            .rename("filter[name]", "new name")
+            .set("a&b", "1");
+        assert_eq!(
+            rewrite.apply("/p?filter%5Bname%5D=x&a%26b=0"),
+            "/p?new%20name=x&a%26b=1"
+        );
+        // 删除与重命名、设置使用同一比较方式，编码与未编码的写法都能命中
+        let rewrite = QueryRewrite::default()
+            .remove("my param")
+            .unwrap()
+            .remove("tag[*")
+            .unwrap();
+        assert_eq!(
+            rewrite.apply("/p?my%20param=1&tag%5Ba%5D=2&tag[b]=3&keep=4"),
+            "/p?keep=4"
+        );
+
+        assert!(QueryRewrite::default().is_empty());
+        assert_eq!(QueryRewrite::default().apply("/p?a=1"), "/p?a=1");
+    }
+
+    #[tokio::test]
+    async fn test_query_rewrite_applied_to_matched_rule() {
+        let mut manager =
+            ProxyManager::from_config(ProxyManager::builder().cache_size(1000).build().unwrap())
+                .expect("Failed to construct ProxyManager from config");
+
+        let rule: ProxyRule = "https://example.com/api/* -> http://localhost:3000/local"
+            .parse()
+            .unwrap();
+        manager
+            .add_proxy_rule(ProxyRule {
+                query: QueryRewrite::default()
+                    .remove("utm_*")
+                    .unwrap()
+                    .set("debug", "1"),
+                ..rule
+            })
+            .await;
+
+        let uri: Uri = "https://example.com/api/users?id=7&utm_source=x"
+            .parse()
+            .unwrap();
+        let result = manager.find_target_with_match_info(&uri).await.unwrap();
+        assert_eq!(
+            result.rewrite_uri(&uri).unwrap().to_string(),
+            "http://localhost:3000/local/api/users?id=7&debug=1"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod recording_test {
+    use http::{HeaderMap, Method, Uri};
+    use proxy_fork_core::{RecordMatch, RecordMode, RecordReplay};
+
+    fn url(matching: &RecordMatch, uri: &str) -> String {
+        matching.url(&uri.parse::<Uri>().unwrap(), &HeaderMap::new())
+    }
+
+    #[test]
+    fn test_record_match_url_normalization() {
+        let matching = RecordMatch::default()
+            .ignore_query("_")
+            .unwrap()
+            .ignore_query("utm_*")
+            .unwrap();
+
+        // 默认端口与主机大小写被规范化，查询参数按字典序排列
+        assert_eq!(
+            url(&matching, "https://API.example.com:443/users?b=2&a=1"),
+            "https://api.example.com/users?a=1&b=2"
+        );
+        assert_eq!(
+            url(&matching, "http://example.com:8080/"),
+            "http://example.com:8080/"
+        );
+        // 忽略的参数不参与匹配
+        assert_eq!(
+            url(
+                &matching,
+                "http://example.com/search?q=x&_=123&utm_source=a"
+            ),
+            "http://example.com/search?q=x"
+        );
+
+        // 请求行没有主机时取 Host 请求头
+        let mut headers = HeaderMap::new();
+        headers.insert("host", "example.com".parse().unwrap());
+        assert_eq!(
+            matching.url(&"/path".parse::<Uri>().unwrap(), &headers),
+            "http://example.com/path"
+        );
+    }
+
+    #[test]
+    fn test_record_match_key() {
+        let uri: Uri = "http://example.com/api?a=1".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("accept", "application/json".parse().unwrap());
+        headers.insert("user-agent", "test".parse().unwrap());
+
+        let matching = RecordMatch::default();
+        let key = matching.key(&Method::POST, &uri, &headers, b"body");
+        assert_eq!(key.len(), 64);
+        assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+
+        // 方法与请求体参与匹配，请求头默认不参与
+        assert_ne!(key, matching.key(&Method::PUT, &uri, &headers, b"body"));
+        assert_ne!(key, matching.key(&Method::POST, &uri, &headers, b"other"));
+        assert_eq!(
+            key,
+            matching.key(&Method::POST, &uri, &HeaderMap::new(), b"body")
+        );
+
+        // 忽略请求体
+        let matching = RecordMatch::default().ignore_body(true);
+        assert_eq!(
+            matching.key(&Method::POST, &uri, &headers, b"body"),
+            matching.key(&Method::POST, &uri, &headers, b"other")
+        );
+
+        // 指定的请求头参与匹配
+        let matching = RecordMatch::default().header("Accept");
+        assert_ne!(
+            matching.key(&Method::GET, &uri, &headers, b""),
+            matching.key(&Method::GET, &uri, &HeaderMap::new(), b"")
+        );
+    }
+
+    #[test]
+    fn test_record_replay_path_for() {
+        let recording = RecordReplay::new("recordings", RecordMode::Replay { strict: true });
+        assert_eq!(recording.mode(), RecordMode::Replay { strict: true });
+        assert_eq!(
+            recording.path_for("abc"),
+            std::path::Path::new("recordings").join("abc.json")
+        );
+    }
+}
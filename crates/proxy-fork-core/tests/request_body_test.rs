@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod request_body_test {
+    use http::Request;
+    use http_body_util::BodyExt;
+    use hudsucker::Body;
+    use proxy_fork_core::{JsonPath, QueryRewrite, RequestBodyRewrite};
+    use serde_json::json;
+
+    fn path(s: &str) -> JsonPath {
+        s.parse().unwrap()
+    }
+
+    async fn body(req: Request<Body>) -> String {
+        let bytes = req.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_json_path_parse() {
+        for valid in [
+            "$",
+            "$.user.name",
+            "$.items[0].price",
+            "$.items[-1]",
+            "$.items[*].tags.*",
+            "$['content-type'][\"x.y\"]",
+        ] {
+            assert_eq!(path(valid).to_string(), valid);
+        }
+        for invalid in ["user", "$..name", "$.", "$[abc]", "$['open", "$.a[0"] {
+            assert!(invalid.parse::<JsonPath>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_json_path_edits() {
+        let mut value = json!({
+            "user": { "name": "alice", "roles": ["dev", "ops"] },
+            "items": [{ "price": 1 }, { "price": 2 }, { "name": "free" }],
+            "content-type": "json"
+        });
+
+        assert_eq!(path("$.items[*].price").replace(&mut value, &json!(0)), 2);
+        assert_eq!(
+            value["items"],
+            json!([{ "price": 0 }, { "price": 0 }, { "name": "free" }])
+        );
+        // 替换不存在的值时跳过
+        assert_eq!(path("$.user.email").replace(&mut value, &json!("x")), 0);
+        assert!(value["user"].get("email").is_none());
+
+        // 设置时逐级创建缺失的对象
+        assert_eq!(path("$.meta.trace.id").set(&mut value, &json!("t1")), 1);
+        assert_eq!(value["meta"], json!({ "trace": { "id": "t1" } }));
+        assert_eq!(path("$.user.roles[-1]").set(&mut value, &json!("admin")), 1);
+        assert_eq!(value["user"]["roles"], json!(["dev", "admin"]));
+        // 数组下标越界或路径含通配符时不创建
+        assert_eq!(path("$.user.roles[5]").set(&mut value, &json!("x")), 0);
+        assert_eq!(path("$.missing[*].x").set(&mut value, &json!("x")), 0);
+
+        assert_eq!(path("$['content-type']").remove(&mut value), 1);
+        assert_eq!(path("$.user.roles[*]").remove(&mut value), 2);
+        assert_eq!(value["user"]["roles"], json!([]));
+        assert_eq!(path("$").remove(&mut value), 0);
+    }
+
+    #[test]
+    fn test_rewrite_json_and_form() {
+        let patch = serde_json::from_value(json!([
+            { "op": "add", "path": "/flags/-", "value": "beta" },
+            { "op": "test", "path": "/user/role", "value": "admin" }
+        ]))
+        .unwrap();
+        let rewrite = RequestBodyRewrite::default()
+            .remove("$.debug")
+            .unwrap()
+            .replace("$.user.name", json!("bob"))
+            .unwrap()
+            .set("$.user.role", json!("admin"))
+            .unwrap()
+            .patch(patch);
+
+        // 删除 -> 替换 -> 设置 -> JSON Patch
+        let mut value = json!({ "user": { "name": "alice" }, "flags": [], "debug": true });
+        rewrite.rewrite_json(&mut value).unwrap();
+        assert_eq!(
+            value,
+            json!({ "user": { "name": "bob", "role": "admin" }, "flags": ["beta"] })
+        );
+
+        let mut value = json!({ "user": {} });
+        assert!(rewrite.rewrite_json(&mut value).is_err());
+
+        let form = RequestBodyRewrite::default().form(
+            QueryRewrite::default()
+                .remove("csrf")
+                .unwrap()
+                .set("user", "bob smith"),
+        );
+        assert_eq!(
+            form.rewrite_form("user=alice&csrf=x&next=%2F"),
+            "user=bob%20smith&next=%2F"
+        );
+        assert_eq!(form.rewrite_form("csrf=x"), "user=bob%20smith");
+        assert!(RequestBodyRewrite::default().is_empty());
+        assert!(RequestBodyRewrite::default().set("user", json!(1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply() {
+        let rewrite = RequestBodyRewrite::default()
+            .set("$.env", json!("local"))
+            .unwrap();
+        let request = |content_type: &str, body: &'static str| {
+            Request::post("http://api.example.com/")
+                .header("content-type", content_type)
+                .header("content-length", body.len())
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let req = rewrite
+            .apply(request(
+                "application/vnd.api+json; charset=utf-8",
+                r#"{"env":"prod"}"#,
+            ))
+            .await;
+        assert_eq!(req.headers()["content-length"], "15");
+        assert_eq!(body(req).await, r#"{"env":"local"}"#);
+
+        // 无法解析的 JSON 与不匹配的类型原样转发
+        let req = rewrite
+            .apply(request("application/json", "{not json"))
+            .await;
+        assert_eq!(body(req).await, "{not json");
+        let req = rewrite
+            .apply(request("text/plain", r#"{"env":"prod"}"#))
+            .await;
+        assert_eq!(body(req).await, r#"{"env":"prod"}"#);
+
+        // hudsucker 无法解压的编码（区分大小写）原样转发
+        let mut req = request("application/json", r#"{"env":"prod"}"#);
+        req.headers_mut()
+            .insert("content-encoding", "GZIP".parse().unwrap());
+        let req = rewrite.apply(req).await;
+        assert_eq!(req.headers()["content-encoding"], "GZIP");
+        assert_eq!(req.headers()["content-length"], "14");
+        assert_eq!(body(req).await, r#"{"env":"prod"}"#);
+
+        // JSON Patch 失败时整个请求体不改写
+        let failing = rewrite.clone().patch(
+            serde_json::from_value(json!([{ "op": "remove", "path": "/missing" }])).unwrap(),
+        );
+        let req = failing
+            .apply(request("application/json", r#"{"env":"prod"}"#))
+            .await;
+        assert_eq!(body(req).await, r#"{"env":"prod"}"#);
+    }
+}
@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod rule_action_test {
+    use fs_err as fs;
+    use http::{Request, StatusCode, header};
+    use http_body_util::BodyExt;
+    use hudsucker::{AbortConnection, Body};
+    use proxy_fork_core::{
+        AddressPattern, Block, MockResponse, Protocol, Redirect, RequestTemplate,
+    };
+
+    fn template(uri: &str, pattern: &AddressPattern) -> RequestTemplate {
+        let req = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("X-Request-Id", "abc-123")
+            .body(Body::empty())
+            .unwrap();
+        RequestTemplate::new(&req, pattern)
+    }
+
+    async fn body_text(res: http::Response<Body>) -> String {
+        let bytes = res.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_render_request_values() {
+        let pattern = AddressPattern::new(Protocol::Https, "api.example.com", None, None).unwrap();
+        let t = template(
+            "https://api.example.com/users/42/orders?page=2&q=a%20b&flag",
+            &pattern,
+        );
+
+        assert_eq!(
+            t.render("{{method}} {{host}}{{path}}?{{query}}"),
+            "POST api.example.com/users/42/orders?page=2&q=a%20b&flag"
+        );
+        assert_eq!(
+            t.render("{{path.0}}/{{ path.1 }}/{{path.-1}}"),
+            "users/42/orders"
+        );
+        assert_eq!(t.render("[{{path.5}}][{{path.-9}}]"), "[][]");
+        assert_eq!(
+            t.render("{{query.page}}|{{query.q}}|{{query.flag}}|{{query.none}}"),
+            "2|a b||"
+        );
+        assert_eq!(
+            t.render("{{header.x-request-id}}|{{header.X-Request-Id}}|{{header.missing}}"),
+            "abc-123|abc-123|"
+        );
+
+        // 无法识别或未闭合的表达式原样保留
+        assert_eq!(t.render("{{unknown}} {{path.x}}"), "{{unknown}} {{path.x}}");
+        assert_eq!(t.render("tail {{path"), "tail {{path");
+    }
+
+    #[test]
+    fn test_render_regex_captures() {
+        let pattern = AddressPattern::new(
+            Protocol::Https,
+            "api.example.com",
+            None,
+            Some(r"re:^/users/(?P<id>[0-9]+)/(\w+)"),
+        )
+        .unwrap();
+        let t = template("https://api.example.com/users/42/orders", &pattern);
+        assert_eq!(
+            t.render("{{capture.0}}|{{capture.1}}|{{capture.id}}|{{capture.2}}|{{capture.3}}"),
+            "/users/42/orders|42|42|orders|"
+        );
+
+        // 路径不是正则时取主机正则的捕获组
+        let pattern =
+            AddressPattern::new(Protocol::Https, r"re:^(\w+)\.example\.com$", None, None).unwrap();
+        let t = template("https://shop.example.com/", &pattern);
+        assert_eq!(t.render("{{capture.1}}"), "shop");
+    }
+
+    #[tokio::test]
+    async fn test_mock_response() {
+        let pattern =
+            AddressPattern::new(Protocol::Https, "api.example.com", None, Some("/users/*"))
+                .unwrap();
+        let t = template("https://api.example.com/users/42", &pattern);
+
+        let mock = MockResponse::new(201)
+            .header("content-type", "application/json")
+            .header("x-mock-user", "{{path.1}}")
+            .body(r#"{"id": "{{path.1}}", "trace": "{{header.x-request-id}}"}"#);
+        let res = mock.respond(&t).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(res.headers()["x-mock-user"], "42");
+        assert_eq!(body_text(res).await, r#"{"id": "42", "trace": "abc-123"}"#);
+
+        let res = MockResponse::new(204).respond(&t).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(body_text(res).await, "");
+
+        assert_eq!(
+            MockResponse::new(1000).respond(&t).await.status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_body_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("user.json");
+        fs::write(&file, r#"{"id": "{{path.-1}}"}"#).unwrap();
+
+        let pattern = AddressPattern::new(Protocol::Https, "api.example.com", None, None).unwrap();
+        let t = template("https://api.example.com/users/7", &pattern);
+
+        let res = MockResponse::default().body_file(&file).respond(&t).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(body_text(res).await, r#"{"id": "7"}"#);
+
+        // 显式设置的 Content-Type 优先
+        let res = MockResponse::default()
+            .header("Content-Type", "text/plain")
+            .body_file(&file)
+            .respond(&t)
+            .await;
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/plain");
+
+        let res = MockResponse::default()
+            .body_file(dir.path().join("missing.json"))
+            .respond(&t)
+            .await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_redirect() {
+        let pattern =
+            AddressPattern::new(Protocol::Https, "old.example.com", None, Some("/docs/*")).unwrap();
+        let t = template("https://old.example.com/docs/intro?lang=en", &pattern);
+        let target: http::Uri = "https://new.example.com/docs/intro?lang=en"
+            .parse()
+            .unwrap();
+
+        let res = Redirect::default().respond(&t, &target);
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "https://new.example.com/docs/intro?lang=en"
+        );
+
+        let res = Redirect::new(308)
+            .unwrap()
+            .location("https://docs.example.com/{{path.-1}}?from={{host}}")
+            .respond(&t, &target);
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "https://docs.example.com/intro?from=old.example.com"
+        );
+
+        assert!(Redirect::new(200).is_err());
+        let invalid = Redirect {
+            status: 303,
+            location: None,
+        };
+        assert_eq!(
+            invalid.respond(&t, &target).status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn test_block() {
+        let res = Block::default().respond();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert!(res.extensions().get::<AbortConnection>().is_none());
+
+        assert_eq!(
+            Block::new(451).unwrap().respond().status(),
+            StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS
+        );
+        assert!(Block::new(500).is_err());
+
+        let res = Block::reset().respond();
+        assert!(res.extensions().get::<AbortConnection>().is_some());
+    }
+}
@@ -0,0 +1,468 @@
+#![cfg(feature = "serde")]
+
+#[cfg(test)]
+mod serde_test {
+    use proxy_fork_core::{
+        Address, AddressPattern, BodyRewrite, CookieRewrite, FaultInjection, HeaderPreset,
+        HeaderRewrite, HostHeader, MockResponse, NetworkProfile, PathTransformMode, PatternMatcher,
+        Protocol, ProxyRule, ProxyRuleBuilder, QueryRewrite, RequestBodyRewrite, RuleAction,
+        SameSite, Via,
+    };
+
+    #[test]
+    fn test_address_round_trip() {
+        let address = Address {
+            protocol: Protocol::Http,
+            host: "localhost".to_string(),
+            port: Some(8080),
+            path: Some("/local".to_string()),
+            path_transform_mode: PathTransformMode::Prepend,
+        };
+
+        let json = serde_json::to_value(&address).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "protocol": "http",
+                "host": "localhost",
+                "port": 8080,
+                "path": "/local",
+                "path_transform_mode": "prepend"
+            })
+        );
+
+        let back: Address = serde_json::from_value(json).unwrap();
+        assert_eq!(back, address);
+    }
+
+    #[test]
+    fn test_address_optional_fields_default() {
+        let address: Address =
+            serde_json::from_str(r#"{"protocol":"https","host":"example.com"}"#).unwrap();
+        assert_eq!(address.protocol, Protocol::Https);
+        assert_eq!(address.port, None);
+        assert_eq!(address.path, None);
+        assert_eq!(address.path_transform_mode, PathTransformMode::Preserve);
+    }
+
+    #[test]
+    fn test_path_transform_mode_round_trip() {
+        for raw in [
+            "preserve",
+            "prepend",
+            "replace",
+            "strip_prefix",
+            "s#^/v1/(.*)#/api/v2/$1#",
+        ] {
+            let mode: PathTransformMode = serde_json::from_value(raw.into()).unwrap();
+            assert_eq!(serde_json::to_value(&mode).unwrap(), raw);
+        }
+
+        assert!(serde_json::from_str::<PathTransformMode>(r#""s#(#x#""#).is_err());
+    }
+
+    #[test]
+    fn test_pattern_matcher_round_trip() {
+        for raw in [
+            "example.com",
+            "*.example.com",
+            "re:^api\\d+\\.example\\.com$",
+        ] {
+            let matcher: PatternMatcher = serde_json::from_value(raw.into()).unwrap();
+            let json = serde_json::to_value(&matcher).unwrap();
+            assert_eq!(json, raw);
+        }
+
+        let matcher: PatternMatcher = serde_json::from_str(r#""re:^v[0-9]+$""#).unwrap();
+        assert!(matches!(matcher, PatternMatcher::Regex { .. }));
+
+        // 非法正则应在反序列化时报错
+        assert!(serde_json::from_str::<PatternMatcher>(r#""re:(""#).is_err());
+    }
+
+    #[test]
+    fn test_address_pattern_round_trip() {
+        let pattern =
+            AddressPattern::new(Protocol::Https, "*.example.com", Some(8443), Some("/api/*"))
+                .unwrap();
+
+        let json = serde_json::to_value(&pattern).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "protocol": "https",
+                "port": 8443,
+                "host": "*.example.com",
+                "path": "/api/*"
+            })
+        );
+
+        let back: AddressPattern = serde_json::from_value(json).unwrap();
+        assert_eq!(back.to_string(), pattern.to_string());
+
+        // unix 与 file 只能用于目标地址
+        for protocol in ["unix", "file"] {
+            let json = serde_json::json!({ "protocol": protocol, "host": "/run/app.sock" });
+            assert!(serde_json::from_value::<AddressPattern>(json).is_err());
+        }
+    }
+
+    #[test]
+    fn test_proxy_rule_round_trip() {
+        let rule = ProxyRuleBuilder::default()
+            .pattern(
+                AddressPattern::new(Protocol::Https, "re:^api\\.example\\.com$", None, None)
+                    .unwrap(),
+            )
+            .target(Address {
+                protocol: Protocol::Http,
+                host: "127.0.0.1".to_string(),
+                port: Some(9090),
+                path: Some("/v2".to_string()),
+                path_transform_mode: PathTransformMode::Replace,
+            })
+            .group("local-backend")
+            .build()
+            .unwrap();
+
+        let text = serde_json::to_string(&rule).unwrap();
+        let back: ProxyRule = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(back.pattern.to_string(), rule.pattern.to_string());
+        assert_eq!(back.target, rule.target);
+        assert_eq!(back.group, rule.group);
+        assert_eq!(serde_json::to_string(&back).unwrap(), text);
+    }
+
+    #[test]
+    fn test_query_rewrite_round_trip() {
+        let json = serde_json::json!({
+            "remove": ["utm_*"],
+            "rename": { "q": "query" },
+            "set": { "debug": "1" }
+        });
+        let rewrite: QueryRewrite = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(rewrite.rename, vec![("q".to_string(), "query".to_string())]);
+        assert_eq!(serde_json::to_value(&rewrite).unwrap(), json);
+
+        assert!(serde_json::from_str::<QueryRewrite>(r#"{"drop":["a"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_host_header_round_trip() {
+        for (raw, expected) in [
+            ("target", HostHeader::Target),
+            ("preserve", HostHeader::Preserve),
+            (
+                "api.internal:8080",
+                HostHeader::Value("api.internal:8080".into()),
+            ),
+        ] {
+            let header: HostHeader = serde_json::from_value(raw.into()).unwrap();
+            assert_eq!(header, expected);
+            assert_eq!(serde_json::to_value(&header).unwrap(), raw);
+        }
+
+        assert!(serde_json::from_value::<HostHeader>("".into()).is_err());
+    }
+
+    #[test]
+    fn test_via_round_trip() {
+        for raw in ["global", "direct", "http://proxy.internal:3128"] {
+            let via: Via = serde_json::from_value(raw.into()).unwrap();
+            assert_eq!(serde_json::to_value(&via).unwrap(), raw);
+        }
+        assert!(serde_json::from_value::<Via>("ftp://proxy".into()).is_err());
+
+        let rule: ProxyRule = "https://api.example.com -> https://api.example.com [via=direct]"
+            .parse()
+            .unwrap();
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(value["via"], "direct");
+        let back: ProxyRule = serde_json::from_value(value).unwrap();
+        assert_eq!(back.via, Via::Direct);
+    }
+
+    #[test]
+    fn test_mock_action_round_trip() {
+        let json = serde_json::json!({
+            "status": 201,
+            "headers": { "content-type": "application/json" },
+            "body": "{\"id\": \"{{path.1}}\"}"
+        });
+        let mock: MockResponse = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(mock.status, 201);
+        assert_eq!(serde_json::to_value(&mock).unwrap(), json);
+
+        let mock: MockResponse = serde_json::from_str("{}").unwrap();
+        assert_eq!(mock.status, 200);
+        assert!(serde_json::from_str::<MockResponse>(r#"{"code":200}"#).is_err());
+
+        let rule: ProxyRule = "https://api.example.com -> http://localhost [mock=503]"
+            .parse()
+            .unwrap();
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(value["action"]["mock"]["status"], 503);
+        let back: ProxyRule = serde_json::from_value(value).unwrap();
+        assert!(matches!(back.action, RuleAction::Mock(m) if m.status == 503));
+
+        let rule: ProxyRule = "https://api.example.com -> http://localhost"
+            .parse()
+            .unwrap();
+        assert!(serde_json::to_value(&rule).unwrap().get("action").is_none());
+    }
+
+    #[test]
+    fn test_redirect_and_block_round_trip() {
+        let json = serde_json::json!({
+            "redirect": { "status": 301, "location": "https://new.example.com{{path}}" }
+        });
+        let action: RuleAction = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(&action, RuleAction::Redirect(r) if r.status == 301));
+        assert_eq!(serde_json::to_value(&action).unwrap(), json);
+
+        let action: RuleAction =
+            serde_json::from_value(serde_json::json!({ "block": {} })).unwrap();
+        assert!(matches!(&action, RuleAction::Block(b) if b.status == 403 && !b.reset));
+
+        let json = serde_json::json!({ "block": { "status": 403, "reset": true } });
+        let action: RuleAction = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&action).unwrap(), json);
+
+        let action: RuleAction = serde_json::from_value("forward".into()).unwrap();
+        assert!(action.is_forward());
+    }
+
+    #[test]
+    fn test_fault_round_trip() {
+        let json = serde_json::json!({
+            "latency_ms": [500, 2000],
+            "error_rate": 0.1
+        });
+        let fault: FaultInjection = serde_json::from_value(json).unwrap();
+        assert_eq!(fault.latency_ms, Some((500, 2000)));
+        assert_eq!(fault.error_status, 503);
+        assert_eq!(fault.abort_rate, 0.0);
+
+        let mut rule: ProxyRule = "https://api.example.com/* -> http://localhost:8080"
+            .parse()
+            .unwrap();
+        rule.fault = fault.clone();
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(value["fault"]["latency_ms"], serde_json::json!([500, 2000]));
+        let parsed: ProxyRule = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.fault, fault);
+
+        // 未设置故障注入的规则不输出 fault 字段
+        rule.fault = FaultInjection::default();
+        assert!(serde_json::to_value(&rule).unwrap().get("fault").is_none());
+
+        assert!(
+            serde_json::from_value::<FaultInjection>(serde_json::json!({ "latency": 5 })).is_err()
+        );
+    }
+
+    #[test]
+    fn test_throttle_round_trip() {
+        let rule: ProxyRule = "https://api.example.com/* -> http://localhost:8080 [throttle=3g]"
+            .parse()
+            .unwrap();
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(value["throttle"], "3g");
+        let parsed: ProxyRule = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.throttle, NetworkProfile::preset("3g"));
+
+        let profile: NetworkProfile = serde_json::from_value("1000/500/150".into()).unwrap();
+        assert_eq!(profile, NetworkProfile::new(1000, 500, 150));
+        assert!(serde_json::from_value::<NetworkProfile>("5g".into()).is_err());
+    }
+
+    #[test]
+    fn test_request_headers_round_trip() {
+        let json = serde_json::json!({
+            "remove": ["If-None-Match", "x-debug-*"],
+            "replace": { "Cookie": "s#session=[^;]+#session=dev#" },
+            "set": { "Authorization": "Bearer dev" },
+            "append": { "X-Feature": "beta" }
+        });
+        let rewrite: HeaderRewrite = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(
+            rewrite.set,
+            vec![("Authorization".into(), "Bearer dev".into())]
+        );
+        assert_eq!(rewrite.replace[0].1.pattern(), "session=[^;]+");
+        assert_eq!(serde_json::to_value(&rewrite).unwrap(), json);
+
+        let mut rule: ProxyRule = "https://api.example.com/* -> http://localhost:8080"
+            .parse()
+            .unwrap();
+        assert!(
+            serde_json::to_value(&rule)
+                .unwrap()
+                .get("request_headers")
+                .is_none()
+        );
+        rule.request_headers = rewrite;
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(value["request_headers"], json);
+        let parsed: ProxyRule = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.request_headers.append.len(), 1);
+
+        assert!(
+            serde_json::from_value::<HeaderRewrite>(
+                serde_json::json!({ "replace": { "Cookie": "x" } })
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_response_headers_round_trip() {
+        let mut rule: ProxyRule = "https://api.example.com/* -> http://localhost:8080 \
+            [cors=http://localhost:3000, strip-csp, no-cache]"
+            .parse()
+            .unwrap();
+        rule.response_headers = HeaderRewrite::default().set("X-Proxied", "1");
+
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(
+            value["response_presets"],
+            serde_json::json!(["cors=http://localhost:3000", "strip-csp", "no-cache"])
+        );
+        assert_eq!(value["response_headers"]["set"]["X-Proxied"], "1");
+        let parsed: ProxyRule = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            parsed.response_presets,
+            vec![
+                HeaderPreset::Cors(Some("http://localhost:3000".into())),
+                HeaderPreset::StripCsp,
+                HeaderPreset::NoCache,
+            ]
+        );
+        assert_eq!(parsed.response_headers.set.len(), 1);
+
+        assert!(serde_json::from_value::<HeaderPreset>("allow-all".into()).is_err());
+    }
+
+    #[test]
+    fn test_response_body_round_trip() {
+        let rewrite: BodyRewrite = serde_json::from_value(serde_json::json!({
+            "replace": { "https://cdn.example.com": "http://localhost:3000" },
+            "regex": ["s#app\\.[0-9a-f]+\\.js#app.js#"],
+            "inject_head": "<script src=\"/dev.js\"></script>",
+            "content_types": ["text/html"],
+            "max_size": 1024
+        }))
+        .unwrap();
+        assert_eq!(rewrite.replace[0].1, "http://localhost:3000");
+        assert_eq!(rewrite.regex[0].replacement(), "app.js");
+        assert_eq!(rewrite.max_size, 1024);
+
+        // 未指定时使用默认的 Content-Type 列表与大小上限
+        let defaults: BodyRewrite =
+            serde_json::from_value(serde_json::json!({ "inject_body": "<p>dev</p>" })).unwrap();
+        assert_eq!(
+            defaults.content_types.len(),
+            BodyRewrite::default().content_types.len()
+        );
+        assert_eq!(defaults.max_size, BodyRewrite::default().max_size);
+
+        let mut rule: ProxyRule = "https://www.example.com/* -> https://www.example.com"
+            .parse()
+            .unwrap();
+        assert!(
+            serde_json::to_value(&rule)
+                .unwrap()
+                .get("response_body")
+                .is_none()
+        );
+        rule.response_body = rewrite;
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(
+            value["response_body"]["content_types"],
+            serde_json::json!(["text/html"])
+        );
+        let parsed: ProxyRule = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            parsed.response_body.inject_head.as_deref(),
+            Some("<script src=\"/dev.js\"></script>")
+        );
+
+        assert!(
+            serde_json::from_value::<BodyRewrite>(serde_json::json!({ "inject": "x" })).is_err()
+        );
+    }
+
+    #[test]
+    fn test_request_body_round_trip() {
+        let json = serde_json::json!({
+            "remove": ["$.debug"],
+            "replace": { "$.items[*].price": 0 },
+            "set": { "$.user.role": "admin", "$.flags": { "beta": true } },
+            "patch": [{ "op": "add", "path": "/tags/-", "value": "dev" }],
+            "form": { "set": { "debug": "1" } },
+            "max_size": 4096
+        });
+        let rewrite: RequestBodyRewrite = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(rewrite.set.len(), 2);
+        assert_eq!(rewrite.set[0].0.to_string(), "$.flags");
+        assert_eq!(rewrite.set[0].1, serde_json::json!({ "beta": true }));
+        assert_eq!(rewrite.patch.as_ref().unwrap().0.len(), 1);
+        assert_eq!(serde_json::to_value(&rewrite).unwrap(), json);
+
+        let mut rule: ProxyRule = "https://api.example.com/* -> http://localhost:8080"
+            .parse()
+            .unwrap();
+        assert!(
+            serde_json::to_value(&rule)
+                .unwrap()
+                .get("request_body")
+                .is_none()
+        );
+        rule.request_body = rewrite;
+        let parsed: ProxyRule =
+            serde_json::from_value(serde_json::to_value(&rule).unwrap()).unwrap();
+        assert_eq!(parsed.request_body.remove[0].to_string(), "$.debug");
+
+        // JSONPath 在解析配置时校验
+        assert!(
+            serde_json::from_value::<RequestBodyRewrite>(
+                serde_json::json!({ "remove": ["debug"] })
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cookie_rewrite_round_trip() {
+        let json = serde_json::json!({ "auto": false, "domain": "", "same_site": "Lax" });
+        let rewrite: CookieRewrite = serde_json::from_value(json.clone()).unwrap();
+        assert!(!rewrite.auto);
+        assert_eq!(rewrite.domain.as_deref(), Some(""));
+        assert_eq!(rewrite.same_site, Some(SameSite::Lax));
+        assert_eq!(serde_json::to_value(&rewrite).unwrap(), json);
+
+        // 默认开启自动改写，不序列化
+        let mut rule: ProxyRule = "https://app.example.com/* -> http://localhost:3000"
+            .parse()
+            .unwrap();
+        assert!(rule.cookies.auto);
+        assert!(
+            serde_json::to_value(&rule)
+                .unwrap()
+                .get("cookies")
+                .is_none()
+        );
+        rule.cookies = rewrite;
+        let parsed: ProxyRule =
+            serde_json::from_value(serde_json::to_value(&rule).unwrap()).unwrap();
+        assert_eq!(parsed.cookies, rule.cookies);
+
+        assert!(
+            serde_json::from_value::<CookieRewrite>(
+                serde_json::json!({ "same_site": "Sometimes" })
+            )
+            .is_err()
+        );
+    }
+}